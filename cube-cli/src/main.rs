@@ -1,8 +1,104 @@
+use std::fs;
+use std::io::{self, Read};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use cube_core::puzzle::apply;
 use cube_core::{
-    apply_alg, commutator, conjugate, corner_cycles, edge_cycles, invert_alg, Cube, Move,
+    alg_equivalent, alg_order, analyze, apply_alg, commutator, conjugate, corner_cycles,
+    edge_cycles, invert_alg, orbit_cycles, parse_alg, parse_solution, three_by_three_def, Cube,
+    DefPuzzle, EquivOptions, Move, Puzzle, Reconstruction, SolverTables,
 };
 
-fn main() {
+#[derive(Parser)]
+#[command(name = "cube-cli", about = "Rubik's Cube Playground CLI")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the feature demo (default)
+    Demo,
+    /// Check whether two algorithms have the same effect
+    Equiv {
+        /// First algorithm, e.g. "R U R' U'"
+        a: String,
+        /// Second algorithm
+        b: String,
+        /// Allow both a pre and a post U-layer adjustment
+        #[arg(long)]
+        auf: bool,
+        /// Allow a U-layer adjustment before the first algorithm
+        #[arg(long)]
+        pre_auf: bool,
+        /// Allow a U-layer adjustment after the first algorithm
+        #[arg(long)]
+        post_auf: bool,
+        /// Allow a whole-cube rotation
+        #[arg(long)]
+        symmetry: bool,
+    },
+    /// Split a CFOP reconstruction into stages
+    Recon {
+        /// Scramble sequence
+        #[arg(long)]
+        scramble: String,
+        /// File containing the solution (`-` for stdin); `//` starts a comment
+        solution: String,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Build the two-phase solver pruning tables and write them to a file
+    Tables {
+        /// Output path (load it in the browser with `SolverTables.fromBytes`)
+        out: String,
+    },
+    /// Load a KSolve-style puzzle definition file and describe it
+    Def {
+        /// Definition file (omit to print the built-in 3x3x3 definition)
+        path: Option<String>,
+        /// Report the order and cycles of this algorithm on the loaded puzzle
+        #[arg(long)]
+        alg: Option<String>,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match cli.command.unwrap_or(Command::Demo) {
+        Command::Demo => {
+            run_demo();
+            ExitCode::SUCCESS
+        }
+        Command::Equiv {
+            a,
+            b,
+            auf,
+            pre_auf,
+            post_auf,
+            symmetry,
+        } => {
+            let options = EquivOptions {
+                pre_auf: auf || pre_auf,
+                post_auf: auf || post_auf,
+                symmetry,
+            };
+            run_equiv(&a, &b, &options)
+        }
+        Command::Recon {
+            scramble,
+            solution,
+            json,
+        } => run_recon(&scramble, &solution, json),
+        Command::Tables { out } => run_tables(&out),
+        Command::Def { path, alg } => run_def(path.as_deref(), alg.as_deref()),
+    }
+}
+
+fn run_demo() {
     println!("🧊 Rubik's Cube Playground - Demo\n");
     println!("{}", "=".repeat(50));
 
@@ -16,6 +112,157 @@ fn main() {
     println!("✅ All demos completed!");
 }
 
+fn run_equiv(a: &str, b: &str, options: &EquivOptions) -> ExitCode {
+    let (a, b) = match (parse_alg(a), parse_alg(b)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    match alg_equivalent(&a, &b, options) {
+        Some(eq) => {
+            println!("equivalent");
+            if let Some(m) = eq.pre_auf {
+                println!("  pre-AUF:  {}", m);
+            }
+            if let Some(m) = eq.post_auf {
+                println!("  post-AUF: {}", m);
+            }
+            if !eq.rotation.is_identity() {
+                println!("  rotation: {}", eq.rotation);
+            }
+            ExitCode::SUCCESS
+        }
+        None => {
+            println!("not equivalent");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_recon(scramble: &str, solution_path: &str, json: bool) -> ExitCode {
+    let text = if solution_path == "-" {
+        let mut buf = String::new();
+        io::stdin().read_to_string(&mut buf).map(|_| buf)
+    } else {
+        fs::read_to_string(solution_path)
+    };
+    let text = match text {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("error: {}: {}", solution_path, e);
+            return ExitCode::from(2);
+        }
+    };
+    let (scramble, solution) = match (parse_alg(scramble), parse_solution(&text)) {
+        (Ok(s), Ok(sol)) => (s, sol),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let report = analyze(&scramble, &solution);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        print_recon(&report);
+    }
+    ExitCode::SUCCESS
+}
+
+fn run_tables(out: &str) -> ExitCode {
+    let bytes = SolverTables::build().to_bytes();
+    match fs::write(out, &bytes) {
+        Ok(()) => {
+            println!("wrote {} bytes to {}", bytes.len(), out);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}: {}", out, e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run_def(path: Option<&str>, alg: Option<&str>) -> ExitCode {
+    let Some(path) = path else {
+        print!("{}", three_by_three_def());
+        return ExitCode::SUCCESS;
+    };
+    let puzzle = match fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| DefPuzzle::parse(&text).map_err(|e| e.to_string()))
+    {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("error: {}: {}", path, e);
+            return ExitCode::from(2);
+        }
+    };
+
+    println!("Puzzle: {}", puzzle.name());
+    for orbit in puzzle.orbits() {
+        println!(
+            "  set {}: {} pieces, {} orientations",
+            orbit.name, orbit.pieces, orbit.orientations
+        );
+    }
+    for (i, name) in puzzle.move_names().iter().enumerate() {
+        println!("  move {} (order {})", name, puzzle.move_order(i));
+    }
+
+    if let Some(alg) = alg {
+        let moves = match puzzle.parse_alg(alg) {
+            Ok(m) => m,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::from(2);
+            }
+        };
+        println!("Algorithm: {}", puzzle.format_alg(&moves));
+        println!("  order: {}", alg_order(&puzzle, &moves));
+        let state = apply(&puzzle, &puzzle.identity(), &moves);
+        for (i, orbit) in puzzle.orbits().iter().enumerate() {
+            println!(
+                "  {} cycles: {:?}",
+                orbit.name,
+                orbit_cycles(&puzzle, &state, i)
+            );
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_recon(report: &Reconstruction) {
+    match report.cross_face {
+        Some(face) => println!("Cross face: {:?}", face),
+        None => println!("Cross face: none"),
+    }
+    for split in &report.splits {
+        let case = match split.stage.as_str() {
+            "OLL" => report.oll_case.as_deref(),
+            "PLL" => report.pll_case.as_deref(),
+            _ => None,
+        };
+        let label = match case {
+            Some(name) => format!("{} ({})", split.stage, name),
+            None => split.stage.clone(),
+        };
+        println!(
+            "  {:<20} {:>3} moves  [{}..{})",
+            label, split.moves, split.start, split.end
+        );
+    }
+    println!("Total: {} moves", report.total_moves);
+    println!("Solved: {}", report.solved);
+    for c in &report.comments {
+        println!("  // {} [{}..{})", c.text, c.start, c.end);
+    }
+}
+
 fn demo_basic_moves() {
     println!("\n Demo 1: Basic Moves");
     println!("{}", "-".repeat(50));
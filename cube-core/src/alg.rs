@@ -1,3 +1,4 @@
+use crate::puzzle::{self, ThreeByThree};
 use crate::{Cube, Move};
 
 pub fn apply_alg(cube: &Cube, alg: &[Move]) -> Cube {
@@ -35,22 +36,13 @@ impl Move {
 }
 
 pub fn invert_alg(alg: &[Move]) -> Vec<Move> {
-    alg.iter().rev().map(|m| m.inverse()).collect()
+    puzzle::invert(&ThreeByThree, alg)
 }
 
 pub fn commutator(a: &[Move], b: &[Move]) -> Vec<Move> {
-    let mut res = Vec::new();
-    res.extend_from_slice(a);
-    res.extend_from_slice(b);
-    res.extend(invert_alg(a));
-    res.extend(invert_alg(b));
-    res
+    puzzle::commutator(&ThreeByThree, a, b)
 }
 
 pub fn conjugate(a: &[Move], b: &[Move]) -> Vec<Move> {
-    let mut res = Vec::new();
-    res.extend_from_slice(b);
-    res.extend_from_slice(a);
-    res.extend(invert_alg(b));
-    res
+    puzzle::conjugate(&ThreeByThree, a, b)
 }
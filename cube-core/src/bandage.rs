@@ -0,0 +1,254 @@
+//! bandage.rs: バンデージキューブ（パーツどうしを固定した 3x3x3）
+//!
+//! 隣り合うパーツの組（結合）を並べたものを `Bandage` とする. 結合した 2 つのパーツの
+//! 一方だけが回る層に入る面は回せない. パーツは揃った状態での位置で指し,
+//! 状態は `Cube` のまま持つので, どの面が回せるかは状態ごとに変わる.
+//!
+//! 記法は `UFR-UF-U DB-DBL` のように, 空白で区切った塊の中のパーツを `-` でつなぐ.
+
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use crate::facelet::{CORNER_FACELETS, EDGE_FACELETS};
+use crate::geometry::{normal, position};
+use crate::state::{CORNER_NAMES, EDGE_NAMES};
+use crate::{Cube, Face, Move};
+
+/// 結合に使うパーツ（揃った状態での位置の番号）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BandagePiece {
+    Corner(u8),
+    Edge(u8),
+    Center(Face),
+}
+
+impl BandagePiece {
+    /// `UFR`, `UF`, `U` のような名前. 文字の順は問わない
+    pub fn parse(name: &str) -> Option<BandagePiece> {
+        let key = sorted(name);
+        if let Some(i) = CORNER_NAMES.iter().position(|n| sorted(n) == key) {
+            return Some(BandagePiece::Corner(i as u8));
+        }
+        if let Some(i) = EDGE_NAMES.iter().position(|n| sorted(n) == key) {
+            return Some(BandagePiece::Edge(i as u8));
+        }
+        Face::ALL
+            .iter()
+            .find(|f| format!("{:?}", f) == name)
+            .map(|&f| BandagePiece::Center(f))
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            BandagePiece::Corner(i) => CORNER_NAMES[i as usize].to_string(),
+            BandagePiece::Edge(i) => EDGE_NAMES[i as usize].to_string(),
+            BandagePiece::Center(f) => format!("{:?}", f),
+        }
+    }
+
+    /// 状態 cube でのこのパーツの座標（各成分 -1, 0, 1）
+    pub fn position_in(self, cube: &Cube) -> [i8; 3] {
+        match self {
+            BandagePiece::Corner(i) => {
+                let slot = cube.corner_perm.iter().position(|&p| p == i).unwrap();
+                position(&CORNER_FACELETS[slot])
+            }
+            BandagePiece::Edge(i) => {
+                let slot = cube.edge_perm.iter().position(|&p| p == i).unwrap();
+                position(&EDGE_FACELETS[slot])
+            }
+            BandagePiece::Center(f) => normal(f),
+        }
+    }
+}
+
+fn sorted(name: &str) -> Vec<char> {
+    let mut chars: Vec<char> = name.chars().collect();
+    chars.sort_unstable();
+    chars
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum BandageError {
+    #[error("unknown piece `{0}`")]
+    UnknownPiece(String),
+    #[error("`{0}` and `{1}` are not adjacent")]
+    NotAdjacent(String, String),
+    #[error("{mv} is blocked by the bond `{a}-{b}`")]
+    Blocked { mv: Move, a: String, b: String },
+    #[error("more than {limit} states are reachable")]
+    TooManyStates { limit: usize },
+}
+
+/// 結合の集まり
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Bandage {
+    bonds: Vec<(BandagePiece, BandagePiece)>,
+}
+
+/// 到達できる状態のグラフを幅優先探索した結果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BandageGraph {
+    /// 揃った状態からの手数ごとの状態の数
+    pub depth_counts: Vec<usize>,
+}
+
+impl BandageGraph {
+    /// 到達できる状態の数
+    pub fn len(&self) -> usize {
+        self.depth_counts.iter().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.depth_counts.is_empty()
+    }
+
+    /// 最長の最短手数
+    pub fn diameter(&self) -> usize {
+        self.depth_counts.len() - 1
+    }
+}
+
+impl Bandage {
+    /// 結合を並べて作る. 揃った状態で隣り合わない組はエラー
+    pub fn new(bonds: Vec<(BandagePiece, BandagePiece)>) -> Result<Self, BandageError> {
+        let id = Cube::identity();
+        for &(a, b) in &bonds {
+            let (pa, pb) = (a.position_in(&id), b.position_in(&id));
+            let dist: i8 = (0..3).map(|i| (pa[i] - pb[i]).abs()).sum();
+            if dist != 1 {
+                return Err(BandageError::NotAdjacent(a.name(), b.name()));
+            }
+        }
+        Ok(Bandage { bonds })
+    }
+
+    /// `UFR-UF-U DB-DBL` のような記法から作る
+    pub fn parse(s: &str) -> Result<Self, BandageError> {
+        let mut bonds = Vec::new();
+        for block in s.split_whitespace() {
+            let pieces = block
+                .split('-')
+                .map(|name| {
+                    BandagePiece::parse(name)
+                        .ok_or_else(|| BandageError::UnknownPiece(name.to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            bonds.extend(pieces.windows(2).map(|w| (w[0], w[1])));
+        }
+        Self::new(bonds)
+    }
+
+    pub fn bonds(&self) -> &[(BandagePiece, BandagePiece)] {
+        &self.bonds
+    }
+
+    // 面 face を回すと切れる結合
+    fn blocking_bond(&self, cube: &Cube, face: Face) -> Option<(BandagePiece, BandagePiece)> {
+        let n = normal(face);
+        let in_layer = |p: BandagePiece| {
+            let pos = p.position_in(cube);
+            (0..3).map(|i| pos[i] * n[i]).sum::<i8>() == 1
+        };
+        self.bonds
+            .iter()
+            .copied()
+            .find(|&(a, b)| in_layer(a) != in_layer(b))
+    }
+
+    /// 状態 cube で回せる手
+    pub fn legal_moves(&self, cube: &Cube) -> Vec<Move> {
+        let open: Vec<Face> = Face::ALL
+            .into_iter()
+            .filter(|&f| self.blocking_bond(cube, f).is_none())
+            .collect();
+        Move::ALL
+            .into_iter()
+            .filter(|m| open.contains(&m.face()))
+            .collect()
+    }
+
+    /// 手 m が回せるか確かめる
+    pub fn check(&self, cube: &Cube, m: Move) -> Result<(), BandageError> {
+        match self.blocking_bond(cube, m.face()) {
+            Some((a, b)) => Err(BandageError::Blocked {
+                mv: m,
+                a: a.name(),
+                b: b.name(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// 揃った状態から回せる手だけで到達できる状態を幅優先で数える.
+    /// limit を超えたらエラー
+    pub fn explore(&self, limit: usize) -> Result<BandageGraph, BandageError> {
+        let start = Cube::identity();
+        let mut seen = HashSet::from([start]);
+        let mut depth_counts = vec![1];
+        let mut frontier = vec![start];
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+            for cube in &frontier {
+                for m in self.legal_moves(cube) {
+                    let mut c = *cube;
+                    c.apply_move(m);
+                    if seen.insert(c) {
+                        if seen.len() > limit {
+                            return Err(BandageError::TooManyStates { limit });
+                        }
+                        next.push(c);
+                    }
+                }
+            }
+            if !next.is_empty() {
+                depth_counts.push(next.len());
+            }
+            frontier = next;
+        }
+        Ok(BandageGraph { depth_counts })
+    }
+}
+
+/// 結合つきの状態. 回せない手はエラーになる
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BandagedCube {
+    pub bandage: Bandage,
+    pub cube: Cube,
+}
+
+impl BandagedCube {
+    /// 揃った状態
+    pub fn new(bandage: Bandage) -> Self {
+        BandagedCube {
+            bandage,
+            cube: Cube::identity(),
+        }
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.bandage.legal_moves(&self.cube)
+    }
+
+    pub fn apply_move(&mut self, m: Move) -> Result<(), BandageError> {
+        self.bandage.check(&self.cube, m)?;
+        self.cube.apply_move(m);
+        Ok(())
+    }
+
+    /// 手順を順に適用する. 途中で回せない手があれば, 状態は変えずにエラー
+    pub fn apply_alg(&mut self, alg: &[Move]) -> Result<(), BandageError> {
+        let mut cube = self.cube;
+        for &m in alg {
+            self.bandage.check(&cube, m)?;
+            cube.apply_move(m);
+        }
+        self.cube = cube;
+        Ok(())
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.cube.is_solved()
+    }
+}
@@ -0,0 +1,460 @@
+//! beginner.rs: 初心者向け LBL（層ごと）解法
+//!
+//! 最適解ではなく, 教えるための手順を出力する. クロス → 1 段目の角 → 2 段目の辺 →
+//! 上面クロス → 上面の角の向き (OLL) → 角の位置 → 辺の位置 (PLL) の順に,
+//! 名前の付いた手順の組み合わせで 1 パーツずつ揃える.
+
+use std::sync::OnceLock;
+
+use crate::facelet::CORNER_FACELETS;
+use crate::moves::Face;
+use crate::recognition::is_f2l_solved;
+use crate::solver::SolveError;
+use crate::state::{CORNER_NAMES, EDGE_NAMES};
+use crate::symmetry::Rotation;
+use crate::{apply_alg, parse_alg, Cube, Move};
+
+/// 解法の 1 ステップ
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolveStep {
+    /// 段階の名前 ("cross", "first layer", ...)
+    pub stage: &'static str,
+    /// このステップの手順
+    pub moves: Vec<Move>,
+    /// 学習者向けの説明
+    pub explanation: String,
+}
+
+const SEXY: &str = "R U R' U'";
+const RIGHT_INSERT: &str = "U R U' R' U' F' U F";
+const LEFT_INSERT: &str = "U' L' U L U F U' F'";
+const EDGE_FLIP: &str = "F R U R' U' F'";
+const SUNE: &str = "R U R' U R U2 R'";
+const A_PERM: &str = "R' F R' B2 R F' R' B2 R2";
+const U_PERM: &str = "R U' R U R U R U' R' U' R2";
+
+const AUFS: [Option<Move>; 4] = [None, Some(Move::U), Some(Move::U2), Some(Move::Up)];
+
+// 名前付きの手順（前に AUF を含む）
+struct Macro {
+    label: String,
+    moves: Vec<Move>,
+}
+
+fn alg(s: &str) -> Vec<Move> {
+    parse_alg(s).unwrap()
+}
+
+fn auf_label(auf: Option<Move>) -> String {
+    auf.map_or(String::new(), |m| format!("{}, ", m))
+}
+
+// 各 AUF の後に alg を行う手順の一覧
+fn with_aufs(name: &str, moves: &[Move]) -> Vec<Macro> {
+    AUFS.iter()
+        .map(|&auf| {
+            let mut m: Vec<Move> = auf.into_iter().collect();
+            m.extend_from_slice(moves);
+            Macro {
+                label: format!("{}{}", auf_label(auf), name),
+                moves: m,
+            }
+        })
+        .collect()
+}
+
+// 反復深化で macro の列を探す. keep は途中の各手順の後でも保たれている必要がある
+fn search_macros(
+    cube: &Cube,
+    macros: &[Macro],
+    max_depth: usize,
+    goal: &dyn Fn(&Cube) -> bool,
+    keep: &dyn Fn(&Cube) -> bool,
+) -> Option<Vec<usize>> {
+    fn dfs(
+        cube: &Cube,
+        macros: &[Macro],
+        depth: usize,
+        path: &mut Vec<usize>,
+        goal: &dyn Fn(&Cube) -> bool,
+        keep: &dyn Fn(&Cube) -> bool,
+    ) -> bool {
+        if goal(cube) {
+            return true;
+        }
+        if depth == 0 {
+            return false;
+        }
+        for (i, m) in macros.iter().enumerate() {
+            let next = apply_alg(cube, &m.moves);
+            if !keep(&next) {
+                continue;
+            }
+            path.push(i);
+            if dfs(&next, macros, depth - 1, path, goal, keep) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    (0..=max_depth).find_map(|depth| {
+        let mut path = Vec::new();
+        dfs(cube, macros, depth, &mut path, goal, keep).then_some(path)
+    })
+}
+
+// macro の列で goal まで進め, そのステップを返す（すでに goal なら None）
+fn macro_step(
+    cube: &mut Cube,
+    stage: &'static str,
+    intro: &str,
+    macros: &[Macro],
+    max_depth: usize,
+    goal: &dyn Fn(&Cube) -> bool,
+    keep: &dyn Fn(&Cube) -> bool,
+) -> Result<Option<SolveStep>, SolveError> {
+    let path =
+        search_macros(cube, macros, max_depth, goal, keep).ok_or(SolveError::Stuck(stage))?;
+    if path.is_empty() {
+        return Ok(None);
+    }
+    let moves: Vec<Move> = path.iter().flat_map(|&i| macros[i].moves.clone()).collect();
+    let labels: Vec<&str> = path.iter().map(|&i| macros[i].label.as_str()).collect();
+    *cube = apply_alg(cube, &moves);
+    Ok(Some(SolveStep {
+        stage,
+        moves,
+        explanation: format!("{}: {}", intro, labels.join(", then ")),
+    }))
+}
+
+fn edge_solved(cube: &Cube, i: usize) -> bool {
+    cube.edge_perm[i] == i as u8 && cube.edge_ori[i] == 0
+}
+
+fn corner_solved(cube: &Cube, i: usize) -> bool {
+    cube.corner_perm[i] == i as u8 && cube.corner_ori[i] == 0
+}
+
+fn first_layer_solved(cube: &Cube) -> bool {
+    (4..8).all(|i| corner_solved(cube, i)) && (8..12).all(|i| edge_solved(cube, i))
+}
+
+fn last_layer_oriented(cube: &Cube) -> bool {
+    cube.corner_ori[..4] == [0; 4] && cube.edge_ori[..4] == [0; 4]
+}
+
+fn up_to_auf(cube: &Cube, pred: impl Fn(&Cube) -> bool) -> bool {
+    let mut c = *cube;
+    (0..4).any(|_| {
+        c.apply_move(Move::U);
+        pred(&c)
+    })
+}
+
+/// D 面（1 段目）を下にした LBL 解法
+pub fn solve_beginner(cube: &Cube) -> Result<Vec<SolveStep>, SolveError> {
+    if !cube.is_legal() {
+        return Err(SolveError::Illegal);
+    }
+    let mut cube = *cube;
+    let mut steps = Vec::new();
+
+    solve_cross(&mut cube, &mut steps);
+    solve_first_layer_corners(&mut cube, &mut steps)?;
+    solve_middle_edges(&mut cube, &mut steps)?;
+
+    steps.extend(macro_step(
+        &mut cube,
+        "yellow cross",
+        "Orient the top edges into a cross",
+        &with_aufs(EDGE_FLIP, &alg(EDGE_FLIP)),
+        3,
+        &|c| c.edge_ori[..4] == [0; 4],
+        &is_f2l_solved,
+    )?);
+    steps.extend(macro_step(
+        &mut cube,
+        "OLL",
+        "Orient the top corners with Sune",
+        &with_aufs(SUNE, &alg(SUNE)),
+        4,
+        &last_layer_oriented,
+        &|c| is_f2l_solved(c) && c.edge_ori[..4] == [0; 4],
+    )?);
+    steps.extend(macro_step(
+        &mut cube,
+        "PLL corners",
+        "Permute the top corners with the A-perm",
+        &with_aufs(A_PERM, &alg(A_PERM)),
+        2,
+        &|c| up_to_auf(c, |c| c.corner_perm[..4] == [0, 1, 2, 3]),
+        &|c| is_f2l_solved(c) && last_layer_oriented(c),
+    )?);
+    steps.extend(macro_step(
+        &mut cube,
+        "PLL edges",
+        "Permute the top edges with the U-perm",
+        &with_aufs(U_PERM, &alg(U_PERM)),
+        3,
+        &|c| up_to_auf(c, Cube::is_solved),
+        &|c| is_f2l_solved(c) && last_layer_oriented(c),
+    )?);
+    if let Some(auf) = AUFS.into_iter().flatten().find(|&m| {
+        let mut c = cube;
+        c.apply_move(m);
+        c.is_solved()
+    }) {
+        steps.push(SolveStep {
+            stage: "AUF",
+            moves: vec![auf],
+            explanation: "Turn the top layer to finish".to_string(),
+        });
+    }
+
+    Ok(steps)
+}
+
+// 辺の位置と向きをまとめた 0..24 の値
+fn edge_slot(cube: &Cube, piece: u8) -> usize {
+    let pos = cube.edge_perm.iter().position(|&p| p == piece).unwrap();
+    pos * 2 + cube.edge_ori[pos] as usize
+}
+
+// クロスの辺 (DF, DR, DB, DL)
+const CROSS_EDGES: [u8; 4] = [8, 9, 10, 11];
+
+struct CrossTables {
+    // 各手で辺スロットがどこへ移るか
+    moves: Vec<[usize; 24]>,
+    // dist[k - 1]: 最初の k 本の辺のスロットの組 → 揃うまでの手数
+    dist: Vec<Vec<u8>>,
+}
+
+// 辺スロットの組を 24 進数で 1 つの番号にする
+fn encode_slots(slots: &[usize]) -> usize {
+    slots.iter().fold(0, |acc, &s| acc * 24 + s)
+}
+
+fn move_slots(t: &[usize; 24], slots: &[usize]) -> Vec<usize> {
+    slots.iter().map(|&s| t[s]).collect()
+}
+
+fn cross_tables() -> &'static CrossTables {
+    static TABLES: OnceLock<CrossTables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let moves: Vec<[usize; 24]> = Move::ALL
+            .iter()
+            .map(|&m| {
+                let mut c = Cube::identity();
+                c.apply_move(m);
+                let mut t = [0; 24];
+                for piece in 0..12u8 {
+                    let to = edge_slot(&c, piece);
+                    for o in 0..2 {
+                        t[piece as usize * 2 + o] = (to & !1) | ((to & 1) ^ o);
+                    }
+                }
+                t
+            })
+            .collect();
+
+        // ゴールからの幅優先探索
+        let dist = (1..=CROSS_EDGES.len())
+            .map(|k| {
+                let goal: Vec<usize> = CROSS_EDGES[..k].iter().map(|&p| p as usize * 2).collect();
+                let mut dist = vec![u8::MAX; 24usize.pow(k as u32)];
+                dist[encode_slots(&goal)] = 0;
+                let mut frontier = vec![goal];
+                let mut d = 0;
+                while !frontier.is_empty() {
+                    let mut next = Vec::new();
+                    for slots in &frontier {
+                        for t in &moves {
+                            let moved = move_slots(t, slots);
+                            let idx = encode_slots(&moved);
+                            if dist[idx] == u8::MAX {
+                                dist[idx] = d + 1;
+                                next.push(moved);
+                            }
+                        }
+                    }
+                    frontier = next;
+                    d += 1;
+                }
+                dist
+            })
+            .collect();
+        CrossTables { moves, dist }
+    })
+}
+
+// クロスの辺を 1 本ずつ, それまでの辺を崩さない最短手順で揃える
+fn solve_cross(cube: &mut Cube, steps: &mut Vec<SolveStep>) {
+    let tables = cross_tables();
+    for k in 1..=CROSS_EDGES.len() {
+        let dist = &tables.dist[k - 1];
+
+        // 距離が減る手を貪欲に選ぶ
+        let mut slots: Vec<usize> = CROSS_EDGES[..k]
+            .iter()
+            .map(|&p| edge_slot(cube, p))
+            .collect();
+        let mut moves = Vec::new();
+        while dist[encode_slots(&slots)] > 0 {
+            let here = dist[encode_slots(&slots)];
+            let (i, moved) = tables
+                .moves
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (i, move_slots(t, &slots)))
+                .find(|(_, moved)| dist[encode_slots(moved)] < here)
+                .unwrap();
+            moves.push(Move::ALL[i]);
+            slots = moved;
+        }
+        if moves.is_empty() {
+            continue;
+        }
+        *cube = apply_alg(cube, &moves);
+        let piece = CROSS_EDGES[k - 1] as usize;
+        steps.push(SolveStep {
+            stage: "cross",
+            moves,
+            explanation: format!(
+                "Bring the {} edge down next to its centers, keeping the solved cross edges",
+                EDGE_NAMES[piece]
+            ),
+        });
+    }
+}
+
+// D 面の角 slot を DFR に見立てる持ち替え（U 軸まわり）
+fn slot_frame(slot: usize) -> Rotation {
+    let [_, a, b] = CORNER_FACELETS[slot];
+    Rotation::all()
+        .into_iter()
+        .find(|r| {
+            r.apply_face(Face::U) == Face::U
+                && r.apply_face(Face::F) == a
+                && r.apply_face(Face::R) == b
+        })
+        .unwrap()
+}
+
+// 1 段目の角: 取り出す → 真上に合わせる → R U R' U' を揃うまで繰り返す
+fn solve_first_layer_corners(
+    cube: &mut Cube,
+    steps: &mut Vec<SolveStep>,
+) -> Result<(), SolveError> {
+    let sexy = alg(SEXY);
+    for target in [5usize, 4, 7, 6] {
+        if corner_solved(cube, target) {
+            continue;
+        }
+        let mut moves = Vec::new();
+        let mut parts = Vec::new();
+        let mut c = *cube;
+
+        let pos = c
+            .corner_perm
+            .iter()
+            .position(|&p| p == target as u8)
+            .unwrap();
+        if pos >= 4 {
+            let frame = slot_frame(pos);
+            let out = frame.apply_alg(&sexy);
+            c = apply_alg(&c, &out);
+            moves.extend(out);
+            parts.push(format!(
+                "take it out of {} with {}",
+                CORNER_NAMES[pos], SEXY
+            ));
+        }
+
+        // 目標の真上 (U 層) へ
+        let [_, a, b] = CORNER_FACELETS[target];
+        let above = (0..4)
+            .find(|&u| CORNER_FACELETS[u].contains(&a) && CORNER_FACELETS[u].contains(&b))
+            .unwrap();
+        let auf = AUFS
+            .iter()
+            .find(|&&auf| {
+                let mut t = c;
+                if let Some(m) = auf {
+                    t.apply_move(m);
+                }
+                t.corner_perm[above] == target as u8
+            })
+            .copied()
+            .ok_or(SolveError::Stuck("first layer"))?;
+        if let Some(m) = auf {
+            c.apply_move(m);
+            moves.push(m);
+            parts.push(format!("turn U to bring it above {}", CORNER_NAMES[target]));
+        }
+
+        let insert = slot_frame(target).apply_alg(&sexy);
+        let mut reps = 0;
+        while !corner_solved(&c, target) {
+            if reps == 6 {
+                return Err(SolveError::Stuck("first layer"));
+            }
+            c = apply_alg(&c, &insert);
+            moves.extend(insert.iter().copied());
+            reps += 1;
+        }
+        parts.push(format!("repeat {} {} time(s)", SEXY, reps));
+
+        *cube = c;
+        steps.push(SolveStep {
+            stage: "first layer",
+            moves,
+            explanation: format!(
+                "Insert the {} corner: {}",
+                CORNER_NAMES[target],
+                parts.join(", ")
+            ),
+        });
+    }
+    Ok(())
+}
+
+// 2 段目の辺: 左右への挿入手順（取り出しにも使う）を組み合わせる
+fn solve_middle_edges(cube: &mut Cube, steps: &mut Vec<SolveStep>) -> Result<(), SolveError> {
+    let mut macros = Vec::new();
+    for slot in [5usize, 4, 7, 6] {
+        let frame = slot_frame(slot);
+        for (name, s) in [("right insert", RIGHT_INSERT), ("left insert", LEFT_INSERT)] {
+            let label = format!("{} ({}) from {}", name, s, frame_front(&frame));
+            macros.extend(with_aufs(&label, &frame.apply_alg(&alg(s))));
+        }
+    }
+
+    let mut done: Vec<usize> = Vec::new();
+    for target in [5usize, 4, 7, 6] {
+        let prior = done.clone();
+        let goal = move |c: &Cube| edge_solved(c, target);
+        let keep =
+            move |c: &Cube| first_layer_solved(c) && prior.iter().all(|&e| edge_solved(c, e));
+        let intro = format!("Insert the {} edge", EDGE_NAMES[target]);
+        steps.extend(macro_step(
+            cube,
+            "middle layer",
+            &intro,
+            &macros,
+            2,
+            &goal,
+            &keep,
+        )?);
+        done.push(target);
+    }
+    Ok(())
+}
+
+// 持ち替え後に正面に来る面
+fn frame_front(frame: &Rotation) -> String {
+    format!("the {:?} face", frame.apply_face(Face::F))
+}
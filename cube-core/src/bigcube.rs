@@ -0,0 +1,750 @@
+//! bigcube.rs: NxNxN（4x4x4 ～ 7x7x7）
+//!
+//! 状態はステッカーの置換で持つ. `stickers[位置] = その位置にある（揃ったときの）ステッカー`.
+//! 手は幾何的に求める: 各ステッカーの座標と法線を持ち, 回す層のものを 90 度回転させる.
+//!
+//! 座標は 2 倍して整数にしたもの（キュービーの中心が -(N-1) ..= N-1 の偶奇が N-1 と同じ値）.
+//! 各面のステッカーは外から見て左上から行ごとに並べる（U は B が上, D は F が上, 側面は U が上）.
+//!
+//! パーツは位置の種類ごとの軌道（orbit）に分かれる: 角, 中央の辺（奇数のみ）, ウイング,
+//! X センター, T センター, オブリーク, 固定センター（奇数のみ）.
+//! 同じ色のセンターは区別しないので, センターの置換の偶奇に制約はない.
+//! 角と辺のパーツのステッカーは 3x3x3 の facelet の順（U/D または F/B の面から）に並べ,
+//! 最初のステッカーでパーツと向きを決める.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use crate::facelet::{CORNER_FACELETS, EDGE_FACELETS};
+use crate::notation::{tokens, ParseError};
+use crate::{Cube, Face};
+
+/// 扱える大きさ
+pub const BIG_CUBE_SIZES: std::ops::RangeInclusive<usize> = 4..=7;
+
+/// 層を指定した手. 層は面 `face` から数えて `from ..= to`（1 が一番外側）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BigMove {
+    pub face: Face,
+    pub from: u8,
+    pub to: u8,
+    /// 面を外から見て時計回り 90 度単位の回転量 (1, 2, 3)
+    pub quarter_turns: u8,
+}
+
+impl BigMove {
+    pub fn inverse(self) -> BigMove {
+        BigMove {
+            quarter_turns: 4 - self.quarter_turns,
+            ..self
+        }
+    }
+
+    /// SiGN 記法（`R`, `2R`, `Rw`, `3Rw`, `2-3Rw`, `x`）. n は立方体の大きさ
+    pub fn to_notation(self, n: usize) -> String {
+        let face = FACE_LETTERS[self.face as usize];
+        let body = match (self.from, self.to as usize) {
+            (1, to) if to == n && matches!(self.face, Face::R | Face::U | Face::F) => {
+                ROTATION_LETTERS[self.face as usize].to_string()
+            }
+            (1, 1) => face.to_string(),
+            (1, 2) => format!("{}w", face),
+            (1, to) => format!("{}{}w", to, face),
+            (from, to) if from as usize == to => format!("{}{}", from, face),
+            (from, to) => format!("{}-{}{}w", from, to, face),
+        };
+        let suffix = match self.quarter_turns {
+            1 => "",
+            2 => "2",
+            _ => "'",
+        };
+        format!("{}{}", body, suffix)
+    }
+}
+
+const FACE_LETTERS: [&str; 6] = ["U", "D", "L", "R", "F", "B"];
+const ROTATION_LETTERS: [&str; 6] = ["y", "", "", "x", "z", ""];
+
+/// SiGN 記法の手順をパースする. 層の番号は 1 ..= n でなければならない
+///
+/// - `R`: 外側の 1 層, `2R`: 2 層目だけ, `2-3R`: 2 ～ 3 層目
+/// - `Rw` と `r`: 外側の 2 層, `3Rw` と `3r`: 外側の 3 層
+/// - `x`, `y`, `z`: 全体の持ち替え（R, U, F の向き）
+pub fn parse_big_alg(s: &str, n: usize) -> Result<Vec<BigMove>, ParseError> {
+    tokens(s)
+        .enumerate()
+        .map(|(index, (offset, token))| {
+            parse_big_move(token, n).ok_or_else(|| ParseError {
+                token: token.to_string(),
+                index,
+                offset,
+            })
+        })
+        .collect()
+}
+
+fn parse_big_move(token: &str, n: usize) -> Option<BigMove> {
+    let (body, quarter_turns) = if let Some(b) = token.strip_suffix("2'") {
+        (b, 2)
+    } else if let Some(b) = token.strip_suffix('2') {
+        (b, 2)
+    } else if let Some(b) = token.strip_suffix('\'') {
+        (b, 3)
+    } else {
+        (token, 1)
+    };
+
+    // 層の範囲の前置き（"3" または "2-3"）
+    let digits_end = body
+        .find(|c: char| !(c.is_ascii_digit() || c == '-'))
+        .unwrap_or(body.len());
+    let (prefix, rest) = body.split_at(digits_end);
+    let range: Option<(u8, u8)> = match prefix.split_once('-') {
+        _ if prefix.is_empty() => None,
+        Some((a, b)) => Some((a.parse().ok()?, b.parse().ok()?)),
+        None => {
+            let k = prefix.parse().ok()?;
+            Some((k, k))
+        }
+    };
+
+    let mut chars = rest.chars();
+    let letter = chars.next()?;
+    let wide_suffix = match chars.as_str() {
+        "" => false,
+        "w" => true,
+        _ => return None,
+    };
+    let n8 = n as u8;
+
+    let (face, from, to) = match letter {
+        'x' | 'y' | 'z' => {
+            if range.is_some() || wide_suffix {
+                return None;
+            }
+            let face = match letter {
+                'x' => Face::R,
+                'y' => Face::U,
+                _ => Face::F,
+            };
+            (face, 1, n8)
+        }
+        _ => {
+            let upper = letter.to_ascii_uppercase();
+            let face = Face::ALL[FACE_LETTERS.iter().position(|f| f.starts_with(upper))?];
+            let wide = wide_suffix || letter.is_ascii_lowercase();
+            if letter.is_ascii_lowercase() && wide_suffix {
+                return None;
+            }
+            match (range, wide) {
+                (Some((a, b)), _) if a != b => (face, a, b),
+                (Some((k, _)), true) => (face, 1, k),
+                (Some((k, _)), false) => (face, k, k),
+                (None, true) => (face, 1, 2),
+                (None, false) => (face, 1, 1),
+            }
+        }
+    };
+    if from == 0 || from > to || to > n8 {
+        return None;
+    }
+    Some(BigMove {
+        face,
+        from,
+        to,
+        quarter_turns,
+    })
+}
+
+/// 手順を SiGN 記法の文字列にする
+pub fn format_big_alg(moves: &[BigMove], n: usize) -> String {
+    moves
+        .iter()
+        .map(|m| m.to_notation(n))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 軌道の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OrbitKind {
+    Corner,
+    /// 中央の辺（奇数のみ）
+    Midge,
+    Wing,
+    XCenter,
+    TCenter,
+    Oblique,
+    /// 固定センター（奇数のみ. 中央の層を回すと動く）
+    FixedCenter,
+}
+
+impl OrbitKind {
+    /// パーツの向きの数（ウイングは手で裏返らないので 1）
+    pub fn orientations(self) -> u8 {
+        match self {
+            OrbitKind::Corner => 3,
+            OrbitKind::Midge => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// パーツの軌道. 手で移り合う位置の集まり
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Orbit {
+    pub kind: OrbitKind,
+    /// 例: "corners", "wings 2", "x-centers 2", "obliques 2-3"
+    pub name: String,
+    /// 軌道に属するキュービーの番号
+    pub cubies: Vec<usize>,
+}
+
+/// 軌道ごとの合法性の問題
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrbitProblem {
+    /// ステッカーがパーツとして揃っていない, 別の軌道にある, 裏返っている
+    Misplaced,
+    /// 角のねじれの総和が 3 の倍数でない
+    Twist,
+    /// 中央の辺の反転の総和が偶数でない
+    Flip,
+    /// 角と中央の辺の置換の偶奇が合わない（固定センター基準）
+    Parity,
+}
+
+/// 1 つの軌道の合法性
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrbitLegality {
+    pub name: String,
+    pub kind: OrbitKind,
+    pub problems: Vec<OrbitProblem>,
+}
+
+// 大きさごとの幾何情報
+struct Model {
+    n: usize,
+    /// ステッカーごとの (座標, 法線)
+    stickers: Vec<([i32; 3], [i32; 3])>,
+    index: HashMap<([i32; 3], [i32; 3]), usize>,
+    /// キュービーごとのステッカー
+    cubies: Vec<Vec<usize>>,
+    cubie_of: Vec<usize>,
+    orbits: Vec<Orbit>,
+    /// キュービーのステッカーが手で移りうる位置の組
+    placements: Vec<HashSet<Vec<usize>>>,
+    /// 全体の持ち替え 24 通り（ステッカーの移動先）
+    rotations: Vec<Vec<usize>>,
+}
+
+fn normal(face: Face) -> [i32; 3] {
+    crate::geometry::normal(face).map(i32::from)
+}
+
+// 外から見て (右, 下) の向き
+fn face_axes(face: Face) -> ([i32; 3], [i32; 3]) {
+    match face {
+        Face::U => ([1, 0, 0], [0, 0, 1]),
+        Face::D => ([1, 0, 0], [0, 0, -1]),
+        Face::L => ([0, 0, 1], [0, -1, 0]),
+        Face::R => ([0, 0, -1], [0, -1, 0]),
+        Face::F => ([1, 0, 0], [0, -1, 0]),
+        Face::B => ([-1, 0, 0], [0, -1, 0]),
+    }
+}
+
+fn face_of_normal(nv: [i32; 3]) -> Face {
+    Face::ALL.into_iter().find(|&f| normal(f) == nv).unwrap()
+}
+
+fn dot(a: [i32; 3], b: [i32; 3]) -> i32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [i32; 3], b: [i32; 3]) -> [i32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+// 軸 a まわりに外から見て時計回り 90 度（a まわりに -90 度）
+fn rotate(v: [i32; 3], a: [i32; 3]) -> [i32; 3] {
+    let c = cross(a, v);
+    let d = dot(a, v);
+    [a[0] * d - c[0], a[1] * d - c[1], a[2] * d - c[2]]
+}
+
+impl Model {
+    fn get(n: usize) -> &'static Model {
+        static MODELS: [OnceLock<Model>; 4] = [const { OnceLock::new() }; 4];
+        MODELS[n - 4].get_or_init(|| Model::build(n))
+    }
+
+    fn build(n: usize) -> Model {
+        let m = n as i32 - 1;
+        let mut stickers = Vec::with_capacity(6 * n * n);
+        for face in Face::ALL {
+            let (right, down) = face_axes(face);
+            let nv = normal(face);
+            for row in 0..n as i32 {
+                for col in 0..n as i32 {
+                    let (x, y) = (2 * col - m, 2 * row - m);
+                    let p = [0, 1, 2].map(|i| nv[i] * m + right[i] * x + down[i] * y);
+                    stickers.push((p, nv));
+                }
+            }
+        }
+        let index: HashMap<_, _> = stickers.iter().enumerate().map(|(i, &s)| (s, i)).collect();
+
+        // 同じ座標のステッカーをキュービーにまとめる
+        let mut cubie_index: HashMap<[i32; 3], usize> = HashMap::new();
+        let mut cubies: Vec<Vec<usize>> = Vec::new();
+        let mut cubie_of = vec![0; stickers.len()];
+        for (i, &(p, _)) in stickers.iter().enumerate() {
+            let c = *cubie_index.entry(p).or_insert_with(|| {
+                cubies.push(Vec::new());
+                cubies.len() - 1
+            });
+            cubies[c].push(i);
+            cubie_of[i] = c;
+        }
+        // 角と辺のステッカーは 3x3x3 の facelet の順（U/D または F/B の面から）に並べる
+        for stickers_of in cubies.iter_mut() {
+            let faces: Vec<Face> = stickers_of
+                .iter()
+                .map(|&i| face_of_normal(stickers[i].1))
+                .collect();
+            let order: Option<&[Face]> = match faces.len() {
+                3 => CORNER_FACELETS
+                    .iter()
+                    .find(|f| faces.iter().all(|x| f.contains(x)))
+                    .map(|f| &f[..]),
+                2 => EDGE_FACELETS
+                    .iter()
+                    .find(|f| faces.iter().all(|x| f.contains(x)))
+                    .map(|f| &f[..]),
+                _ => None,
+            };
+            if let Some(order) = order {
+                stickers_of.sort_by_key(|&i| {
+                    order
+                        .iter()
+                        .position(|&f| f == face_of_normal(stickers[i].1))
+                });
+            }
+        }
+
+        let mut model = Model {
+            n,
+            stickers,
+            index,
+            cubies,
+            cubie_of,
+            orbits: Vec::new(),
+            placements: Vec::new(),
+            rotations: Vec::new(),
+        };
+        model.orbits = model.classify();
+        model.placements = model.reachable_placements();
+        model.rotations = model.whole_rotations();
+        model
+    }
+
+    // 面から数えた層の番号（1 が外側）
+    fn depth(&self, coord: i32) -> i32 {
+        (self.n as i32 - 1 - coord.abs()) / 2 + 1
+    }
+
+    fn classify(&self) -> Vec<Orbit> {
+        let m = self.n as i32 - 1;
+        let mut orbits: Vec<Orbit> = Vec::new();
+        for (c, stickers) in self.cubies.iter().enumerate() {
+            let (p, nv) = self.stickers[stickers[0]];
+            let (kind, name) = match stickers.len() {
+                3 => (OrbitKind::Corner, "corners".to_string()),
+                2 => {
+                    let free = p.iter().copied().find(|v| v.abs() != m).unwrap();
+                    if free == 0 {
+                        (OrbitKind::Midge, "midges".to_string())
+                    } else {
+                        (OrbitKind::Wing, format!("wings {}", self.depth(free)))
+                    }
+                }
+                _ => {
+                    // 面の中での (右, 下) 座標を, 両方正になるまで面内で回す
+                    let face = face_of_normal(nv);
+                    let (right, down) = face_axes(face);
+                    let (mut a, mut b) = (dot(p, right), dot(p, down));
+                    while !(a > 0 && b >= 0) && !(a == 0 && b == 0) {
+                        (a, b) = (-b, a);
+                    }
+                    match (a, b) {
+                        (0, 0) => (OrbitKind::FixedCenter, "centers".to_string()),
+                        (a, 0) => (OrbitKind::TCenter, format!("t-centers {}", self.depth(a))),
+                        (a, b) if a == b => {
+                            (OrbitKind::XCenter, format!("x-centers {}", self.depth(a)))
+                        }
+                        (a, b) => (
+                            OrbitKind::Oblique,
+                            format!("obliques {}-{}", self.depth(a), self.depth(b)),
+                        ),
+                    }
+                }
+            };
+            match orbits.iter_mut().find(|o| o.name == name) {
+                Some(o) => o.cubies.push(c),
+                None => orbits.push(Orbit {
+                    kind,
+                    name,
+                    cubies: vec![c],
+                }),
+            }
+        }
+        orbits
+    }
+
+    // 面 face から数えて from..=to 層目を 1/4 回転したときの, 各ステッカーの移動先
+    fn quarter_turn(&self, face: Face, from: u8, to: u8) -> Vec<usize> {
+        let a = normal(face);
+        let m = self.n as i32 - 1;
+        self.stickers
+            .iter()
+            .enumerate()
+            .map(|(i, &(p, nv))| {
+                let layer = (m - dot(p, a)) / 2 + 1;
+                if (from as i32..=to as i32).contains(&layer) {
+                    self.index[&(rotate(p, a), rotate(nv, a))]
+                } else {
+                    i
+                }
+            })
+            .collect()
+    }
+
+    fn reachable_placements(&self) -> Vec<HashSet<Vec<usize>>> {
+        let gens: Vec<Vec<usize>> = Face::ALL
+            .into_iter()
+            .flat_map(|f| (1..=self.n as u8).map(move |k| (f, k)))
+            .map(|(f, k)| self.quarter_turn(f, k, k))
+            .collect();
+        self.cubies
+            .iter()
+            .map(|home| {
+                let mut seen: HashSet<Vec<usize>> = HashSet::from([home.clone()]);
+                let mut queue = vec![home.clone()];
+                while let Some(slots) = queue.pop() {
+                    for g in &gens {
+                        let next: Vec<usize> = slots.iter().map(|&s| g[s]).collect();
+                        if seen.insert(next.clone()) {
+                            queue.push(next);
+                        }
+                    }
+                }
+                seen
+            })
+            .collect()
+    }
+
+    fn whole_rotations(&self) -> Vec<Vec<usize>> {
+        let n = self.n as u8;
+        let gens = [
+            self.quarter_turn(Face::R, 1, n),
+            self.quarter_turn(Face::U, 1, n),
+        ];
+        let identity: Vec<usize> = (0..self.stickers.len()).collect();
+        let mut all = vec![identity];
+        let mut frontier = 0;
+        while frontier < all.len() {
+            for g in &gens {
+                let next: Vec<usize> = all[frontier].iter().map(|&s| g[s]).collect();
+                if !all.contains(&next) {
+                    all.push(next);
+                }
+            }
+            frontier += 1;
+        }
+        all
+    }
+
+    // 面 f の中心方向にあるステッカー（座標 p の法線 f のもの）
+    fn sticker_at(&self, faces: &[Face], f: Face) -> usize {
+        let m = self.n as i32 - 1;
+        let mut p = [0; 3];
+        for &g in faces {
+            let nv = normal(g);
+            for i in 0..3 {
+                p[i] += nv[i] * m;
+            }
+        }
+        self.index[&(p, normal(f))]
+    }
+}
+
+/// NxNxN の状態
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BigCube {
+    n: usize,
+    stickers: Vec<u16>,
+}
+
+impl BigCube {
+    /// 揃った n x n x n. n は 4 ..= 7
+    pub fn new(n: usize) -> Option<BigCube> {
+        if !BIG_CUBE_SIZES.contains(&n) {
+            return None;
+        }
+        let model = Model::get(n);
+        Some(BigCube {
+            n,
+            stickers: (0..model.stickers.len() as u16).collect(),
+        })
+    }
+
+    /// ステッカーの並びから作る. `stickers[位置] = その位置にあるステッカー`
+    /// （位置とステッカーの番号は面ごとに外から見て左上から行ごと）. 置換でなければ None.
+    /// パーツとして組めない並び（別の軌道のステッカーが入るなど）も受け付け,
+    /// `legality` で `OrbitProblem::Misplaced` になる
+    pub fn from_stickers(n: usize, stickers: Vec<u16>) -> Option<BigCube> {
+        let len = BigCube::new(n)?.stickers.len();
+        let mut seen = vec![false; len];
+        let ok = stickers.len() == len
+            && stickers
+                .iter()
+                .all(|&s| (s as usize) < len && !std::mem::replace(&mut seen[s as usize], true));
+        ok.then_some(BigCube { n, stickers })
+    }
+
+    pub fn stickers(&self) -> &[u16] {
+        &self.stickers
+    }
+
+    fn model(&self) -> &'static Model {
+        Model::get(self.n)
+    }
+
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    pub fn apply_move(&mut self, m: BigMove) {
+        let dest = self.model().quarter_turn(m.face, m.from, m.to);
+        for _ in 0..m.quarter_turns {
+            self.permute(&dest);
+        }
+    }
+
+    pub fn apply_alg(&self, alg: &[BigMove]) -> BigCube {
+        let mut c = self.clone();
+        for &m in alg {
+            c.apply_move(m);
+        }
+        c
+    }
+
+    // 位置 i のステッカーを dest[i] へ移す
+    fn permute(&mut self, dest: &[usize]) {
+        let old = self.stickers.clone();
+        for (i, &d) in dest.iter().enumerate() {
+            self.stickers[d] = old[i];
+        }
+    }
+
+    /// 位置 i のステッカーの色（揃ったときの面）
+    pub fn color(&self, i: usize) -> Face {
+        self.face_of(self.stickers[i] as usize)
+    }
+
+    fn face_of(&self, sticker: usize) -> Face {
+        Face::ALL[sticker / (self.n * self.n)]
+    }
+
+    /// 面 f のステッカーの色（外から見て左上から行ごと）
+    pub fn face_colors(&self, f: Face) -> Vec<Face> {
+        let nn = self.n * self.n;
+        (f as usize * nn..(f as usize + 1) * nn)
+            .map(|i| self.color(i))
+            .collect()
+    }
+
+    /// 各面が 1 色か（全体の向きは問わない）
+    pub fn is_solved(&self) -> bool {
+        Face::ALL.into_iter().all(|f| {
+            let colors = self.face_colors(f);
+            colors.iter().all(|&c| c == colors[0])
+        })
+    }
+
+    pub fn orbits(&self) -> &'static [Orbit] {
+        &self.model().orbits
+    }
+
+    // 軌道内の位置ごとに, 入っているパーツの軌道内の番号. 別の軌道のパーツなら None.
+    // 位置の最初のステッカーがどのパーツのものかで決める
+    pub(crate) fn orbit_slots(&self, orbit: &Orbit) -> Vec<Option<usize>> {
+        let model = self.model();
+        orbit
+            .cubies
+            .iter()
+            .map(|&c| {
+                let home = model.cubie_of[self.stickers[model.cubies[c][0]] as usize];
+                orbit.cubies.iter().position(|&h| h == home)
+            })
+            .collect()
+    }
+
+    /// 軌道内の置換. `perm[i] = 軌道内の位置 i にあるパーツの番号`.
+    /// 別の軌道のパーツが入っていたり, 同じパーツが 2 か所に数えられたりして
+    /// 置換にならない状態（`from_stickers` で作れる）では None
+    pub fn orbit_perm(&self, orbit: &Orbit) -> Option<Vec<u8>> {
+        let slots = self.orbit_slots(orbit);
+        let mut seen = vec![false; slots.len()];
+        slots
+            .into_iter()
+            .map(|slot| {
+                let p = slot.filter(|&p| !std::mem::replace(&mut seen[p], true))?;
+                Some(p as u8)
+            })
+            .collect()
+    }
+
+    /// 軌道内の向き（位置の順）. 約束は `Cube` と同じで, 向きのない軌道は 0
+    pub fn orbit_ori(&self, orbit: &Orbit) -> Vec<u8> {
+        let model = self.model();
+        orbit
+            .cubies
+            .iter()
+            .map(|&c| {
+                let sticker = self.stickers[model.cubies[c][0]] as usize;
+                let k = model.cubies[model.cubie_of[sticker]]
+                    .iter()
+                    .position(|&s| s == sticker)
+                    .unwrap() as u8;
+                match orbit.kind {
+                    OrbitKind::Corner => (3 - k) % 3,
+                    OrbitKind::Midge => k,
+                    _ => 0,
+                }
+            })
+            .collect()
+    }
+
+    /// 軌道内の置換のサイクル（軌道内でのキュービーの番号. `corner_cycles` と同じ形）.
+    /// 別の軌道のパーツが入っている状態では, そこで切れた列も含む
+    pub fn orbit_cycles(&self, orbit: &Orbit) -> Vec<Vec<u8>> {
+        let perm = self.orbit_slots(orbit);
+        let n = perm.len();
+        let mut visited = vec![false; n];
+        let mut cycles = Vec::new();
+        for i in 0..n {
+            if !visited[i] && perm[i] != Some(i) {
+                let mut cycle = Vec::new();
+                let mut j = Some(i);
+                while let Some(k) = j.filter(|&k| !visited[k]) {
+                    visited[k] = true;
+                    cycle.push(k as u8);
+                    j = perm[k];
+                }
+                cycles.push(cycle);
+            }
+        }
+        cycles
+    }
+
+    /// 軌道ごとの合法性
+    pub fn legality(&self) -> Vec<OrbitLegality> {
+        let model = self.model();
+        let mut where_is = vec![0; self.stickers.len()];
+        for (slot, &s) in self.stickers.iter().enumerate() {
+            where_is[s as usize] = slot;
+        }
+        let reduced = self.reduced_cube();
+
+        model
+            .orbits
+            .iter()
+            .map(|orbit| {
+                let mut problems = Vec::new();
+                let placed = orbit.cubies.iter().all(|&c| {
+                    let slots: Vec<usize> = model.cubies[c].iter().map(|&s| where_is[s]).collect();
+                    model.placements[c].contains(&slots)
+                });
+                if !placed {
+                    problems.push(OrbitProblem::Misplaced);
+                }
+                if let (true, Some(cube)) = (placed, &reduced) {
+                    let info = cube.legality();
+                    match orbit.kind {
+                        OrbitKind::Corner if info.corner_twist_sum_mod3 != 0 => {
+                            problems.push(OrbitProblem::Twist)
+                        }
+                        OrbitKind::Midge => {
+                            if info.edge_flip_sum_mod2 != 0 {
+                                problems.push(OrbitProblem::Flip);
+                            }
+                            if info.corner_parity != info.edge_parity {
+                                problems.push(OrbitProblem::Parity);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                OrbitLegality {
+                    name: orbit.name.clone(),
+                    kind: orbit.kind,
+                    problems,
+                }
+            })
+            .collect()
+    }
+
+    pub fn is_legal(&self) -> bool {
+        self.legality().iter().all(|o| o.problems.is_empty())
+    }
+
+    /// 角と中央の辺（奇数のみ. 偶数では揃った辺）を 3x3x3 として見たもの.
+    /// 奇数では固定センターが元の位置に来るよう持ち替えてから見る.
+    /// パーツが壊れていれば None
+    pub fn reduced_cube(&self) -> Option<Cube> {
+        let model = self.model();
+        let odd = self.n % 2 == 1;
+        let view = if odd {
+            let centers: Vec<usize> = Face::ALL
+                .into_iter()
+                .map(|f| model.sticker_at(&[f], f))
+                .collect();
+            let rotation = model.rotations.iter().find(|r| {
+                centers.iter().all(|&c| {
+                    let slot = r[self.stickers.iter().position(|&s| s as usize == c).unwrap()];
+                    slot == c
+                })
+            })?;
+            let mut view = self.clone();
+            view.permute(rotation);
+            view
+        } else {
+            self.clone()
+        };
+
+        let mut facelets = Cube::identity().to_facelets();
+        for (i, faces) in CORNER_FACELETS.iter().enumerate() {
+            for (k, &f) in faces.iter().enumerate() {
+                facelets.corners[i][k] = view.color(model.sticker_at(faces, f));
+            }
+        }
+        if odd {
+            for (i, faces) in EDGE_FACELETS.iter().enumerate() {
+                for (k, &f) in faces.iter().enumerate() {
+                    facelets.edges[i][k] = view.color(model.sticker_at(faces, f));
+                }
+            }
+        }
+        Cube::from_facelets(&facelets)
+    }
+}
+
+/// 逆手順
+pub fn invert_big_alg(moves: &[BigMove]) -> Vec<BigMove> {
+    moves.iter().rev().map(|m| m.inverse()).collect()
+}
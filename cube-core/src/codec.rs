@@ -0,0 +1,221 @@
+//! codec.rs: 状態と手順の URL 向けの短い文字列表現
+//!
+//! 形式は `<種類><版>.<本体>`. 種類は状態が `s`, 手順が `a`. 本体は
+//! base64url の文字だけを使うので, そのままクエリやフラグメントに入れられる.
+//!
+//! - 状態 (`s1.`): 角の位置 3 bit × 8, 角の向き 2 bit × 8, 辺の位置 4 bit × 12,
+//!   辺の向き 1 bit × 12 の計 100 bit を 0 で埋めて 17 文字にしたもの
+//! - 手順 (`a1.`): 1 手を 1 文字（`Move::ALL` の番号）
+
+use thiserror::Error;
+
+use crate::{Cube, LegalityInfo, Move};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+const STATE_PREFIX: &str = "s1.";
+const ALG_PREFIX: &str = "a1.";
+const STATE_BITS: usize = 100;
+const STATE_CHARS: usize = STATE_BITS.div_ceil(6);
+
+/// 文字列表現を読めなかった理由
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum CodecError {
+    #[error("missing `{expected}` prefix")]
+    MissingPrefix { expected: &'static str },
+    #[error("unsupported version `{found}` (expected `{expected}`)")]
+    UnsupportedVersion {
+        expected: &'static str,
+        found: String,
+    },
+    #[error("invalid character `{ch}` at offset {offset}")]
+    InvalidCharacter { ch: char, offset: usize },
+    #[error("expected {expected} characters but found {found}")]
+    WrongLength { expected: usize, found: usize },
+    #[error("{0} is not a valid arrangement")]
+    InvalidPieces(&'static str),
+    #[error("state cannot be reached from solved (corner parity {}, edge parity {}, flip sum {}, twist sum {})", .0.corner_parity, .0.edge_parity, .0.edge_flip_sum_mod2, .0.corner_twist_sum_mod3)]
+    Illegal(LegalityInfo),
+}
+
+/// 状態を `s1.` で始まる文字列にする
+pub fn encode_state(cube: &Cube) -> String {
+    let mut bits = BitWriter::default();
+    for &p in &cube.corner_perm {
+        bits.push(p, 3);
+    }
+    for &o in &cube.corner_ori {
+        bits.push(o, 2);
+    }
+    for &p in &cube.edge_perm {
+        bits.push(p, 4);
+    }
+    for &o in &cube.edge_ori {
+        bits.push(o, 1);
+    }
+    let mut s = String::from(STATE_PREFIX);
+    s.extend(
+        bits.finish()
+            .into_iter()
+            .map(|v| ALPHABET[v as usize] as char),
+    );
+    s
+}
+
+/// `encode_state` の逆. 合法でない状態はエラー
+pub fn decode_state(s: &str) -> Result<Cube, CodecError> {
+    let body = strip_prefix(s, STATE_PREFIX)?;
+    let values = decode_chars(body, STATE_PREFIX.len())?;
+    if values.len() != STATE_CHARS {
+        return Err(CodecError::WrongLength {
+            expected: STATE_CHARS,
+            found: values.len(),
+        });
+    }
+    let mut bits = BitReader::new(&values);
+    let mut cube = Cube::identity();
+    for p in cube.corner_perm.iter_mut() {
+        *p = bits.read(3);
+    }
+    for o in cube.corner_ori.iter_mut() {
+        *o = bits.read(2);
+    }
+    for p in cube.edge_perm.iter_mut() {
+        *p = bits.read(4);
+    }
+    for o in cube.edge_ori.iter_mut() {
+        *o = bits.read(1);
+    }
+    if bits.read(STATE_CHARS * 6 - STATE_BITS) != 0 {
+        return Err(CodecError::InvalidPieces("padding"));
+    }
+
+    if !is_permutation(&cube.corner_perm) {
+        return Err(CodecError::InvalidPieces("corner permutation"));
+    }
+    if cube.corner_ori.iter().any(|&o| o > 2) {
+        return Err(CodecError::InvalidPieces("corner orientation"));
+    }
+    if !is_permutation(&cube.edge_perm) {
+        return Err(CodecError::InvalidPieces("edge permutation"));
+    }
+    let legality = cube.legality();
+    if !legality.is_legal {
+        return Err(CodecError::Illegal(legality));
+    }
+    Ok(cube)
+}
+
+/// 手順を `a1.` で始まる文字列にする
+pub fn encode_alg(moves: &[Move]) -> String {
+    let mut s = String::from(ALG_PREFIX);
+    s.extend(moves.iter().map(|&m| ALPHABET[m as usize] as char));
+    s
+}
+
+/// `encode_alg` の逆
+pub fn decode_alg(s: &str) -> Result<Vec<Move>, CodecError> {
+    let body = strip_prefix(s, ALG_PREFIX)?;
+    let values = decode_chars(body, ALG_PREFIX.len())?;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            Move::ALL
+                .get(v as usize)
+                .copied()
+                .ok_or(CodecError::InvalidCharacter {
+                    ch: ALPHABET[v as usize] as char,
+                    offset: ALG_PREFIX.len() + i,
+                })
+        })
+        .collect()
+}
+
+// 種類と版を確かめて本体を返す
+fn strip_prefix<'a>(s: &'a str, prefix: &'static str) -> Result<&'a str, CodecError> {
+    if let Some(body) = s.strip_prefix(prefix) {
+        return Ok(body);
+    }
+    let kind = &prefix[..1];
+    match s.split_once('.') {
+        Some((head, _)) if head.len() > 1 && head.starts_with(kind) => {
+            Err(CodecError::UnsupportedVersion {
+                expected: &prefix[1..prefix.len() - 1],
+                found: head[1..].to_string(),
+            })
+        }
+        _ => Err(CodecError::MissingPrefix { expected: prefix }),
+    }
+}
+
+fn decode_chars(body: &str, offset: usize) -> Result<Vec<u8>, CodecError> {
+    body.char_indices()
+        .map(|(i, ch)| {
+            ALPHABET
+                .iter()
+                .position(|&a| a as char == ch)
+                .map(|v| v as u8)
+                .ok_or(CodecError::InvalidCharacter {
+                    ch,
+                    offset: offset + i,
+                })
+        })
+        .collect()
+}
+
+fn is_permutation(perm: &[u8]) -> bool {
+    let mut seen = vec![false; perm.len()];
+    perm.iter().all(|&p| {
+        let p = p as usize;
+        p < perm.len() && !std::mem::replace(&mut seen[p], true)
+    })
+}
+
+// 上位ビットから 6 bit ずつ区切る
+#[derive(Default)]
+struct BitWriter {
+    values: Vec<u8>,
+    acc: u32,
+    len: usize,
+}
+
+impl BitWriter {
+    fn push(&mut self, value: u8, bits: usize) {
+        self.acc = (self.acc << bits) | value as u32;
+        self.len += bits;
+        while self.len >= 6 {
+            self.len -= 6;
+            self.values.push((self.acc >> self.len) as u8 & 0x3f);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            let pad = 6 - self.len;
+            self.push(0, pad);
+        }
+        self.values
+    }
+}
+
+struct BitReader<'a> {
+    values: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(values: &'a [u8]) -> Self {
+        BitReader { values, pos: 0 }
+    }
+
+    fn read(&mut self, bits: usize) -> u8 {
+        let mut v = 0;
+        for _ in 0..bits {
+            let bit = (self.values[self.pos / 6] >> (5 - self.pos % 6)) & 1;
+            v = (v << 1) | bit;
+            self.pos += 1;
+        }
+        v
+    }
+}
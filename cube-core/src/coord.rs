@@ -0,0 +1,127 @@
+//! coord.rs: 小さなパズルの最短手数表
+//!
+//! 状態を 2 つの独立な部分（例: 辺とセンター）に分け, それぞれ揃った状態から
+//! 幅優先探索で到達できるものを列挙して番号を付ける（座標）.
+//! 2 つの座標の組全体の最短手数表を `prune_table` で作り, 1 手ずつ近づけて最短解を求める.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::solver::prune_table;
+
+/// 部分的な状態の列挙と, 各状態・各手の移り先
+pub(crate) struct Coordinate<T> {
+    states: Vec<T>,
+    index: HashMap<T, u32>,
+    moves: Vec<Vec<u32>>,
+}
+
+impl<T: Clone + Eq + Hash> Coordinate<T> {
+    /// start から n_moves 個の手 `apply(state, m)` で到達できる状態を列挙する
+    pub fn build(start: T, n_moves: usize, apply: impl Fn(&T, usize) -> T) -> Self {
+        let mut states = vec![start.clone()];
+        let mut index = HashMap::from([(start, 0)]);
+        let mut moves = Vec::new();
+        let mut i = 0;
+        while i < states.len() {
+            let row = (0..n_moves)
+                .map(|m| {
+                    let next = apply(&states[i], m);
+                    *index.entry(next.clone()).or_insert_with(|| {
+                        states.push(next);
+                        states.len() as u32 - 1
+                    })
+                })
+                .collect();
+            moves.push(row);
+            i += 1;
+        }
+        Coordinate {
+            states,
+            index,
+            moves,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn index(&self, state: &T) -> Option<usize> {
+        self.index.get(state).map(|&i| i as usize)
+    }
+
+    /// 揃った状態（番号 0）からの最短手数
+    pub fn distances(&self) -> Vec<u8> {
+        prune_table(self.len(), 1, self.moves[0].len(), |i, _, m| {
+            (self.step(i, m), 0)
+        })
+    }
+
+    pub fn step(&self, index: usize, m: usize) -> usize {
+        self.moves[index][m] as usize
+    }
+}
+
+/// 2 つの座標の組の最短手数表
+pub(crate) struct ProductTable<A, B> {
+    pub a: Coordinate<A>,
+    pub b: Coordinate<B>,
+    n_moves: usize,
+    distance: Vec<u8>,
+}
+
+impl<A: Clone + Eq + Hash, B: Clone + Eq + Hash> ProductTable<A, B> {
+    pub fn build(a: Coordinate<A>, b: Coordinate<B>, n_moves: usize) -> Self {
+        let distance = prune_table(a.len(), b.len(), n_moves, |i, j, m| {
+            (a.step(i, m), b.step(j, m))
+        });
+        ProductTable {
+            a,
+            b,
+            n_moves,
+            distance,
+        }
+    }
+
+    /// 状態の数（到達できるもの）
+    pub fn len(&self) -> usize {
+        self.distance.iter().filter(|&&d| d != u8::MAX).count()
+    }
+
+    /// 最長の最短手数
+    pub fn diameter(&self) -> usize {
+        self.distance
+            .iter()
+            .filter(|&&d| d != u8::MAX)
+            .max()
+            .copied()
+            .unwrap_or(0) as usize
+    }
+
+    /// 最短手数. 列挙されていない部分状態や, 組として到達できないものは None
+    pub fn distance(&self, a: &A, b: &B) -> Option<usize> {
+        let (i, j) = (self.a.index(a)?, self.b.index(b)?);
+        let d = self.distance[i * self.b.len() + j];
+        (d != u8::MAX).then_some(d as usize)
+    }
+
+    /// 最短解（手の番号の列）
+    pub fn solve(&self, a: &A, b: &B) -> Option<Vec<usize>> {
+        let (mut i, mut j) = (self.a.index(a)?, self.b.index(b)?);
+        let mut d = self.distance[i * self.b.len() + j];
+        if d == u8::MAX {
+            return None;
+        }
+        let mut solution = Vec::new();
+        while d > 0 {
+            // 1 手近づく手を選ぶ
+            let (m, ni, nj) = (0..self.n_moves)
+                .map(|m| (m, self.a.step(i, m), self.b.step(j, m)))
+                .find(|&(_, ni, nj)| self.distance[ni * self.b.len() + nj] == d - 1)?;
+            solution.push(m);
+            (i, j, d) = (ni, nj, d - 1);
+        }
+        Some(solution)
+    }
+}
@@ -0,0 +1,424 @@
+//! cuboid.rs: 直方体パズル 3x3x2（ドミノ）と 1x3x3（フロッピー）
+//!
+//! どちらも側面は 180 度しか回せない. 手は `Move` の一部をそのまま使う.
+//!
+//! ドミノは E スライスのない `Cube` として扱い, 手の適用は `Cube` の処理を使う.
+//! 側面の 180 度回しと U/D では向きが変わらないので, 角 8 つと辺 8 つの置換だけを持つ.
+//! 最短解は角と辺それぞれの最短手数表を下界にした反復深化で求める.
+//! 全状態（8! * 8!）の最短手数表 `DominoDistanceTable` は 1 状態 2 ビットで持ち,
+//! 必要なときだけ明示的に作る.
+//!
+//! フロッピーは U 面の層だけの 3x3 で, 側面を回すと角 2 つが入れ替わって裏返り,
+//! その面の辺が裏返る. 状態は 192 通りなので `DistanceTable` で全状態を探索できる.
+
+use crate::coord::Coordinate;
+use crate::cycles::perm_cycles;
+use crate::notation::{tokens, ParseError};
+use crate::solver::SolveError;
+use crate::state::parity;
+use crate::{Cube, Move};
+
+/// ドミノで回せる手
+pub const DOMINO_MOVES: [Move; 10] = [
+    Move::U,
+    Move::U2,
+    Move::Up,
+    Move::D,
+    Move::D2,
+    Move::Dp,
+    Move::R2,
+    Move::L2,
+    Move::F2,
+    Move::B2,
+];
+
+/// フロッピーで回せる手
+pub const FLOPPY_MOVES: [Move; 4] = [Move::R2, Move::L2, Move::F2, Move::B2];
+
+// ドミノの辺（UF UR UB UL DF DR DB DL）の `Cube` での位置
+const DOMINO_EDGES: [usize; 8] = [0, 1, 2, 3, 8, 9, 10, 11];
+
+// フロッピーの手ごとの入れ替わる角の位置（FL FR BR BL）と裏返る辺（F R B L）
+const FLOPPY_TURNS: [(Move, [usize; 2], usize); 4] = [
+    (Move::R2, [1, 2], 1),
+    (Move::L2, [3, 0], 3),
+    (Move::F2, [0, 1], 0),
+    (Move::B2, [2, 3], 2),
+];
+
+/// ドミノの手順をパースする. 側面の 90 度回しは受け付けない
+pub fn parse_domino_alg(s: &str) -> Result<Vec<Move>, ParseError> {
+    parse_restricted(s, &DOMINO_MOVES)
+}
+
+/// フロッピーの手順をパースする
+pub fn parse_floppy_alg(s: &str) -> Result<Vec<Move>, ParseError> {
+    parse_restricted(s, &FLOPPY_MOVES)
+}
+
+fn parse_restricted(s: &str, allowed: &[Move]) -> Result<Vec<Move>, ParseError> {
+    tokens(s)
+        .enumerate()
+        .map(|(index, (offset, token))| {
+            token
+                .parse::<Move>()
+                .ok()
+                .filter(|m| allowed.contains(m))
+                .ok_or_else(|| ParseError {
+                    token: token.to_string(),
+                    index,
+                    offset,
+                })
+        })
+        .collect()
+}
+
+/// 3x3x2 の状態. 角の番号は `Cube` と同じ, 辺は UF UR UB UL DF DR DB DL
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DominoCube {
+    pub corner_perm: [u8; 8],
+    pub edge_perm: [u8; 8],
+}
+
+impl DominoCube {
+    pub fn identity() -> Self {
+        DominoCube {
+            corner_perm: [0, 1, 2, 3, 4, 5, 6, 7],
+            edge_perm: [0, 1, 2, 3, 4, 5, 6, 7],
+        }
+    }
+
+    /// `DOMINO_MOVES` 以外の手を渡すと panic する
+    pub fn apply_move(&mut self, m: Move) {
+        assert!(DOMINO_MOVES.contains(&m), "{:?} is not a domino move", m);
+        let mut cube = Cube::identity();
+        cube.corner_perm = self.corner_perm;
+        for (i, &slot) in DOMINO_EDGES.iter().enumerate() {
+            cube.edge_perm[slot] = DOMINO_EDGES[self.edge_perm[i] as usize] as u8;
+        }
+        cube.apply_move(m);
+        self.corner_perm = cube.corner_perm;
+        for (i, &slot) in DOMINO_EDGES.iter().enumerate() {
+            let piece = cube.edge_perm[slot] as usize;
+            self.edge_perm[i] = DOMINO_EDGES.iter().position(|&e| e == piece).unwrap() as u8;
+        }
+    }
+
+    pub fn apply_alg(&self, alg: &[Move]) -> Self {
+        let mut c = *self;
+        for &m in alg {
+            c.apply_move(m);
+        }
+        c
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == DominoCube::identity()
+    }
+
+    /// U は角と辺の両方で奇置換, 側面は辺だけで奇置換なので, 偶奇の制約はない
+    pub fn is_legal(&self) -> bool {
+        is_perm(&self.corner_perm) && is_perm(&self.edge_perm)
+    }
+
+    pub fn corner_cycles(&self) -> Vec<Vec<u8>> {
+        perm_cycles(&self.corner_perm)
+    }
+
+    pub fn edge_cycles(&self) -> Vec<Vec<u8>> {
+        perm_cycles(&self.edge_perm)
+    }
+}
+
+impl Default for DominoCube {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// 1x3x3 の状態. 角は FL FR BR BL, 辺は F R B L で, 辺は裏返るだけ
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FloppyCube {
+    pub corner_perm: [u8; 4],
+    /// 裏返っていれば 1
+    pub corner_ori: [u8; 4],
+    pub edge_ori: [u8; 4],
+}
+
+impl FloppyCube {
+    pub fn identity() -> Self {
+        FloppyCube {
+            corner_perm: [0, 1, 2, 3],
+            corner_ori: [0; 4],
+            edge_ori: [0; 4],
+        }
+    }
+
+    /// `FLOPPY_MOVES` 以外の手を渡すと panic する
+    pub fn apply_move(&mut self, m: Move) {
+        let &(_, [a, b], e) = FLOPPY_TURNS
+            .iter()
+            .find(|t| t.0 == m)
+            .unwrap_or_else(|| panic!("{:?} is not a floppy move", m));
+        self.corner_perm.swap(a, b);
+        self.corner_ori.swap(a, b);
+        self.corner_ori[a] ^= 1;
+        self.corner_ori[b] ^= 1;
+        self.edge_ori[e] ^= 1;
+    }
+
+    pub fn apply_alg(&self, alg: &[Move]) -> Self {
+        let mut c = *self;
+        for &m in alg {
+            c.apply_move(m);
+        }
+        c
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == FloppyCube::identity()
+    }
+
+    /// 角は隣へ 1 つ動くたびに裏返るので, 向きは元の位置からの距離の偶奇で決まる.
+    /// また手の数の偶奇が角の置換の偶奇と辺の裏返りの総和の両方に等しい
+    pub fn is_legal(&self) -> bool {
+        is_perm(&self.corner_perm)
+            && (0..4).all(|i| self.corner_ori[i] == (i as u8 + self.corner_perm[i]) % 2)
+            && self.edge_ori.iter().all(|&o| o < 2)
+            && self.edge_ori.iter().sum::<u8>() % 2 == parity(&self.corner_perm)
+    }
+
+    pub fn corner_cycles(&self) -> Vec<Vec<u8>> {
+        perm_cycles(&self.corner_perm)
+    }
+}
+
+impl Default for FloppyCube {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+fn is_perm(perm: &[u8]) -> bool {
+    let mut seen = vec![false; perm.len()];
+    perm.iter()
+        .all(|&p| (p as usize) < perm.len() && !std::mem::replace(&mut seen[p as usize], true))
+}
+
+// 角と辺の置換それぞれの座標（各 8! 状態）
+fn domino_coordinates() -> (Coordinate<[u8; 8]>, Coordinate<[u8; 8]>) {
+    let id = DominoCube::identity();
+    let corners = Coordinate::build(id.corner_perm, DOMINO_MOVES.len(), |&perm, m| {
+        DominoCube {
+            corner_perm: perm,
+            ..id
+        }
+        .apply_alg(&[DOMINO_MOVES[m]])
+        .corner_perm
+    });
+    let edges = Coordinate::build(id.edge_perm, DOMINO_MOVES.len(), |&perm, m| {
+        DominoCube {
+            edge_perm: perm,
+            ..id
+        }
+        .apply_alg(&[DOMINO_MOVES[m]])
+        .edge_perm
+    });
+    (corners, edges)
+}
+
+/// ドミノの角と辺それぞれの最短手数表（各 8! 状態）
+pub struct DominoTables {
+    corners: Coordinate<[u8; 8]>,
+    edges: Coordinate<[u8; 8]>,
+    corner_distance: Vec<u8>,
+    edge_distance: Vec<u8>,
+}
+
+impl DominoTables {
+    pub fn build() -> Self {
+        let (corners, edges) = domino_coordinates();
+        DominoTables {
+            corner_distance: corners.distances(),
+            edge_distance: edges.distances(),
+            corners,
+            edges,
+        }
+    }
+
+    /// 最短手数
+    pub fn distance(&self, cube: &DominoCube) -> Result<usize, SolveError> {
+        Ok(self.solve(cube)?.len())
+    }
+
+    /// 最短手数の解（角と辺の最短手数の大きい方を下界にした反復深化）
+    pub fn solve(&self, cube: &DominoCube) -> Result<Vec<Move>, SolveError> {
+        if !cube.is_legal() {
+            return Err(SolveError::Illegal);
+        }
+        let c = self.corners.index(&cube.corner_perm);
+        let e = self.edges.index(&cube.edge_perm);
+        let (c, e) = c.zip(e).ok_or(SolveError::InvalidTables)?;
+        let mut path = Vec::new();
+        for depth in 0.. {
+            if self.search(c, e, depth, None, &mut path) {
+                return Ok(path.into_iter().map(|m| DOMINO_MOVES[m]).collect());
+            }
+        }
+        unreachable!()
+    }
+
+    fn search(
+        &self,
+        c: usize,
+        e: usize,
+        depth: u8,
+        last: Option<usize>,
+        path: &mut Vec<usize>,
+    ) -> bool {
+        let h = self.corner_distance[c].max(self.edge_distance[e]);
+        if h > depth {
+            return false;
+        }
+        if depth == 0 {
+            return true;
+        }
+        for (m, mv) in DOMINO_MOVES.iter().enumerate() {
+            // 同じ面を続けず, 向かい合う面は U → D のような順にだけ回す
+            let face = mv.face() as usize;
+            if let Some(l) = last {
+                if l == face || (l == face ^ 1 && l > face) {
+                    continue;
+                }
+            }
+            path.push(m);
+            if self.search(
+                self.corners.step(c, m),
+                self.edges.step(e, m),
+                depth - 1,
+                Some(face),
+                path,
+            ) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+}
+
+/// ドミノの全状態（8! * 8!）の最短手数表. 1 状態 2 ビットで手数 mod 3 を持つ（約 400MB）.
+/// 作るのに数分かかるので, 最短解だけなら `DominoTables` を使う
+pub struct DominoDistanceTable {
+    corners: Coordinate<[u8; 8]>,
+    edges: Coordinate<[u8; 8]>,
+    data: Vec<u8>,
+    depth_counts: Vec<u64>,
+}
+
+// まだ到達していない状態
+const UNSEEN: u8 = 3;
+
+impl DominoDistanceTable {
+    /// 幅優先探索で作る. 半分以上の状態に届いた後は, 未到達の状態から前の層を探す
+    pub fn build() -> Self {
+        let (corners, edges) = domino_coordinates();
+        let n = corners.len() * edges.len();
+        let mut table = DominoDistanceTable {
+            data: vec![0xff; n.div_ceil(4)],
+            depth_counts: vec![1],
+            corners,
+            edges,
+        };
+        table.set(0, 0);
+        let mut seen = 1u64;
+        for depth in 0.. {
+            let (cur, next) = (depth % 3, (depth + 1) % 3);
+            let backward = seen * 2 > n as u64;
+            let mut count = 0u64;
+            for i in 0..n {
+                let v = table.get(i);
+                if backward && v == UNSEEN {
+                    if (0..DOMINO_MOVES.len()).any(|m| table.get(table.step(i, m)) == cur) {
+                        table.set(i, next);
+                        count += 1;
+                    }
+                } else if !backward && v == cur {
+                    for m in 0..DOMINO_MOVES.len() {
+                        let j = table.step(i, m);
+                        if table.get(j) == UNSEEN {
+                            table.set(j, next);
+                            count += 1;
+                        }
+                    }
+                }
+            }
+            if count == 0 {
+                break;
+            }
+            seen += count;
+            table.depth_counts.push(count);
+        }
+        table
+    }
+
+    fn get(&self, i: usize) -> u8 {
+        (self.data[i / 4] >> (i % 4 * 2)) & 3
+    }
+
+    fn set(&mut self, i: usize, v: u8) {
+        let shift = i % 4 * 2;
+        self.data[i / 4] = (self.data[i / 4] & !(3 << shift)) | (v << shift);
+    }
+
+    // 状態 i に手 m を適用した状態（番号は 角 * 8! + 辺）
+    fn step(&self, i: usize, m: usize) -> usize {
+        let n = self.edges.len();
+        self.corners.step(i / n, m) * n + self.edges.step(i % n, m)
+    }
+
+    /// 状態の数
+    pub fn len(&self) -> u64 {
+        self.depth_counts.iter().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 最長の最短手数（God's number）
+    pub fn diameter(&self) -> usize {
+        self.depth_counts.len() - 1
+    }
+
+    /// 手数ごとの状態の数
+    pub fn depth_counts(&self) -> &[u64] {
+        &self.depth_counts
+    }
+
+    /// 最短手数
+    pub fn distance(&self, cube: &DominoCube) -> Result<usize, SolveError> {
+        Ok(self.solve(cube)?.len())
+    }
+
+    /// 最短解. 手数 mod 3 が 1 小さい隣の状態をたどる
+    pub fn solve(&self, cube: &DominoCube) -> Result<Vec<Move>, SolveError> {
+        if !cube.is_legal() {
+            return Err(SolveError::Illegal);
+        }
+        let c = self.corners.index(&cube.corner_perm);
+        let e = self.edges.index(&cube.edge_perm);
+        let (c, e) = c.zip(e).ok_or(SolveError::InvalidTables)?;
+        let mut i = c * self.edges.len() + e;
+        let mut solution = Vec::new();
+        while i != 0 {
+            let prev = (self.get(i) + 2) % 3;
+            let m = (0..DOMINO_MOVES.len())
+                .find(|&m| self.get(self.step(i, m)) == prev)
+                .ok_or(SolveError::InvalidTables)?;
+            solution.push(DOMINO_MOVES[m]);
+            i = self.step(i, m);
+        }
+        Ok(solution)
+    }
+}
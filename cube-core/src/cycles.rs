@@ -4,7 +4,16 @@
 use crate::Cube;
 
 pub fn corner_cycles(cube: &Cube) -> Vec<Vec<u8>> {
-    let perm = &cube.corner_perm;
+    perm_cycles(&cube.corner_perm)
+}
+
+// edge 用も同じように書ける
+pub fn edge_cycles(cube: &Cube) -> Vec<Vec<u8>> {
+    perm_cycles(&cube.edge_perm)
+}
+
+// 置換 perm（perm[位置] = パーツ）の長さ 2 以上のサイクル
+pub fn perm_cycles(perm: &[u8]) -> Vec<Vec<u8>> {
     let n = perm.len();
     let mut visited = vec![false; n];
     let mut cycles = Vec::new();
@@ -26,26 +35,23 @@ pub fn corner_cycles(cube: &Cube) -> Vec<Vec<u8>> {
     cycles
 }
 
-// edge 用も同じように書ける
-pub fn edge_cycles(cube: &Cube) -> Vec<Vec<u8>> {
-    let perm = &cube.edge_perm;
+// サイクルタイプ: counts[k] は長さ k + 1 のサイクルの数（counts[0] は動いていないパーツの数）
+pub fn cycle_type(perm: &[u8]) -> Vec<u8> {
     let n = perm.len();
     let mut visited = vec![false; n];
-    let mut cycles = Vec::new();
+    let mut counts = vec![0; n];
 
     for i in 0..n {
-        if !visited[i] && perm[i] as usize != i {
-            let mut cycle = Vec::new();
+        if !visited[i] {
+            let mut len = 0;
             let mut j = i;
             while !visited[j] {
                 visited[j] = true;
-                cycle.push(j as u8);
                 j = perm[j] as usize;
+                len += 1;
             }
-            if cycle.len() > 1 {
-                cycles.push(cycle);
-            }
+            counts[len - 1] += 1;
         }
     }
-    cycles
+    counts
 }
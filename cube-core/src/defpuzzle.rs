@@ -0,0 +1,594 @@
+//! defpuzzle.rs: KSolve / twsearch 形式の定義ファイルで与えるパズル
+//!
+//! 定義ファイルは空白区切りで, `#` から行末まではコメント.
+//!
+//! ```text
+//! Name 3x3x3
+//! Set CORNERS 8 3          # 軌道の名前, パーツの数, 向きの数
+//! Set EDGES 12 2
+//! Solved                   # 省略すると 1 2 3 ... の並びで向きは 0
+//! CORNERS
+//! 1 2 3 4 5 6 7 8
+//! End
+//! Move U
+//! CORNERS
+//! 2 3 4 1 5 6 7 8          # 位置 i に来るパーツが元あった位置
+//! 0 0 0 0 0 0 0 0          # 位置 i に来たパーツの向きの変化（省略すると 0）
+//! End
+//! ```
+//!
+//! 手 m を状態 s に適用すると `perm[i] = s.perm[m.perm[i]]`,
+//! `ori[i] = s.ori[m.perm[i]] + m.ori[i]` となり, `Cube` の角・辺と同じ約束になる.
+//! 書かれていない軌道は動かさない. 手の 2 乗や逆（`U2`, `U'`）は手の位数から作る.
+//! 揃った状態には同じ番号を何度書いてもよい（区別しないパーツ）.
+
+use std::fmt::Write;
+
+use thiserror::Error;
+
+use crate::notation::{tokens, ParseError};
+use crate::puzzle::{self, OrbitInfo, Puzzle, ThreeByThree};
+use crate::state::parity;
+use crate::Move;
+
+/// 定義ファイルを読めなかった理由. `line` は 1 始まりの行番号
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum DefError {
+    #[error("line {line}: unexpected `{token}`")]
+    UnexpectedToken { line: usize, token: String },
+    #[error("unexpected end of file (expected {expected})")]
+    UnexpectedEnd { expected: &'static str },
+    #[error("line {line}: invalid number `{token}`")]
+    InvalidNumber { line: usize, token: String },
+    #[error("line {line}: unknown set `{name}`")]
+    UnknownSet { line: usize, name: String },
+    #[error("line {line}: `{name}` is defined twice")]
+    Duplicate { line: usize, name: String },
+    #[error("line {line}: set `{set}` has an invalid permutation")]
+    InvalidPermutation { line: usize, set: String },
+    #[error("line {line}: set `{set}` has an orientation out of range")]
+    InvalidOrientation { line: usize, set: String },
+    #[error("line {line}: move `{name}` has an order larger than 255")]
+    OrderTooLarge { line: usize, name: String },
+    #[error("line {line}: more than {MAX_SETS} sets are defined")]
+    TooManySets { line: usize },
+    #[error("no sets are defined")]
+    NoSets,
+}
+
+/// 定義できる軌道の数の上限（合法性の判定で置換の偶奇を 1 つの u64 に詰める）
+const MAX_SETS: usize = 64;
+
+/// 実行時に定義されたパズルの状態（軌道ごとの置換と向き. 番号は 0 始まり）
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DefState {
+    pub perm: Vec<Vec<u8>>,
+    pub ori: Vec<Vec<u8>>,
+}
+
+/// 定義された手 `index` を `power` 回
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DefMove {
+    pub index: usize,
+    pub power: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct MoveDef {
+    name: String,
+    effect: DefState,
+    order: u8,
+}
+
+/// 定義ファイルから読んだパズル
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefPuzzle {
+    name: String,
+    sets: Vec<OrbitInfo>,
+    solved: DefState,
+    moves: Vec<MoveDef>,
+}
+
+impl DefPuzzle {
+    /// 定義ファイルを読む
+    pub fn parse(text: &str) -> Result<DefPuzzle, DefError> {
+        Parser::new(text).parse()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 定義された手の名前（定義順）
+    pub fn move_names(&self) -> Vec<&str> {
+        self.moves.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    /// 手 index の位数
+    pub fn move_order(&self, index: usize) -> u8 {
+        self.moves[index].order
+    }
+
+    /// `U`, `U2`, `U'` のような手順を読む（手の名前は定義ファイルのもの）
+    pub fn parse_alg(&self, s: &str) -> Result<Vec<DefMove>, ParseError> {
+        tokens(s)
+            .enumerate()
+            .map(|(index, (offset, token))| {
+                self.parse_move(token).ok_or_else(|| ParseError {
+                    token: token.to_string(),
+                    index,
+                    offset,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_move(&self, token: &str) -> Option<DefMove> {
+        let find = |name: &str| self.moves.iter().position(|m| m.name == name);
+        // 名前そのものを先に探す（数字で終わる名前もあるため）
+        if let Some(index) = find(token) {
+            return Some(DefMove { index, power: 1 });
+        }
+        let (body, inverse) = match token.strip_suffix('\'') {
+            Some(b) => (b, true),
+            None => (token, false),
+        };
+        let (index, power) = match find(body) {
+            Some(index) => (index, 1),
+            None => {
+                let split = body.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+                let index = find(&body[..split])?;
+                (index, body[split..].parse::<u32>().ok()?)
+            }
+        };
+        let order = self.moves[index].order as u32;
+        let power = if inverse {
+            (order - power % order) % order
+        } else {
+            power % order
+        };
+        (power != 0).then_some(DefMove {
+            index,
+            power: power as u8,
+        })
+    }
+
+    /// 手順を定義ファイルの名前で書く
+    pub fn format_alg(&self, alg: &[DefMove]) -> String {
+        alg.iter()
+            .map(|m| {
+                let def = &self.moves[m.index];
+                match m.power {
+                    1 => def.name.clone(),
+                    p if p + 1 == def.order => format!("{}'", def.name),
+                    p => format!("{}{}", def.name, p),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// 定義ファイルの形式で書き出す
+    pub fn to_def(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "Name {}", self.name).unwrap();
+        writeln!(out).unwrap();
+        for set in &self.sets {
+            writeln!(out, "Set {} {} {}", set.name, set.pieces, set.orientations).unwrap();
+        }
+        writeln!(out).unwrap();
+        write_block(&mut out, "Solved", &self.sets, &self.solved);
+        for m in &self.moves {
+            writeln!(out).unwrap();
+            write_block(&mut out, &format!("Move {}", m.name), &self.sets, &m.effect);
+        }
+        out
+    }
+}
+
+// 状態 s に効果 effect を適用する
+fn compose(s: &DefState, effect: &DefState, sets: &[OrbitInfo]) -> DefState {
+    let mut r = s.clone();
+    for (k, set) in sets.iter().enumerate() {
+        for i in 0..set.pieces {
+            let from = effect.perm[k][i] as usize;
+            r.perm[k][i] = s.perm[k][from];
+            r.ori[k][i] = (s.ori[k][from] + effect.ori[k][i]) % set.orientations;
+        }
+    }
+    r
+}
+
+fn identity_state(sets: &[OrbitInfo]) -> DefState {
+    DefState {
+        perm: sets.iter().map(|s| (0..s.pieces as u8).collect()).collect(),
+        ori: sets.iter().map(|s| vec![0; s.pieces]).collect(),
+    }
+}
+
+fn write_block(out: &mut String, header: &str, sets: &[OrbitInfo], state: &DefState) {
+    writeln!(out, "{}", header).unwrap();
+    for (k, set) in sets.iter().enumerate() {
+        writeln!(out, "{}", set.name).unwrap();
+        let line = |values: &[u8], offset: u8| {
+            values
+                .iter()
+                .map(|v| (v + offset).to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        writeln!(out, "{}", line(&state.perm[k], 1)).unwrap();
+        if set.orientations > 1 {
+            writeln!(out, "{}", line(&state.ori[k], 0)).unwrap();
+        }
+    }
+    writeln!(out, "End").unwrap();
+}
+
+impl Puzzle for DefPuzzle {
+    type Move = DefMove;
+    type State = DefState;
+
+    fn identity(&self) -> DefState {
+        self.solved.clone()
+    }
+
+    fn apply(&self, state: &mut DefState, m: DefMove) {
+        let effect = &self.moves[m.index].effect;
+        for _ in 0..m.power {
+            *state = compose(state, effect, &self.sets);
+        }
+    }
+
+    fn inverse(&self, m: DefMove) -> DefMove {
+        DefMove {
+            power: self.moves[m.index].order - m.power,
+            ..m
+        }
+    }
+
+    fn is_solved(&self, state: &DefState) -> bool {
+        *state == self.solved
+    }
+
+    /// 手で変わらない量だけを見る（必要条件）: パーツの組, 向きの総和,
+    /// 区別できるパーツだけの軌道の置換の偶奇の組み合わせ.
+    /// true でも手で作れるとは限らない（確かめるには `DistanceTable` などで探索する）
+    fn is_legal(&self, state: &DefState) -> bool {
+        let shapes_ok = self.sets.iter().enumerate().all(|(k, set)| {
+            let mut have = state.perm[k].clone();
+            let mut want = self.solved.perm[k].clone();
+            have.sort_unstable();
+            want.sort_unstable();
+            have == want
+                && state.ori[k].len() == set.pieces
+                && state.ori[k].iter().all(|&o| o < set.orientations)
+        });
+        if !shapes_ok {
+            return false;
+        }
+
+        // 向きの総和は, 手の向きの変化の総和の gcd の倍数だけ変わる
+        let twist_ok = self.sets.iter().enumerate().all(|(k, set)| {
+            let n = set.orientations as u32;
+            let sum = |ori: &[u8]| ori.iter().map(|&o| o as u32).sum::<u32>() % n;
+            let step = self
+                .moves
+                .iter()
+                .fold(n, |g, m| gcd(g, sum(&m.effect.ori[k])));
+            (sum(&state.ori[k]) + n - sum(&self.solved.ori[k])).is_multiple_of(step)
+        });
+
+        // 置換の偶奇のベクトルが, 手の偶奇のベクトルの張る空間に入るか (GF(2))
+        let distinct: Vec<bool> = self
+            .solved
+            .perm
+            .iter()
+            .map(|p| {
+                let mut sorted = p.clone();
+                sorted.sort_unstable();
+                sorted.windows(2).all(|w| w[0] != w[1])
+            })
+            .collect();
+        let parity_vector = |perm: &[Vec<u8>]| -> u64 {
+            perm.iter()
+                .enumerate()
+                .filter(|&(k, _)| distinct[k])
+                .fold(0, |acc, (k, p)| acc | (parity(p) as u64) << k)
+        };
+        let mut basis: Vec<u64> = Vec::new();
+        for m in &self.moves {
+            let mut v = parity_vector(&m.effect.perm);
+            for &b in &basis {
+                v = v.min(v ^ b);
+            }
+            if v != 0 {
+                basis.push(v);
+                basis.sort_unstable_by(|a, b| b.cmp(a));
+            }
+        }
+        let relative: Vec<Vec<u8>> = state
+            .perm
+            .iter()
+            .zip(&self.solved.perm)
+            .zip(&distinct)
+            .map(|((p, s), &d)| {
+                if d {
+                    // 揃った状態からの相対的な置換
+                    p.iter()
+                        .map(|x| s.iter().position(|y| y == x).unwrap() as u8)
+                        .collect()
+                } else {
+                    p.clone()
+                }
+            })
+            .collect();
+        let mut v = parity_vector(&relative);
+        for &b in &basis {
+            v = v.min(v ^ b);
+        }
+        twist_ok && v == 0
+    }
+
+    fn moves(&self) -> Vec<DefMove> {
+        self.moves
+            .iter()
+            .enumerate()
+            .flat_map(|(index, m)| (1..m.order).map(move |power| DefMove { index, power }))
+            .collect()
+    }
+
+    fn orbits(&self) -> Vec<OrbitInfo> {
+        self.sets.clone()
+    }
+
+    fn orbit_perm(&self, state: &DefState, orbit: usize) -> Vec<u8> {
+        state.perm[orbit].clone()
+    }
+
+    fn orbit_ori(&self, state: &DefState, orbit: usize) -> Vec<u8> {
+        state.ori[orbit].clone()
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, &'a str)>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        let tokens = text
+            .lines()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                let line = line.split('#').next().unwrap_or("");
+                line.split_whitespace().map(move |t| (i + 1, t))
+            })
+            .collect();
+        Parser { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<(usize, &'a str)> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<(usize, &'a str), DefError> {
+        let t = self.peek().ok_or(DefError::UnexpectedEnd { expected })?;
+        self.pos += 1;
+        Ok(t)
+    }
+
+    fn number(&mut self, expected: &'static str) -> Result<usize, DefError> {
+        Ok(self.number_with_line(expected)?.1)
+    }
+
+    // 数とそれがある行
+    fn number_with_line(&mut self, expected: &'static str) -> Result<(usize, usize), DefError> {
+        let (line, token) = self.next(expected)?;
+        let n = token.parse().map_err(|_| DefError::InvalidNumber {
+            line,
+            token: token.to_string(),
+        })?;
+        Ok((line, n))
+    }
+
+    fn parse(mut self) -> Result<DefPuzzle, DefError> {
+        let mut name = String::new();
+        let mut sets: Vec<OrbitInfo> = Vec::new();
+        let mut solved = None;
+        let mut moves: Vec<MoveDef> = Vec::new();
+
+        while let Some((line, keyword)) = self.peek() {
+            self.pos += 1;
+            match keyword {
+                "Name" => name = self.next("a name")?.1.to_string(),
+                "Set" => {
+                    let (line, set) = self.next("a set name")?;
+                    let pieces = self.number("a piece count")?;
+                    let orientations = self.number("an orientation count")?;
+                    if sets.iter().any(|s| s.name == set) {
+                        return Err(DefError::Duplicate {
+                            line,
+                            name: set.to_string(),
+                        });
+                    }
+                    if sets.len() == MAX_SETS {
+                        return Err(DefError::TooManySets { line });
+                    }
+                    if !(1..=255).contains(&pieces) || !(1..=255).contains(&orientations) {
+                        return Err(DefError::InvalidNumber {
+                            line,
+                            token: format!("{} {}", pieces, orientations),
+                        });
+                    }
+                    sets.push(OrbitInfo {
+                        name: set.to_string(),
+                        pieces,
+                        orientations: orientations as u8,
+                    });
+                }
+                "Solved" => {
+                    if solved.is_some() {
+                        return Err(DefError::Duplicate {
+                            line,
+                            name: keyword.to_string(),
+                        });
+                    }
+                    solved = Some(self.block(&sets, false)?);
+                }
+                "Move" => {
+                    let (line, move_name) = self.next("a move name")?;
+                    if moves.iter().any(|m| m.name == move_name) {
+                        return Err(DefError::Duplicate {
+                            line,
+                            name: move_name.to_string(),
+                        });
+                    }
+                    let effect = self.block(&sets, true)?;
+                    let order = move_order(&effect, &sets).ok_or(DefError::OrderTooLarge {
+                        line,
+                        name: move_name.to_string(),
+                    })?;
+                    moves.push(MoveDef {
+                        name: move_name.to_string(),
+                        effect,
+                        order,
+                    });
+                }
+                _ => {
+                    return Err(DefError::UnexpectedToken {
+                        line,
+                        token: keyword.to_string(),
+                    })
+                }
+            }
+        }
+
+        if sets.is_empty() {
+            return Err(DefError::NoSets);
+        }
+        let solved = solved.unwrap_or_else(|| identity_state(&sets));
+        Ok(DefPuzzle {
+            name,
+            sets,
+            solved,
+            moves,
+        })
+    }
+
+    // `<set> <perm...> [<ori...>]` の並びを `End` まで読む.
+    // 手の置換は 1..=n の並べ替えでなければならない
+    fn block(&mut self, sets: &[OrbitInfo], is_move: bool) -> Result<DefState, DefError> {
+        let mut state = identity_state(sets);
+        loop {
+            let (line, token) = self.next("`End`")?;
+            if token == "End" {
+                return Ok(state);
+            }
+            let k = sets
+                .iter()
+                .position(|s| s.name == token)
+                .ok_or(DefError::UnknownSet {
+                    line,
+                    name: token.to_string(),
+                })?;
+            let set = &sets[k];
+            // エラーの行は不正な数のある行
+            let invalid_perm = |line| DefError::InvalidPermutation {
+                line,
+                set: set.name.clone(),
+            };
+
+            let mut perm = Vec::with_capacity(set.pieces);
+            let mut seen = vec![false; set.pieces];
+            for _ in 0..set.pieces {
+                let (line, v) = self.number_with_line("a piece number")?;
+                if !(1..=set.pieces).contains(&v) {
+                    return Err(invalid_perm(line));
+                }
+                // 手の置換は 1..=n の並べ替えなので, 同じ番号は 2 度現れない
+                if is_move && std::mem::replace(&mut seen[v - 1], true) {
+                    return Err(invalid_perm(line));
+                }
+                perm.push((v - 1) as u8);
+            }
+            state.perm[k] = perm;
+
+            // 向きの行は省略できる
+            if self.peek().is_some_and(|(_, t)| t.parse::<usize>().is_ok()) {
+                for i in 0..set.pieces {
+                    let (line, o) = self.number_with_line("an orientation")?;
+                    if o >= set.orientations as usize {
+                        return Err(DefError::InvalidOrientation {
+                            line,
+                            set: set.name.clone(),
+                        });
+                    }
+                    state.ori[k][i] = o as u8;
+                }
+            }
+        }
+    }
+}
+
+// 手の位数（255 を超えるものは扱わない）
+fn move_order(effect: &DefState, sets: &[OrbitInfo]) -> Option<u8> {
+    let start = identity_state(sets);
+    let mut state = effect.clone();
+    for order in 1..=255 {
+        if state == start {
+            return Some(order);
+        }
+        state = compose(&state, effect, sets);
+    }
+    None
+}
+
+/// 任意の `Puzzle` を定義ファイルにする. 手は名前と 1 手の組で与える
+pub fn puzzle_to_def<P: Puzzle>(puzzle: &P, name: &str, moves: &[(&str, P::Move)]) -> String {
+    let sets = puzzle.orbits();
+    let state = |s: &P::State| DefState {
+        perm: (0..sets.len()).map(|k| puzzle.orbit_perm(s, k)).collect(),
+        ori: (0..sets.len()).map(|k| puzzle.orbit_ori(s, k)).collect(),
+    };
+    let identity = puzzle.identity();
+    let def = DefPuzzle {
+        name: name.to_string(),
+        sets: sets.clone(),
+        solved: state(&identity),
+        moves: moves
+            .iter()
+            .map(|&(move_name, m)| {
+                let effect = state(&puzzle::apply(puzzle, &identity, &[m]));
+                MoveDef {
+                    name: move_name.to_string(),
+                    order: move_order(&effect, &sets).unwrap_or(1),
+                    effect,
+                }
+            })
+            .collect(),
+    };
+    def.to_def()
+}
+
+/// 3x3x3 の定義ファイル（軌道 corners, edges は `Cube` と同じ番号と向き）
+pub fn three_by_three_def() -> String {
+    let moves = [
+        ("U", Move::U),
+        ("D", Move::D),
+        ("L", Move::L),
+        ("R", Move::R),
+        ("F", Move::F),
+        ("B", Move::B),
+    ];
+    puzzle_to_def(&ThreeByThree, "3x3x3", &moves)
+}
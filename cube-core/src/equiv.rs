@@ -0,0 +1,89 @@
+//! equiv.rs: 2 つの手順が同じ効果を持つかの判定
+
+use crate::symmetry::Rotation;
+use crate::{apply_alg, Cube, Move};
+
+/// 比較時にどこまでの違いを許すか. `Default` は完全一致
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EquivOptions {
+    /// 前に U 面の調整 (AUF) を入れてよい
+    pub pre_auf: bool,
+    /// 後に U 面の調整 (AUF) を入れてよい
+    pub post_auf: bool,
+    /// 持ち替え（24 通りの回転で読み替え）てよい
+    pub symmetry: bool,
+}
+
+impl EquivOptions {
+    /// 完全一致のみ
+    pub fn exact() -> Self {
+        Self::default()
+    }
+
+    /// 前後の AUF を許す
+    pub fn modulo_auf() -> Self {
+        EquivOptions {
+            pre_auf: true,
+            post_auf: true,
+            symmetry: false,
+        }
+    }
+
+    /// 持ち替えを許す
+    pub fn modulo_symmetry() -> Self {
+        EquivOptions {
+            pre_auf: false,
+            post_auf: false,
+            symmetry: true,
+        }
+    }
+}
+
+/// 一致したときの調整内容: `pre_auf` + rotation(a) + `post_auf` が b と同じ状態になる
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Equivalence {
+    pub pre_auf: Option<Move>,
+    pub post_auf: Option<Move>,
+    pub rotation: Rotation,
+}
+
+const AUFS: [Option<Move>; 4] = [None, Some(Move::U), Some(Move::Up), Some(Move::U2)];
+
+/// 手順 a と b が（options の範囲で）同じ状態を作るか調べる.
+/// 調整の少ないものから順に試し, 最初に見つかったものを返す
+pub fn alg_equivalent(a: &[Move], b: &[Move], options: &EquivOptions) -> Option<Equivalence> {
+    let target = apply_alg(&Cube::identity(), b);
+
+    let rotations = if options.symmetry {
+        Rotation::all()
+    } else {
+        vec![Rotation::identity()]
+    };
+    let pres: &[Option<Move>] = if options.pre_auf { &AUFS } else { &AUFS[..1] };
+    let posts: &[Option<Move>] = if options.post_auf { &AUFS } else { &AUFS[..1] };
+
+    for rotation in rotations {
+        let rotated = rotation.apply_alg(a);
+        for &pre_auf in pres {
+            let mut start = Cube::identity();
+            if let Some(m) = pre_auf {
+                start.apply_move(m);
+            }
+            let mid = apply_alg(&start, &rotated);
+            for &post_auf in posts {
+                let mut end = mid;
+                if let Some(m) = post_auf {
+                    end.apply_move(m);
+                }
+                if end == target {
+                    return Some(Equivalence {
+                        pre_auf,
+                        post_auf,
+                        rotation,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
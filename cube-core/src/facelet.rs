@@ -0,0 +1,116 @@
+//! facelet.rs: ステッカー（面の色）による表現
+//!
+//! 角は U/D 面のステッカーを先頭に外から見て時計回り, 辺は向きの基準となる面を先頭に並べる.
+//! corner_ori = o のとき, パーツの k 番目のステッカーは位置の (k + o) % 3 番目の面に来る
+//! （辺も同様に mod 2）.
+
+use crate::moves::Face;
+use crate::Cube;
+
+use Face::*;
+
+/// 各角位置の面（state.rs の corner index 順）
+pub const CORNER_FACELETS: [[Face; 3]; 8] = [
+    [U, F, L],
+    [U, R, F],
+    [U, B, R],
+    [U, L, B],
+    [D, L, F],
+    [D, F, R],
+    [D, R, B],
+    [D, B, L],
+];
+
+/// 各辺位置の面（state.rs の edge index 順）
+pub const EDGE_FACELETS: [[Face; 2]; 12] = [
+    [U, F],
+    [U, R],
+    [U, B],
+    [U, L],
+    [F, L],
+    [F, R],
+    [B, R],
+    [B, L],
+    [D, F],
+    [D, R],
+    [D, B],
+    [D, L],
+];
+
+/// 位置ごとのステッカーの色（色は揃ったときの面で表す）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Facelets {
+    pub corners: [[Face; 3]; 8],
+    pub edges: [[Face; 2]; 12],
+}
+
+impl Facelets {
+    /// 面 f に見えている 8 枚のステッカー（センターを除く）
+    pub fn face(&self, f: Face) -> Vec<Face> {
+        let corners = (0..8).flat_map(|i| (0..3).map(move |j| (i, j)));
+        let edges = (0..12).flat_map(|i| (0..2).map(move |j| (i, j)));
+        corners
+            .filter(|&(i, j)| CORNER_FACELETS[i][j] == f)
+            .map(|(i, j)| self.corners[i][j])
+            .chain(
+                edges
+                    .filter(|&(i, j)| EDGE_FACELETS[i][j] == f)
+                    .map(|(i, j)| self.edges[i][j]),
+            )
+            .collect()
+    }
+}
+
+impl Cube {
+    /// ステッカー表現に変換
+    pub fn to_facelets(&self) -> Facelets {
+        let mut corners = CORNER_FACELETS;
+        for i in 0..8 {
+            let piece = CORNER_FACELETS[self.corner_perm[i] as usize];
+            let o = self.corner_ori[i] as usize;
+            for (k, &color) in piece.iter().enumerate() {
+                corners[i][(k + o) % 3] = color;
+            }
+        }
+        let mut edges = EDGE_FACELETS;
+        for i in 0..12 {
+            let piece = EDGE_FACELETS[self.edge_perm[i] as usize];
+            let o = self.edge_ori[i] as usize;
+            for (k, &color) in piece.iter().enumerate() {
+                edges[i][(k + o) % 2] = color;
+            }
+        }
+        Facelets { corners, edges }
+    }
+
+    /// ステッカー表現から復元. 存在しないパーツや重複があれば None
+    /// （向きや偶奇の合法性は検査しない）
+    pub fn from_facelets(f: &Facelets) -> Option<Cube> {
+        let mut cube = Cube::identity();
+        for i in 0..8 {
+            let colors = f.corners[i];
+            let o = colors.iter().position(|&c| c == U || c == D)?;
+            let piece = CORNER_FACELETS
+                .iter()
+                .position(|p| (0..3).all(|k| p[k] == colors[(k + o) % 3]))?;
+            cube.corner_perm[i] = piece as u8;
+            cube.corner_ori[i] = o as u8;
+        }
+        for i in 0..12 {
+            let colors = f.edges[i];
+            let (piece, o) = EDGE_FACELETS.iter().enumerate().find_map(|(p, e)| {
+                (0..2)
+                    .find(|&o| (0..2).all(|k| e[k] == colors[(k + o) % 2]))
+                    .map(|o| (p, o))
+            })?;
+            cube.edge_perm[i] = piece as u8;
+            cube.edge_ori[i] = o as u8;
+        }
+        let distinct = |perm: &[u8]| (0..perm.len() as u8).all(|p| perm.contains(&p));
+        if distinct(&cube.corner_perm) && distinct(&cube.edge_perm) {
+            Some(cube)
+        } else {
+            None
+        }
+    }
+}
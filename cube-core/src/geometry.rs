@@ -0,0 +1,275 @@
+//! geometry.rs: 3D 描画向けのパーツ（キュービー）の位置・回転・ステッカー
+//!
+//! 座標系は x = R, y = U, z = F 方向（Three.js と同じ右手系, y が上）.
+//! 各キュービーの回転は, 揃った状態での向きから現在の向きへの回転.
+
+use serde::Serialize;
+
+use crate::facelet::{CORNER_FACELETS, EDGE_FACELETS};
+use crate::moves::Face;
+use crate::{Cube, Move};
+
+/// パーツの種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PieceKind {
+    Corner,
+    Edge,
+    Center,
+}
+
+/// 1 枚のステッカー: 向いている面と色（色は揃ったときの面で表す）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Sticker {
+    pub face: Face,
+    pub color: Face,
+}
+
+/// 見えている 1 つのキュービー
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Cubie {
+    pub kind: PieceKind,
+    /// パーツの番号（揃った状態での位置）
+    pub piece: usize,
+    /// 現在の位置の番号
+    pub slot: usize,
+    /// 中心を原点とした座標（各成分 -1, 0, 1）
+    pub position: [i8; 3],
+    /// 回転行列（行優先）
+    pub rotation: [[i8; 3]; 3],
+    pub stickers: Vec<Sticker>,
+}
+
+/// 面の外向き法線
+pub fn normal(face: Face) -> [i8; 3] {
+    match face {
+        Face::U => [0, 1, 0],
+        Face::D => [0, -1, 0],
+        Face::L => [-1, 0, 0],
+        Face::R => [1, 0, 0],
+        Face::F => [0, 0, 1],
+        Face::B => [0, 0, -1],
+    }
+}
+
+/// 面の組が表す位置（法線の和）
+pub fn position(faces: &[Face]) -> [i8; 3] {
+    faces.iter().fold([0; 3], |mut p, &f| {
+        let n = normal(f);
+        for i in 0..3 {
+            p[i] += n[i];
+        }
+        p
+    })
+}
+
+fn cross(a: [i8; 3], b: [i8; 3]) -> [i8; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+// home[k] を to[k] へ移す回転行列（home は互いに直交する単位ベクトル）
+fn rotation_between(home: &[[i8; 3]], to: &[[i8; 3]]) -> [[i8; 3]; 3] {
+    let mut pairs: Vec<([i8; 3], [i8; 3])> = home.iter().copied().zip(to.iter().copied()).collect();
+    if pairs.len() == 2 {
+        pairs.push((cross(home[0], home[1]), cross(to[0], to[1])));
+    }
+    // R = Σ to_k home_k^T
+    let mut r = [[0; 3]; 3];
+    for (h, t) in pairs {
+        for i in 0..3 {
+            for j in 0..3 {
+                r[i][j] += t[i] * h[j];
+            }
+        }
+    }
+    r
+}
+
+impl Cubie {
+    /// 回転を四元数 [x, y, z, w] で表したもの
+    pub fn quaternion(&self) -> [f64; 4] {
+        let m = self.rotation.map(|row| row.map(f64::from));
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            [
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+                s / 4.0,
+            ]
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            [
+                s / 4.0,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[2][1] - m[1][2]) / s,
+            ]
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            [
+                (m[0][1] + m[1][0]) / s,
+                s / 4.0,
+                (m[1][2] + m[2][1]) / s,
+                (m[0][2] - m[2][0]) / s,
+            ]
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            [
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                s / 4.0,
+                (m[1][0] - m[0][1]) / s,
+            ]
+        }
+    }
+}
+
+impl Cube {
+    /// 見えている 26 個のキュービー（角 8, 辺 12, センター 6 の順）
+    pub fn cubies(&self) -> Vec<Cubie> {
+        let mut cubies = Vec::with_capacity(26);
+        for (slot, faces) in CORNER_FACELETS.iter().enumerate() {
+            let piece = self.corner_perm[slot] as usize;
+            let o = self.corner_ori[slot] as usize;
+            cubies.push(cubie(
+                PieceKind::Corner,
+                piece,
+                slot,
+                faces,
+                &CORNER_FACELETS[piece],
+                o,
+            ));
+        }
+        for (slot, faces) in EDGE_FACELETS.iter().enumerate() {
+            let piece = self.edge_perm[slot] as usize;
+            let o = self.edge_ori[slot] as usize;
+            cubies.push(cubie(
+                PieceKind::Edge,
+                piece,
+                slot,
+                faces,
+                &EDGE_FACELETS[piece],
+                o,
+            ));
+        }
+        for (i, face) in Face::ALL.into_iter().enumerate() {
+            cubies.push(cubie(PieceKind::Center, i, i, &[face], &[face], 0));
+        }
+        cubies
+    }
+}
+
+// パーツの k 番目のステッカーは位置の (k + o) 番目の面に来る（facelet.rs と同じ規約）
+fn cubie(
+    kind: PieceKind,
+    piece: usize,
+    slot: usize,
+    slot_faces: &[Face],
+    colors: &[Face],
+    o: usize,
+) -> Cubie {
+    let n = slot_faces.len();
+    let stickers: Vec<Sticker> = (0..n)
+        .map(|k| Sticker {
+            face: slot_faces[(k + o) % n],
+            color: colors[k],
+        })
+        .collect();
+    let rotation = if n == 1 {
+        [[1, 0, 0], [0, 1, 0], [0, 0, 1]]
+    } else {
+        let home: Vec<[i8; 3]> = colors.iter().map(|&f| normal(f)).collect();
+        let to: Vec<[i8; 3]> = stickers.iter().map(|s| normal(s.face)).collect();
+        rotation_between(&home, &to)
+    };
+    Cubie {
+        kind,
+        piece,
+        slot,
+        position: position(slot_faces),
+        rotation,
+        stickers,
+    }
+}
+
+/// 回転軸
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// アニメーション用の手の情報
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MoveInfo {
+    pub face: Face,
+    pub axis: Axis,
+    /// 回る層の軸方向の座標（-1 または 1）
+    pub layer: i8,
+    /// 面を外から見て時計回り 90 度単位の回転量 (1, 2, 3)
+    pub quarter_turns: u8,
+    /// 軸の正の向きまわりの回転角（ラジアン, 右手系, -π/2 ..= π）
+    pub angle: f64,
+    /// 動く角の位置を時計回りの巡回順に並べたもの.
+    /// 各位置のパーツは quarter_turns 個先の位置へ移る
+    pub corner_slots: [usize; 4],
+    /// 動く辺の位置（同じく巡回順）
+    pub edge_slots: [usize; 4],
+}
+
+impl Move {
+    pub fn info(self) -> MoveInfo {
+        let face = self.face();
+        let n = normal(face);
+        let (axis, k) = match n {
+            [_, 0, 0] => (Axis::X, 0),
+            [0, _, 0] => (Axis::Y, 1),
+            _ => (Axis::Z, 2),
+        };
+        let layer = n[k];
+        let quarter_turns = self.quarter_turns();
+        // 外から見て時計回り = 法線まわりに負の向き
+        let angle = match quarter_turns {
+            2 => std::f64::consts::PI,
+            q => (q as f64 - 2.0) * std::f64::consts::FRAC_PI_2 * f64::from(layer),
+        };
+
+        // 巡回順は時計回り 90 度の手から求める
+        let mut cube = Cube::identity();
+        cube.apply_move(Move::from_face(face, 1).unwrap());
+        let corner_slots = cycle_of(&cube.corner_perm, |i| CORNER_FACELETS[i].contains(&face));
+        let edge_slots = cycle_of(&cube.edge_perm, |i| EDGE_FACELETS[i].contains(&face));
+
+        MoveInfo {
+            face,
+            axis,
+            layer,
+            quarter_turns,
+            angle,
+            corner_slots,
+            edge_slots,
+        }
+    }
+}
+
+// 90 度の手の置換 perm（perm[移動先] = 移動元）で動く 4 つの位置を巡回順に並べる
+fn cycle_of(perm: &[u8], in_layer: impl Fn(usize) -> bool) -> [usize; 4] {
+    let start = (0..perm.len()).find(|&i| in_layer(i)).unwrap();
+    let mut slots = [start; 4];
+    for k in 1..4 {
+        // 前の位置のパーツが移る先
+        slots[k] = perm
+            .iter()
+            .position(|&p| p as usize == slots[k - 1])
+            .unwrap();
+    }
+    slots
+}
@@ -0,0 +1,195 @@
+//! history.rs: 手の履歴（元に戻す・やり直し・タイムラインの移動）
+//!
+//! 履歴は木で持つ. 戻した後に別の手を回すと新しい枝ができ, 元の枝も残る.
+//! タイムラインは根から「最後に通った子」をたどった 1 本の列.
+
+use thiserror::Error;
+
+use crate::{Cube, Move};
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("history index {index} is out of range (timeline has {len} moves)")]
+pub struct HistoryError {
+    pub index: usize,
+    pub len: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    cube: Cube,
+    parent: Option<usize>,
+    /// 親からこのノードへの手
+    mv: Option<Move>,
+    children: Vec<usize>,
+    /// やり直しで進む子
+    active: Option<usize>,
+}
+
+/// 手の履歴つきのキューブ
+#[derive(Clone, Debug)]
+pub struct History {
+    nodes: Vec<Node>,
+    current: usize,
+}
+
+impl History {
+    pub fn new(start: Cube) -> Self {
+        History {
+            nodes: vec![Node {
+                cube: start,
+                parent: None,
+                mv: None,
+                children: Vec::new(),
+                active: None,
+            }],
+            current: 0,
+        }
+    }
+
+    /// 現在の状態
+    pub fn cube(&self) -> &Cube {
+        &self.nodes[self.current].cube
+    }
+
+    /// 最初の状態
+    pub fn start(&self) -> &Cube {
+        &self.nodes[0].cube
+    }
+
+    /// 手を回す. 戻した後なら新しい枝を作る（同じ手の枝があればそれをたどる）
+    pub fn apply(&mut self, m: Move) {
+        let here = self.current;
+        let existing = self.nodes[here]
+            .children
+            .iter()
+            .copied()
+            .find(|&c| self.nodes[c].mv == Some(m));
+        let next = existing.unwrap_or_else(|| {
+            let mut cube = self.nodes[here].cube;
+            cube.apply_move(m);
+            self.nodes.push(Node {
+                cube,
+                parent: Some(here),
+                mv: Some(m),
+                children: Vec::new(),
+                active: None,
+            });
+            let id = self.nodes.len() - 1;
+            self.nodes[here].children.push(id);
+            id
+        });
+        self.nodes[here].active = Some(next);
+        self.current = next;
+    }
+
+    pub fn apply_alg(&mut self, moves: &[Move]) {
+        for &m in moves {
+            self.apply(m);
+        }
+    }
+
+    /// 1 手戻す. 戻した手を返す
+    pub fn undo(&mut self) -> Option<Move> {
+        let node = &self.nodes[self.current];
+        let parent = node.parent?;
+        let m = node.mv;
+        self.current = parent;
+        m
+    }
+
+    /// 1 手やり直す. 進めた手を返す
+    pub fn redo(&mut self) -> Option<Move> {
+        let next = self.nodes[self.current].active?;
+        self.current = next;
+        self.nodes[next].mv
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.current != 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.nodes[self.current].active.is_some()
+    }
+
+    /// タイムライン上の index 手目の後の状態へ移動する（0 は最初の状態）
+    pub fn seek(&mut self, index: usize) -> Result<(), HistoryError> {
+        let path = self.timeline_nodes();
+        let node = *path.get(index).ok_or(HistoryError {
+            index,
+            len: path.len() - 1,
+        })?;
+        self.current = node;
+        Ok(())
+    }
+
+    /// タイムラインの手の列（現在位置より先のやり直せる手も含む）
+    pub fn history(&self) -> Vec<Move> {
+        self.timeline_nodes()
+            .iter()
+            .filter_map(|&n| self.nodes[n].mv)
+            .collect()
+    }
+
+    /// タイムライン上の現在位置（適用済みの手数）
+    pub fn position(&self) -> usize {
+        self.ancestors(self.current).len() - 1
+    }
+
+    /// 現在位置から分かれる枝の最初の手（やり直しで進む枝が先頭）
+    pub fn branches(&self) -> Vec<Move> {
+        let node = &self.nodes[self.current];
+        node.active
+            .into_iter()
+            .chain(
+                node.children
+                    .iter()
+                    .copied()
+                    .filter(|&c| Some(c) != node.active),
+            )
+            .filter_map(|c| self.nodes[c].mv)
+            .collect()
+    }
+
+    /// 現在位置から m で始まる枝をやり直しの対象にする
+    pub fn switch_branch(&mut self, m: Move) -> bool {
+        let here = self.current;
+        match self.nodes[here]
+            .children
+            .iter()
+            .copied()
+            .find(|&c| self.nodes[c].mv == Some(m))
+        {
+            Some(c) => {
+                self.nodes[here].active = Some(c);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 根から node までのノード
+    fn ancestors(&self, node: usize) -> Vec<usize> {
+        let mut path = vec![node];
+        while let Some(p) = self.nodes[*path.last().unwrap()].parent {
+            path.push(p);
+        }
+        path.reverse();
+        path
+    }
+
+    // 根から現在位置を通り, やり直せる限り先までのノード
+    fn timeline_nodes(&self) -> Vec<usize> {
+        let mut path = self.ancestors(self.current);
+        while let Some(next) = self.nodes[*path.last().unwrap()].active {
+            path.push(next);
+        }
+        path
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(Cube::identity())
+    }
+}
@@ -24,6 +24,7 @@ fn permutation_parity(perm: &[u8]) -> i32 {
     sign
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct LegalityInfo {
     pub corner_parity: i32,
     pub edge_parity: i32,
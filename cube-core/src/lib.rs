@@ -1,12 +1,83 @@
 pub mod alg;
+pub mod bandage;
+pub mod beginner;
+pub mod bigcube;
+pub mod codec;
+mod coord;
+pub mod cuboid;
 pub mod cycles;
+pub mod defpuzzle;
+pub mod equiv;
+pub mod facelet;
+pub mod geometry;
+pub mod history;
 pub mod legality;
+pub mod megaminx;
 pub mod moves;
+pub mod notation;
+pub mod pocket;
+pub mod puzzle;
+pub mod pyraminx;
+pub mod random;
+pub mod recognition;
+pub mod recon;
+mod shape;
+pub mod skewb;
+pub mod solver;
+pub mod stages;
 pub mod state;
+pub mod supercube;
+pub mod symmetry;
+pub mod trainer;
+pub mod void;
 
 // 主要な型を再エクスポート
 pub use alg::{apply_alg, commutator, conjugate, invert_alg};
-pub use cycles::{corner_cycles, edge_cycles};
+pub use bandage::{Bandage, BandageError, BandageGraph, BandagePiece, BandagedCube};
+pub use beginner::{solve_beginner, SolveStep};
+pub use bigcube::{
+    format_big_alg, invert_big_alg, parse_big_alg, BigCube, BigMove, Orbit, OrbitKind,
+    OrbitLegality, OrbitProblem,
+};
+pub use codec::{decode_alg, decode_state, encode_alg, encode_state, CodecError};
+pub use cuboid::{
+    parse_domino_alg, parse_floppy_alg, DominoCube, DominoDistanceTable, DominoTables, FloppyCube,
+    DOMINO_MOVES, FLOPPY_MOVES,
+};
+pub use cycles::{corner_cycles, cycle_type, edge_cycles, perm_cycles};
+pub use defpuzzle::{three_by_three_def, DefError, DefMove, DefPuzzle, DefState};
+pub use equiv::{alg_equivalent, EquivOptions, Equivalence};
+pub use geometry::{Axis, Cubie, MoveInfo, PieceKind, Sticker};
+pub use history::{History, HistoryError};
 pub use legality::LegalityInfo;
-pub use moves::Move;
+pub use megaminx::{
+    format_megaminx_alg, format_pochmann, megaminx_corner_faces, megaminx_edge_faces,
+    parse_megaminx_alg, pochmann_scramble, MegaFace, Megaminx, MegaminxLegality, MegaminxMove,
+};
+pub use moves::{Face, Move};
+pub use notation::{format_alg, parse_alg, NotationStyle, ParseError};
+pub use pocket::{PocketCube, PocketTables};
+pub use puzzle::{
+    alg_order, group_report, orbit_cycles, BigCubeSize, DistanceTable, Domino, Floppy, GroupReport,
+    OrbitInfo, Pocket, Puzzle, ThreeByThree,
+};
+pub use pyraminx::{
+    format_pyraminx_alg, parse_pyraminx_alg, Pyraminx, PyraminxLegality, PyraminxMove,
+    PyraminxTables, Vertex,
+};
+pub use random::{random_state_scramble, scramble_for, Rng};
+pub use recognition::{recognize, CaseMatch, CaseSet};
+pub use recon::{analyze, parse_solution, Reconstruction};
+pub use skewb::{
+    format_skewb_alg, parse_skewb_alg, Skewb, SkewbAxis, SkewbLegality, SkewbMove, SkewbTables,
+};
+pub use solver::{solve, SolveError, SolverProgress, SolverSession, SolverTables};
+pub use stages::{Progress, Stage};
 pub use state::Cube;
+pub use supercube::{solve_super, SuperCube, SuperLegalityInfo};
+pub use symmetry::Rotation;
+pub use trainer::{Drill, DrillSet, Trainer};
+pub use void::{
+    apply_void_alg, format_void_alg, parse_void_alg, solve_in, CenterMode, Slice, SliceMove,
+    VoidMove,
+};
@@ -0,0 +1,390 @@
+//! megaminx.rs: メガミンクス
+//!
+//! 角 20 個（向き 0..3）と辺 30 個（向き 0..2）を `Cube` と同じ置換と向きの配列で持つ.
+//! 面は上の U とそのまわりの F, R, BR, BL, L, 下の D とそのまわりの B, DBL, DL, DR, DBR.
+//! `MegaFace::ALL` で i 番の面の向かいは i + 6 番.
+//!
+//! 面の手は 72 度単位で, `R` (時計回り), `R2` (144 度), `R2'`, `R'`.
+//! Pochmann 式の `R++` / `D--` は向かいの面の層を除いた全体を 144 度回す（`R+` / `R-` は 72 度）.
+//!
+//! パーツの番号は面の番号の組の辞書順. 向きの基準は番号が最小の面のステッカー.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::cycles::perm_cycles;
+use crate::notation::{tokens, ParseError};
+use crate::random::Rng;
+use crate::shape::{close, dot, normalize, rotate, turn, Piece, Turn, Vec3};
+use crate::state::parity;
+
+/// 1 行の手数（`R±± D±±` を 5 回）と行数
+const POCHMANN_LINE: usize = 10;
+const POCHMANN_LINES: usize = 7;
+
+/// メガミンクスの面
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MegaFace {
+    U,
+    F,
+    R,
+    BR,
+    BL,
+    L,
+    D,
+    B,
+    DBL,
+    DL,
+    DR,
+    DBR,
+}
+
+impl MegaFace {
+    pub const ALL: [MegaFace; 12] = [
+        MegaFace::U,
+        MegaFace::F,
+        MegaFace::R,
+        MegaFace::BR,
+        MegaFace::BL,
+        MegaFace::L,
+        MegaFace::D,
+        MegaFace::B,
+        MegaFace::DBL,
+        MegaFace::DL,
+        MegaFace::DR,
+        MegaFace::DBR,
+    ];
+
+    /// 向かいの面
+    pub fn opposite(self) -> MegaFace {
+        MegaFace::ALL[(self as usize + 6) % 12]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MegaFace::U => "U",
+            MegaFace::F => "F",
+            MegaFace::R => "R",
+            MegaFace::BR => "BR",
+            MegaFace::BL => "BL",
+            MegaFace::L => "L",
+            MegaFace::D => "D",
+            MegaFace::B => "B",
+            MegaFace::DBL => "DBL",
+            MegaFace::DL => "DL",
+            MegaFace::DR => "DR",
+            MegaFace::DBR => "DBR",
+        }
+    }
+}
+
+/// メガミンクスの手
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MegaminxMove {
+    pub face: MegaFace,
+    /// 時計回りの 72 度を単位とした回転量 (1..=4)
+    pub turns: u8,
+    /// 向かいの面の層を除いた全体を回す（Pochmann 式）
+    pub wide: bool,
+}
+
+impl MegaminxMove {
+    pub fn inverse(self) -> MegaminxMove {
+        MegaminxMove {
+            turns: 5 - self.turns,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for MegaminxMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let suffix = match (self.wide, self.turns) {
+            (true, 1) => "+",
+            (true, 2) => "++",
+            (true, 3) => "--",
+            (true, _) => "-",
+            (false, 1) => "",
+            (false, 2) => "2",
+            (false, 3) => "2'",
+            (false, _) => "'",
+        };
+        write!(f, "{}{}", self.face.name(), suffix)
+    }
+}
+
+/// `R++ D-- U'` や `F2' BL` のような手順をパースする
+pub fn parse_megaminx_alg(s: &str) -> Result<Vec<MegaminxMove>, ParseError> {
+    tokens(s)
+        .enumerate()
+        .map(|(index, (offset, token))| {
+            parse_move(token).ok_or_else(|| ParseError {
+                token: token.to_string(),
+                index,
+                offset,
+            })
+        })
+        .collect()
+}
+
+fn parse_move(token: &str) -> Option<MegaminxMove> {
+    let split = token
+        .find(|c: char| !c.is_ascii_uppercase())
+        .unwrap_or(token.len());
+    let (name, suffix) = token.split_at(split);
+    let face = *MegaFace::ALL.iter().find(|f| f.name() == name)?;
+    let (turns, wide) = match suffix {
+        "" => (1, false),
+        "2" => (2, false),
+        "2'" => (3, false),
+        "'" => (4, false),
+        "+" => (1, true),
+        "++" => (2, true),
+        "--" => (3, true),
+        "-" => (4, true),
+        _ => return None,
+    };
+    Some(MegaminxMove { face, turns, wide })
+}
+
+/// 手順を記法にする
+pub fn format_megaminx_alg(moves: &[MegaminxMove]) -> String {
+    moves
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// 面とパーツの形, 手ごとの角と辺の動き
+struct Model {
+    corners: Vec<[MegaFace; 3]>,
+    edges: Vec<[MegaFace; 2]>,
+    // 面の番号 * 2 + wide
+    turns: Vec<(Turn, Turn)>,
+}
+
+fn model() -> &'static Model {
+    static MODEL: OnceLock<Model> = OnceLock::new();
+    MODEL.get_or_init(|| {
+        // U は真上, F は手前. まわりの面は U から見て反時計回りに F, R, BR, BL, L
+        let up: Vec3 = [0.0, 1.0, 0.0];
+        let front: Vec3 = normalize([0.0, 1.0, 2.0]);
+        let ring = std::f64::consts::TAU / 5.0;
+        let mut normals: Vec<Vec3> = (0..5).map(|k| rotate(front, up, ring * k as f64)).collect();
+        normals.insert(0, up);
+        for i in 0..6 {
+            normals.push(normals[i].map(|x| -x));
+        }
+
+        // 隣り合う面の法線の内積は 1/√5
+        let adjacent = |a: usize, b: usize| dot(normals[a], normals[b]) > 0.4;
+        let mut corners = Vec::new();
+        let mut edges = Vec::new();
+        for a in 0..12 {
+            for b in a + 1..12 {
+                if !adjacent(a, b) {
+                    continue;
+                }
+                edges.push([a, b]);
+                for c in b + 1..12 {
+                    if adjacent(a, c) && adjacent(b, c) {
+                        corners.push([a, b, c]);
+                    }
+                }
+            }
+        }
+        let piece = |faces: &[usize]| {
+            let ns: Vec<Vec3> = faces.iter().map(|&f| normals[f]).collect();
+            let pos = [0, 1, 2].map(|i| ns.iter().map(|n| n[i]).sum());
+            Piece::new(pos, &ns)
+        };
+        let corner_pieces: Vec<Piece> = corners.iter().map(|f| piece(f)).collect();
+        let edge_pieces: Vec<Piece> = edges.iter().map(|f| piece(f)).collect();
+
+        // 面の層のパーツは法線方向の成分が 1.4 以上, 向かいの層は -1.4 以下で, 他は ±0.9 以内.
+        // その間の ±1.2 で切る
+        let angle = ring;
+        let mut turns = Vec::new();
+        for &n in &normals {
+            for cut in [1.2, -1.2] {
+                turns.push((
+                    turn(&corner_pieces, n, angle, cut),
+                    turn(&edge_pieces, n, angle, cut),
+                ));
+            }
+        }
+        // ステッカーの並び（反時計回り）で面を持つ
+        let faces = |p: &Piece| -> Vec<MegaFace> {
+            p.normals
+                .iter()
+                .map(|&n| MegaFace::ALL[normals.iter().position(|&m| close(m, n)).unwrap()])
+                .collect()
+        };
+        Model {
+            corners: corner_pieces
+                .iter()
+                .map(|p| faces(p).try_into().unwrap())
+                .collect(),
+            edges: edge_pieces
+                .iter()
+                .map(|p| faces(p).try_into().unwrap())
+                .collect(),
+            turns,
+        }
+    })
+}
+
+/// 角の位置の面（index 順）. 最初の面が向きの基準
+pub fn megaminx_corner_faces(i: usize) -> [MegaFace; 3] {
+    model().corners[i]
+}
+
+/// 辺の位置の面（index 順）. 最初の面が向きの基準
+pub fn megaminx_edge_faces(i: usize) -> [MegaFace; 2] {
+    model().edges[i]
+}
+
+/// メガミンクスの状態
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Megaminx {
+    pub corner_perm: [u8; 20],
+    pub corner_ori: [u8; 20],
+    pub edge_perm: [u8; 30],
+    pub edge_ori: [u8; 30],
+}
+
+/// `LegalityInfo` と同じ内訳. 面の手は 5 サイクルなので, 置換はどちらも偶置換
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MegaminxLegality {
+    pub corner_parity: i32,
+    pub edge_parity: i32,
+    pub edge_flip_sum_mod2: u8,
+    pub corner_twist_sum_mod3: u8,
+    pub is_legal: bool,
+}
+
+impl Megaminx {
+    pub fn identity() -> Self {
+        Megaminx {
+            corner_perm: std::array::from_fn(|i| i as u8),
+            corner_ori: [0; 20],
+            edge_perm: std::array::from_fn(|i| i as u8),
+            edge_ori: [0; 30],
+        }
+    }
+
+    pub fn apply_move(&mut self, m: MegaminxMove) {
+        let (corners, edges) = &model().turns[m.face as usize * 2 + m.wide as usize];
+        for _ in 0..m.turns {
+            corners.apply(&mut self.corner_perm, &mut self.corner_ori, 3);
+            edges.apply(&mut self.edge_perm, &mut self.edge_ori, 2);
+        }
+    }
+
+    pub fn apply_alg(&self, alg: &[MegaminxMove]) -> Self {
+        let mut c = *self;
+        for &m in alg {
+            c.apply_move(m);
+        }
+        c
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == Megaminx::identity()
+    }
+
+    /// 全体の向きを問わずに揃っているか（Pochmann 式の手は全体も回す）
+    pub fn is_solved_up_to_rotation(&self) -> bool {
+        let m = model();
+        // 各角の 3 面の色から, 全体の向きとして面の対応が一つに決まるかを見る
+        let mut face_map: [Option<usize>; 12] = [None; 12];
+        let mut assign = |slot: MegaFace, color: MegaFace| match face_map[slot as usize] {
+            None => {
+                face_map[slot as usize] = Some(color as usize);
+                true
+            }
+            Some(c) => c == color as usize,
+        };
+        let corners_ok = (0..20).all(|i| {
+            let piece = m.corners[self.corner_perm[i] as usize];
+            let o = self.corner_ori[i] as usize;
+            (0..3).all(|k| assign(m.corners[i][(k + o) % 3], piece[k]))
+        });
+        let edges_ok = (0..30).all(|i| {
+            let piece = m.edges[self.edge_perm[i] as usize];
+            let o = self.edge_ori[i] as usize;
+            (0..2).all(|k| assign(m.edges[i][(k + o) % 2], piece[k]))
+        });
+        corners_ok && edges_ok
+    }
+
+    pub fn legality(&self) -> MegaminxLegality {
+        let sign = |perm: &[u8]| 1 - 2 * parity(perm) as i32;
+        let corner_parity = sign(&self.corner_perm);
+        let edge_parity = sign(&self.edge_perm);
+        let edge_flip_sum_mod2 = (self.edge_ori.iter().map(|&o| o as u32).sum::<u32>() % 2) as u8;
+        let corner_twist_sum_mod3 =
+            (self.corner_ori.iter().map(|&o| o as u32).sum::<u32>() % 3) as u8;
+        let is_legal = corner_parity == 1
+            && edge_parity == 1
+            && edge_flip_sum_mod2 == 0
+            && corner_twist_sum_mod3 == 0;
+        MegaminxLegality {
+            corner_parity,
+            edge_parity,
+            edge_flip_sum_mod2,
+            corner_twist_sum_mod3,
+            is_legal,
+        }
+    }
+
+    pub fn is_legal(&self) -> bool {
+        self.legality().is_legal
+    }
+
+    pub fn corner_cycles(&self) -> Vec<Vec<u8>> {
+        perm_cycles(&self.corner_perm)
+    }
+
+    pub fn edge_cycles(&self) -> Vec<Vec<u8>> {
+        perm_cycles(&self.edge_perm)
+    }
+}
+
+impl Default for Megaminx {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// Pochmann 式のランダム手順スクランブル.
+/// 1 行は `R±± D±±` を 5 回と `U` か `U'` で, それを 7 行
+pub fn pochmann_scramble(rng: &mut Rng) -> Vec<MegaminxMove> {
+    let mut moves = Vec::with_capacity(POCHMANN_LINES * (POCHMANN_LINE + 1));
+    for _ in 0..POCHMANN_LINES {
+        for i in 0..POCHMANN_LINE {
+            let face = if i % 2 == 0 { MegaFace::R } else { MegaFace::D };
+            moves.push(MegaminxMove {
+                face,
+                turns: 2 + rng.below(2) as u8,
+                wide: true,
+            });
+        }
+        moves.push(MegaminxMove {
+            face: MegaFace::U,
+            turns: if rng.below(2) == 0 { 1 } else { 4 },
+            wide: false,
+        });
+    }
+    moves
+}
+
+/// Pochmann 式スクランブルを 1 行ずつ改行して書く
+pub fn format_pochmann(moves: &[MegaminxMove]) -> String {
+    moves
+        .chunks(POCHMANN_LINE + 1)
+        .map(format_megaminx_alg)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -1,4 +1,6 @@
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Move {
     U,
     U2,
@@ -20,6 +22,62 @@ pub enum Move {
     Bp,
 }
 
+/// 面（回転の軸となる外側の層）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub enum Face {
+    U,
+    D,
+    L,
+    R,
+    F,
+    B,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::U, Face::D, Face::L, Face::R, Face::F, Face::B];
+}
+
+impl Move {
+    pub const ALL: [Move; 18] = [
+        Move::U,
+        Move::U2,
+        Move::Up,
+        Move::D,
+        Move::D2,
+        Move::Dp,
+        Move::L,
+        Move::L2,
+        Move::Lp,
+        Move::R,
+        Move::R2,
+        Move::Rp,
+        Move::F,
+        Move::F2,
+        Move::Fp,
+        Move::B,
+        Move::B2,
+        Move::Bp,
+    ];
+
+    /// 回す面
+    pub fn face(self) -> Face {
+        Face::ALL[self as usize / 3]
+    }
+
+    /// 時計回り 90 度単位の回転量 (1, 2, 3)
+    pub fn quarter_turns(self) -> u8 {
+        self as u8 % 3 + 1
+    }
+
+    /// 面と回転量から手を作る（回転量は mod 4, 0 なら None）
+    pub fn from_face(face: Face, quarter_turns: u8) -> Option<Move> {
+        match quarter_turns % 4 {
+            0 => None,
+            q => Some(Move::ALL[face as usize * 3 + q as usize - 1]),
+        }
+    }
+}
+
 use crate::state::Cube;
 
 // Corner indices (0..7):
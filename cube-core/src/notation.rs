@@ -0,0 +1,117 @@
+//! notation.rs: シングマスター記法のパース
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::Move;
+
+/// 記法のパースエラー（不正なトークンとその位置を保持）
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("invalid move `{token}` at token {index} (offset {offset})")]
+pub struct ParseError {
+    /// 不正なトークン
+    pub token: String,
+    /// 何番目のトークンか (0 始まり)
+    pub index: usize,
+    /// 入力文字列中のバイト位置
+    pub offset: usize,
+}
+
+/// 反時計回りの表記
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NotationStyle {
+    /// `R'`（標準のシングマスター記法）
+    #[default]
+    Prime,
+    /// `Rp`（引用符を使えない場面向け）
+    P,
+}
+
+const FACE_LETTERS: [&str; 6] = ["U", "D", "L", "R", "F", "B"];
+
+impl Move {
+    /// 記法の文字列. どちらの形式も `parse` で同じ手に戻る
+    pub fn to_notation(self, style: NotationStyle) -> String {
+        let face = FACE_LETTERS[self.face() as usize];
+        let suffix = match (self.quarter_turns(), style) {
+            (1, _) => "",
+            (2, _) => "2",
+            (_, NotationStyle::Prime) => "'",
+            (_, NotationStyle::P) => "p",
+        };
+        format!("{}{}", face, suffix)
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_notation(NotationStyle::Prime))
+    }
+}
+
+/// 手順を空白区切りの記法にする（`parse_alg` の逆）
+pub fn format_alg(moves: &[Move], style: NotationStyle) -> String {
+    moves
+        .iter()
+        .map(|m| m.to_notation(style))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+
+    // `'` と `p` のどちらの逆回転表記も受け付ける
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let m = match s {
+            "U" => Move::U,
+            "U2" => Move::U2,
+            "U'" | "Up" => Move::Up,
+            "D" => Move::D,
+            "D2" => Move::D2,
+            "D'" | "Dp" => Move::Dp,
+            "L" => Move::L,
+            "L2" => Move::L2,
+            "L'" | "Lp" => Move::Lp,
+            "R" => Move::R,
+            "R2" => Move::R2,
+            "R'" | "Rp" => Move::Rp,
+            "F" => Move::F,
+            "F2" => Move::F2,
+            "F'" | "Fp" => Move::Fp,
+            "B" => Move::B,
+            "B2" => Move::B2,
+            "B'" | "Bp" => Move::Bp,
+            _ => {
+                return Err(ParseError {
+                    token: s.to_string(),
+                    index: 0,
+                    offset: 0,
+                })
+            }
+        };
+        Ok(m)
+    }
+}
+
+/// 空白区切りの手順をパースする
+pub fn parse_alg(s: &str) -> Result<Vec<Move>, ParseError> {
+    tokens(s)
+        .enumerate()
+        .map(|(index, (offset, token))| {
+            token.parse().map_err(|_| ParseError {
+                token: token.to_string(),
+                index,
+                offset,
+            })
+        })
+        .collect()
+}
+
+// (バイト位置, トークン) の列
+pub(crate) fn tokens(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.split_whitespace()
+        .map(move |t| (t.as_ptr() as usize - s.as_ptr() as usize, t))
+}
@@ -0,0 +1,245 @@
+//! pocket.rs: 2x2x2（ポケットキューブ）
+//!
+//! 角だけの `Cube` として扱い, 手の適用は `Cube` の角の処理をそのまま使う.
+//! センターがないので, 全体の持ち替えで移り合う状態は同じとみなす.
+//! 解法では DBL の角が動かない <U, R, F> だけを使い, 状態は「DBL の角が
+//! 位置 7 に向き 0 で来る」ように持ち替えて見直してから探す.
+//! 持ち替えを除いた 7! * 3^6 = 3,674,160 状態の最短手数表を幅優先で作る.
+
+use std::sync::OnceLock;
+
+use crate::random::{randomize_orientation, Rng};
+use crate::solver::{prune_table, rank_perm, unrank_perm, SolveError};
+use crate::{invert_alg, Cube, Move};
+
+const N_PERM7: usize = 5040; // 7!
+const N_TWIST6: usize = 729; // 3^6
+
+/// 状態数（持ち替えを除く）
+pub const POCKET_STATES: usize = N_PERM7 * N_TWIST6;
+
+// 解法で使う手（Move::ALL の index）: U*, R*, F*. DBL の角は動かない
+pub(crate) const SOLVER_MOVES: [usize; 9] = [0, 1, 2, 9, 10, 11, 12, 13, 14];
+
+/// 2x2x2 の状態. 位置と向きの約束は `Cube` の角と同じ
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PocketCube {
+    pub corner_perm: [u8; 8],
+    pub corner_ori: [u8; 8],
+}
+
+impl PocketCube {
+    pub fn identity() -> Self {
+        Self::from_cube(&Cube::identity())
+    }
+
+    /// 3x3x3 の角だけを取り出す
+    pub fn from_cube(cube: &Cube) -> Self {
+        PocketCube {
+            corner_perm: cube.corner_perm,
+            corner_ori: cube.corner_ori,
+        }
+    }
+
+    // 辺が揃った `Cube` として見る
+    fn as_cube(&self) -> Cube {
+        Cube {
+            corner_perm: self.corner_perm,
+            corner_ori: self.corner_ori,
+            ..Cube::identity()
+        }
+    }
+
+    pub fn apply_move(&mut self, m: Move) {
+        let mut cube = self.as_cube();
+        cube.apply_move(m);
+        *self = Self::from_cube(&cube);
+    }
+
+    pub fn apply_alg(&self, alg: &[Move]) -> Self {
+        let mut c = *self;
+        for &m in alg {
+            c.apply_move(m);
+        }
+        c
+    }
+
+    /// 持ち替えを除いて揃っているか
+    pub fn is_solved(&self) -> bool {
+        self.is_legal() && self.normalized() == PocketCube::identity()
+    }
+
+    /// 角の置換が正しく, ねじれの総和が 3 の倍数か（置換の偶奇は自由）
+    pub fn is_legal(&self) -> bool {
+        let mut seen = [false; 8];
+        let perm_ok = self
+            .corner_perm
+            .iter()
+            .all(|&p| (p as usize) < 8 && !std::mem::replace(&mut seen[p as usize], true));
+        perm_ok
+            && self.corner_ori.iter().all(|&o| o < 3)
+            && self.corner_ori.iter().sum::<u8>() % 3 == 0
+    }
+
+    /// 一様にランダムな状態（DBL の角を位置 7 に置いた向きで返す）
+    pub fn random(rng: &mut Rng) -> Self {
+        let mut c = PocketCube::identity();
+        let perm: [u8; 7] = unrank_perm(rng.below(N_PERM7));
+        c.corner_perm[..7].copy_from_slice(&perm);
+        randomize_orientation(rng, &mut c.corner_ori[..7], 3);
+        c
+    }
+
+    /// DBL の角が位置 7 に向き 0 で来るように持ち替えて見直した状態.
+    /// 同じ手順を回したとき, 持ち替えを除いて同じ状態になる
+    pub fn normalized(&self) -> PocketCube {
+        let frame = orientations()
+            .iter()
+            .find(|g| {
+                g.corner_perm[7] == self.corner_perm[7] && g.corner_ori[7] == self.corner_ori[7]
+            })
+            .unwrap();
+        // frame で位置 t にあるパーツを t と呼び直し, その向きを基準にする
+        let mut d = *self;
+        for s in 0..8 {
+            let t = frame
+                .corner_perm
+                .iter()
+                .position(|&p| p == self.corner_perm[s])
+                .unwrap();
+            d.corner_perm[s] = t as u8;
+            d.corner_ori[s] = (self.corner_ori[s] + 3 - frame.corner_ori[t]) % 3;
+        }
+        d
+    }
+}
+
+impl Default for PocketCube {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+// 揃った状態を持ち替えた 24 通り（x = R L', y = U D', z = F B'）
+fn orientations() -> &'static [PocketCube] {
+    static ALL: OnceLock<Vec<PocketCube>> = OnceLock::new();
+    ALL.get_or_init(|| {
+        let rotations = [
+            [Move::R, Move::Lp],
+            [Move::U, Move::Dp],
+            [Move::F, Move::Bp],
+        ];
+        let mut all = vec![PocketCube::identity()];
+        let mut frontier = 0;
+        while frontier < all.len() {
+            for r in &rotations {
+                let next = all[frontier].apply_alg(r);
+                if !all.contains(&next) {
+                    all.push(next);
+                }
+            }
+            frontier += 1;
+        }
+        all
+    })
+}
+
+// ---- 座標（DBL の角が揃った状態について） ----
+
+fn perm_coord(c: &PocketCube) -> usize {
+    rank_perm(&c.corner_perm[..7])
+}
+
+fn twist_coord(c: &PocketCube) -> usize {
+    c.corner_ori[..6]
+        .iter()
+        .fold(0, |acc, &o| acc * 3 + o as usize)
+}
+
+fn set_twist(c: &mut PocketCube, mut value: usize) {
+    let mut sum = 0;
+    for i in (0..6).rev() {
+        c.corner_ori[i] = (value % 3) as u8;
+        sum += c.corner_ori[i];
+        value /= 3;
+    }
+    c.corner_ori[6] = (3 - sum % 3) % 3;
+}
+
+/// 2x2x2 の移動表と, 全状態の最短手数表
+pub struct PocketTables {
+    perm_move: Vec<[u16; 9]>,
+    twist_move: Vec<[u16; 9]>,
+    distance: Vec<u8>,
+}
+
+impl PocketTables {
+    pub fn build() -> Self {
+        let perm_move: Vec<[u16; 9]> = (0..N_PERM7)
+            .map(|i| {
+                let mut c = PocketCube::identity();
+                let perm: [u8; 7] = unrank_perm(i);
+                c.corner_perm[..7].copy_from_slice(&perm);
+                SOLVER_MOVES.map(|m| perm_coord(&c.apply_alg(&[Move::ALL[m]])) as u16)
+            })
+            .collect();
+        let twist_move: Vec<[u16; 9]> = (0..N_TWIST6)
+            .map(|i| {
+                let mut c = PocketCube::identity();
+                set_twist(&mut c, i);
+                SOLVER_MOVES.map(|m| twist_coord(&c.apply_alg(&[Move::ALL[m]])) as u16)
+            })
+            .collect();
+        let distance = prune_table(N_PERM7, N_TWIST6, SOLVER_MOVES.len(), |p, t, m| {
+            (perm_move[p][m] as usize, twist_move[t][m] as usize)
+        });
+        PocketTables {
+            perm_move,
+            twist_move,
+            distance,
+        }
+    }
+
+    /// 持ち替えを除いて揃えるまでの最短手数
+    pub fn distance(&self, cube: &PocketCube) -> Result<usize, SolveError> {
+        let (p, t) = coords(cube)?;
+        Ok(self.distance[p * N_TWIST6 + t] as usize)
+    }
+
+    /// 最短手数の解（<U, R, F> の手だけを使う）
+    pub fn solve(&self, cube: &PocketCube) -> Result<Vec<Move>, SolveError> {
+        let (mut p, mut t) = coords(cube)?;
+        let mut solution = Vec::new();
+        let mut d = self.distance[p * N_TWIST6 + t];
+        while d > 0 {
+            // 手数表で 1 手近づく手を選ぶ
+            let (m, np, nt) = (0..SOLVER_MOVES.len())
+                .map(|m| {
+                    (
+                        m,
+                        self.perm_move[p][m] as usize,
+                        self.twist_move[t][m] as usize,
+                    )
+                })
+                .find(|&(_, np, nt)| self.distance[np * N_TWIST6 + nt] == d - 1)
+                .ok_or(SolveError::InvalidTables)?;
+            solution.push(Move::ALL[SOLVER_MOVES[m]]);
+            (p, t, d) = (np, nt, d - 1);
+        }
+        Ok(solution)
+    }
+
+    /// ランダムステートスクランブル
+    pub fn random_scramble(&self, rng: &mut Rng) -> Vec<Move> {
+        let solution = self.solve(&PocketCube::random(rng)).unwrap();
+        invert_alg(&solution)
+    }
+}
+
+fn coords(cube: &PocketCube) -> Result<(usize, usize), SolveError> {
+    if !cube.is_legal() {
+        return Err(SolveError::Illegal);
+    }
+    let d = cube.normalized();
+    Ok((perm_coord(&d), twist_coord(&d)))
+}
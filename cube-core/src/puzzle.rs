@@ -0,0 +1,717 @@
+//! puzzle.rs: パズル一般の操作
+//!
+//! `Puzzle` はパズルの定義（手の集合と状態の型）で, 状態そのものは `State` として別に持つ.
+//! 大きさや手が実行時に決まるパズル（NxNxN, 定義ファイルから読むもの）も扱えるよう,
+//! 操作は定義の値 `&self` を通して行う.
+//!
+//! 状態はパーツの軌道ごとに「位置 → パーツ」の置換と向きで見られるものとし,
+//! サイクル分解・手順の位数・幅優先探索による最短手数表はこの上で共通に書く.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+use crate::bigcube::{BigCube, BigMove, BIG_CUBE_SIZES};
+use crate::cuboid::{DominoCube, FloppyCube, DOMINO_MOVES, FLOPPY_MOVES};
+use crate::cycles::perm_cycles;
+use crate::pocket::SOLVER_MOVES;
+use crate::{Cube, Face, Move, PocketCube};
+
+/// パーツの軌道の説明
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrbitInfo {
+    pub name: String,
+    /// パーツの数
+    pub pieces: usize,
+    /// 向きの数（向きのないパーツは 1）
+    pub orientations: u8,
+}
+
+/// パズルの定義
+pub trait Puzzle {
+    type Move: Copy + Debug + PartialEq + Eq;
+    type State: Clone + Debug + PartialEq + Eq + Hash;
+
+    /// 揃った状態
+    fn identity(&self) -> Self::State;
+
+    fn apply(&self, state: &mut Self::State, m: Self::Move);
+
+    fn inverse(&self, m: Self::Move) -> Self::Move;
+
+    fn is_solved(&self, state: &Self::State) -> bool;
+
+    /// 揃った状態から手で作れる状態か. 手で変わらない量だけを見る実装（`DefPuzzle`）では
+    /// 必要条件で, true でも作れない状態がある
+    fn is_legal(&self, state: &Self::State) -> bool;
+
+    /// 探索に使う手（パズルの全ての状態を作れる集合）
+    fn moves(&self) -> Vec<Self::Move>;
+
+    fn orbits(&self) -> Vec<OrbitInfo>;
+
+    /// 軌道 orbit の置換. `perm[位置] = その位置にあるパーツ`
+    fn orbit_perm(&self, state: &Self::State, orbit: usize) -> Vec<u8>;
+
+    /// 軌道 orbit の向き（位置の順）
+    fn orbit_ori(&self, state: &Self::State, orbit: usize) -> Vec<u8>;
+
+    /// 軌道 orbit の置換のサイクル（`corner_cycles` と同じ形）
+    fn orbit_cycles(&self, state: &Self::State, orbit: usize) -> Vec<Vec<u8>> {
+        perm_cycles(&self.orbit_perm(state, orbit))
+    }
+}
+
+/// 手順を適用した状態
+pub fn apply<P: Puzzle>(puzzle: &P, state: &P::State, alg: &[P::Move]) -> P::State {
+    let mut s = state.clone();
+    for &m in alg {
+        puzzle.apply(&mut s, m);
+    }
+    s
+}
+
+pub fn invert<P: Puzzle>(puzzle: &P, alg: &[P::Move]) -> Vec<P::Move> {
+    alg.iter().rev().map(|&m| puzzle.inverse(m)).collect()
+}
+
+/// [a, b] = a b a' b'
+pub fn commutator<P: Puzzle>(puzzle: &P, a: &[P::Move], b: &[P::Move]) -> Vec<P::Move> {
+    let mut res = Vec::new();
+    res.extend_from_slice(a);
+    res.extend_from_slice(b);
+    res.extend(invert(puzzle, a));
+    res.extend(invert(puzzle, b));
+    res
+}
+
+/// [b: a] = b a b'
+pub fn conjugate<P: Puzzle>(puzzle: &P, a: &[P::Move], b: &[P::Move]) -> Vec<P::Move> {
+    let mut res = Vec::new();
+    res.extend_from_slice(b);
+    res.extend_from_slice(a);
+    res.extend(invert(puzzle, b));
+    res
+}
+
+/// 軌道 orbit の置換のサイクル（`corner_cycles` と同じ形）
+pub fn orbit_cycles<P: Puzzle>(puzzle: &P, state: &P::State, orbit: usize) -> Vec<Vec<u8>> {
+    puzzle.orbit_cycles(state, orbit)
+}
+
+/// 手順を何回繰り返すと元に戻るか（位数）
+pub fn alg_order<P: Puzzle>(puzzle: &P, alg: &[P::Move]) -> usize {
+    let start = puzzle.identity();
+    let mut state = apply(puzzle, &start, alg);
+    let mut order = 1;
+    while state != start {
+        state = apply(puzzle, &state, alg);
+        order += 1;
+    }
+    order
+}
+
+/// 揃った状態から幅優先探索した全状態の最短手数表.
+/// 状態をそのまま `HashMap` に入れるので, 数百万状態までのパズル向け
+#[derive(Clone, Debug)]
+pub struct DistanceTable<P: Puzzle> {
+    puzzle: P,
+    moves: Vec<P::Move>,
+    distance: HashMap<P::State, u8>,
+    depth_counts: Vec<usize>,
+}
+
+impl<P: Puzzle> DistanceTable<P> {
+    /// `puzzle.moves()` を手として作る
+    pub fn build(puzzle: P) -> Self {
+        let moves = puzzle.moves();
+        Self::build_with_moves(puzzle, moves)
+    }
+
+    /// 手を指定して作る（部分群の探索にも使える）
+    pub fn build_with_moves(puzzle: P, moves: Vec<P::Move>) -> Self {
+        let start = puzzle.identity();
+        let mut distance = HashMap::from([(start.clone(), 0)]);
+        let mut depth_counts = vec![1];
+        let mut frontier = vec![start];
+        while !frontier.is_empty() {
+            let depth = depth_counts.len() as u8;
+            let mut next = Vec::new();
+            for state in &frontier {
+                for &m in &moves {
+                    let mut s = state.clone();
+                    puzzle.apply(&mut s, m);
+                    if !distance.contains_key(&s) {
+                        distance.insert(s.clone(), depth);
+                        next.push(s);
+                    }
+                }
+            }
+            if !next.is_empty() {
+                depth_counts.push(next.len());
+            }
+            frontier = next;
+        }
+        DistanceTable {
+            puzzle,
+            moves,
+            distance,
+            depth_counts,
+        }
+    }
+
+    pub fn puzzle(&self) -> &P {
+        &self.puzzle
+    }
+
+    /// 到達できる状態の数
+    pub fn len(&self) -> usize {
+        self.distance.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.distance.is_empty()
+    }
+
+    /// 最長の最短手数（神の数）
+    pub fn diameter(&self) -> usize {
+        self.depth_counts.len() - 1
+    }
+
+    /// 手数ごとの状態の数
+    pub fn depth_counts(&self) -> &[usize] {
+        &self.depth_counts
+    }
+
+    /// 揃えるまでの最短手数. 到達できない状態は None
+    pub fn distance(&self, state: &P::State) -> Option<usize> {
+        self.distance.get(state).map(|&d| d as usize)
+    }
+
+    /// 最短手数の解
+    pub fn solve(&self, state: &P::State) -> Option<Vec<P::Move>> {
+        let mut d = *self.distance.get(state)?;
+        let mut state = state.clone();
+        let mut solution = Vec::new();
+        while d > 0 {
+            // 1 手近づく手を選ぶ
+            let (m, next) = self
+                .moves
+                .iter()
+                .map(|&m| {
+                    let mut s = state.clone();
+                    self.puzzle.apply(&mut s, m);
+                    (m, s)
+                })
+                .find(|(_, s)| self.distance.get(s) == Some(&(d - 1)))?;
+            solution.push(m);
+            state = next;
+            d -= 1;
+        }
+        Some(solution)
+    }
+}
+
+/// 手で作れる状態全体（群）の大きさの内訳
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupReport {
+    pub orbits: Vec<OrbitInfo>,
+    /// 固定化部分群の列の各段での軌道の長さ. 積が群の位数
+    pub base_orbit_sizes: Vec<usize>,
+    /// 群の位数（u128 に収まらなければ None）
+    pub order: Option<u128>,
+}
+
+impl fmt::Display for GroupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for o in &self.orbits {
+            writeln!(
+                f,
+                "{}: {} pieces x {} orientations",
+                o.name, o.pieces, o.orientations
+            )?;
+        }
+        let sizes: Vec<String> = self
+            .base_orbit_sizes
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        match self.order {
+            Some(order) => write!(f, "order: {} = {}", order, sizes.join(" * ")),
+            None => write!(f, "order: {}", sizes.join(" * ")),
+        }
+    }
+}
+
+/// 群の位数を Schreier-Sims 法で求める.
+/// 状態を「(軌道, 位置, 向き) の点」の置換とみなし, `moves()` の各手を生成元にする
+pub fn group_report<P: Puzzle>(puzzle: &P) -> GroupReport {
+    let orbits = puzzle.orbits();
+    let n: usize = orbits
+        .iter()
+        .map(|o| o.pieces * o.orientations as usize)
+        .sum();
+    let mut levels: Vec<Level> = Vec::new();
+    for m in puzzle.moves() {
+        let (h, j) = sift(&levels, move_points(puzzle, &orbits, m), 0);
+        add_generator(&mut levels, h, j, n);
+    }
+    // すべての段で Schreier 生成元がふるい落とせるまで繰り返す
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for k in 0..levels.len() {
+            for x in levels[k].orbit.clone() {
+                for s in 0..levels[k].gens.len() {
+                    if !levels[k].done.insert((x, s)) {
+                        continue;
+                    }
+                    let g = levels[k].schreier(x, s);
+                    let (h, j) = sift(&levels, g, k + 1);
+                    changed |= add_generator(&mut levels, h, j, n);
+                }
+            }
+        }
+    }
+    let base_orbit_sizes: Vec<usize> = levels.iter().map(|l| l.orbit.len()).collect();
+    let order = base_orbit_sizes
+        .iter()
+        .try_fold(1u128, |acc, &s| acc.checked_mul(s as u128));
+    GroupReport {
+        orbits,
+        base_orbit_sizes,
+        order,
+    }
+}
+
+// 手 m を点の置換にする. 位置 i, 向き t の点は, 手の後の位置 j, 向き t + ori[j] へ移る
+fn move_points<P: Puzzle>(puzzle: &P, orbits: &[OrbitInfo], m: P::Move) -> Vec<u16> {
+    let mut state = puzzle.identity();
+    puzzle.apply(&mut state, m);
+    let mut image = Vec::new();
+    for (o, info) in orbits.iter().enumerate() {
+        let offset = image.len();
+        let k = info.orientations as usize;
+        let perm = puzzle.orbit_perm(&state, o);
+        let ori = puzzle.orbit_ori(&state, o);
+        let mut part = vec![0; info.pieces * k];
+        for (j, &i) in perm.iter().enumerate() {
+            for t in 0..k {
+                part[i as usize * k + t] = (offset + j * k + (t + ori[j] as usize) % k) as u16;
+            }
+        }
+        image.extend(part);
+    }
+    image
+}
+
+// 固定化部分群の列の 1 段: 基点, それまでの基点を固定する生成元, 基点の軌道と,
+// 基点を軌道の各点に移す元. done は Schreier 生成元を調べ終えた (点, 生成元) の組
+struct Level {
+    base: usize,
+    gens: Vec<Vec<u16>>,
+    orbit: Vec<usize>,
+    transversal: Vec<Option<Vec<u16>>>,
+    done: HashSet<(usize, usize)>,
+}
+
+impl Level {
+    fn extend_orbit(&mut self) {
+        let mut k = 0;
+        while k < self.orbit.len() {
+            let x = self.orbit[k];
+            for s in 0..self.gens.len() {
+                let y = self.gens[s][x] as usize;
+                if self.transversal[y].is_none() {
+                    let u = compose(self.transversal[x].as_ref().unwrap(), &self.gens[s]);
+                    self.transversal[y] = Some(u);
+                    self.orbit.push(y);
+                }
+            }
+            k += 1;
+        }
+    }
+
+    // u_x s u_{xs}^-1 は基点を固定する
+    fn schreier(&self, x: usize, s: usize) -> Vec<u16> {
+        let us = compose(self.transversal[x].as_ref().unwrap(), &self.gens[s]);
+        let y = us[self.base] as usize;
+        compose(&us, &inverse(self.transversal[y].as_ref().unwrap()))
+    }
+}
+
+// a の後に b
+fn compose(a: &[u16], b: &[u16]) -> Vec<u16> {
+    a.iter().map(|&x| b[x as usize]).collect()
+}
+
+fn inverse(a: &[u16]) -> Vec<u16> {
+    let mut inv = vec![0; a.len()];
+    for (x, &y) in a.iter().enumerate() {
+        inv[y as usize] = x as u16;
+    }
+    inv
+}
+
+// 段 from から順に基点を戻していき, 残った元と止まった段を返す
+fn sift(levels: &[Level], mut g: Vec<u16>, from: usize) -> (Vec<u16>, usize) {
+    for (i, level) in levels.iter().enumerate().skip(from) {
+        match &level.transversal[g[level.base] as usize] {
+            Some(u) => g = compose(&g, &inverse(u)),
+            None => return (g, i),
+        }
+    }
+    (g, levels.len())
+}
+
+// 段 j で止まった元 h を段 0 ..= j の生成元に加える（h はそれらの基点を固定する）.
+// h が恒等置換なら何もせず false
+fn add_generator(levels: &mut Vec<Level>, h: Vec<u16>, j: usize, n: usize) -> bool {
+    let Some(base) = h.iter().enumerate().position(|(x, &y)| x != y as usize) else {
+        return false;
+    };
+    if j == levels.len() {
+        let mut transversal = vec![None; n];
+        transversal[base] = Some((0..n as u16).collect());
+        levels.push(Level {
+            base,
+            gens: Vec::new(),
+            orbit: vec![base],
+            transversal,
+            done: HashSet::new(),
+        });
+    }
+    for level in &mut levels[..=j] {
+        level.gens.push(h.clone());
+        level.extend_orbit();
+    }
+    true
+}
+
+// ---- 実装 ----
+
+/// 3x3x3
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThreeByThree;
+
+impl Puzzle for ThreeByThree {
+    type Move = Move;
+    type State = Cube;
+
+    fn identity(&self) -> Cube {
+        Cube::identity()
+    }
+
+    fn apply(&self, state: &mut Cube, m: Move) {
+        state.apply_move(m);
+    }
+
+    fn inverse(&self, m: Move) -> Move {
+        m.inverse()
+    }
+
+    fn is_solved(&self, state: &Cube) -> bool {
+        state.is_solved()
+    }
+
+    fn is_legal(&self, state: &Cube) -> bool {
+        state.is_legal()
+    }
+
+    fn moves(&self) -> Vec<Move> {
+        Move::ALL.to_vec()
+    }
+
+    fn orbits(&self) -> Vec<OrbitInfo> {
+        vec![
+            OrbitInfo {
+                name: "corners".to_string(),
+                pieces: 8,
+                orientations: 3,
+            },
+            OrbitInfo {
+                name: "edges".to_string(),
+                pieces: 12,
+                orientations: 2,
+            },
+        ]
+    }
+
+    fn orbit_perm(&self, state: &Cube, orbit: usize) -> Vec<u8> {
+        match orbit {
+            0 => state.corner_perm.to_vec(),
+            1 => state.edge_perm.to_vec(),
+            _ => panic!("orbit index {} out of range", orbit),
+        }
+    }
+
+    fn orbit_ori(&self, state: &Cube, orbit: usize) -> Vec<u8> {
+        match orbit {
+            0 => state.corner_ori.to_vec(),
+            1 => state.edge_ori.to_vec(),
+            _ => panic!("orbit index {} out of range", orbit),
+        }
+    }
+}
+
+/// 2x2x2. 揃っているかは持ち替えを除いて判定する.
+/// 探索に使う手は `PocketTables` と同じ U / R / F だけで, DBL の角を固定する
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Pocket;
+
+impl Puzzle for Pocket {
+    type Move = Move;
+    type State = PocketCube;
+
+    fn identity(&self) -> PocketCube {
+        PocketCube::identity()
+    }
+
+    fn apply(&self, state: &mut PocketCube, m: Move) {
+        state.apply_move(m);
+    }
+
+    fn inverse(&self, m: Move) -> Move {
+        m.inverse()
+    }
+
+    fn is_solved(&self, state: &PocketCube) -> bool {
+        state.is_solved()
+    }
+
+    fn is_legal(&self, state: &PocketCube) -> bool {
+        state.is_legal()
+    }
+
+    fn moves(&self) -> Vec<Move> {
+        SOLVER_MOVES.iter().map(|&m| Move::ALL[m]).collect()
+    }
+
+    fn orbits(&self) -> Vec<OrbitInfo> {
+        vec![OrbitInfo {
+            name: "corners".to_string(),
+            pieces: 8,
+            orientations: 3,
+        }]
+    }
+
+    fn orbit_perm(&self, state: &PocketCube, orbit: usize) -> Vec<u8> {
+        assert_eq!(orbit, 0, "orbit index {} out of range", orbit);
+        state.corner_perm.to_vec()
+    }
+
+    fn orbit_ori(&self, state: &PocketCube, orbit: usize) -> Vec<u8> {
+        assert_eq!(orbit, 0, "orbit index {} out of range", orbit);
+        state.corner_ori.to_vec()
+    }
+}
+
+/// NxNxN. 大きさは `BigCubeSize::new` で 4 ..= 7 に限る
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BigCubeSize(usize);
+
+impl BigCubeSize {
+    pub fn new(n: usize) -> Option<Self> {
+        BIG_CUBE_SIZES.contains(&n).then_some(BigCubeSize(n))
+    }
+
+    pub fn n(&self) -> usize {
+        self.0
+    }
+}
+
+impl Puzzle for BigCubeSize {
+    type Move = BigMove;
+    type State = BigCube;
+
+    fn identity(&self) -> BigCube {
+        BigCube::new(self.0).unwrap()
+    }
+
+    fn apply(&self, state: &mut BigCube, m: BigMove) {
+        state.apply_move(m);
+    }
+
+    fn inverse(&self, m: BigMove) -> BigMove {
+        m.inverse()
+    }
+
+    fn is_solved(&self, state: &BigCube) -> bool {
+        state.is_solved()
+    }
+
+    fn is_legal(&self, state: &BigCube) -> bool {
+        state.is_legal()
+    }
+
+    /// R, U, F 方向の 1 層ずつの回転（各層 1/4, 1/2, 3/4）
+    fn moves(&self) -> Vec<BigMove> {
+        let mut moves = Vec::new();
+        for face in [Face::U, Face::R, Face::F] {
+            for layer in 1..=self.0 as u8 {
+                for quarter_turns in 1..=3 {
+                    moves.push(BigMove {
+                        face,
+                        from: layer,
+                        to: layer,
+                        quarter_turns,
+                    });
+                }
+            }
+        }
+        moves
+    }
+
+    fn orbits(&self) -> Vec<OrbitInfo> {
+        self.identity()
+            .orbits()
+            .iter()
+            .map(|o| OrbitInfo {
+                name: o.name.clone(),
+                pieces: o.cubies.len(),
+                orientations: o.kind.orientations(),
+            })
+            .collect()
+    }
+
+    /// 別の軌道のパーツが入っている位置（`BigCube::from_stickers` で作れる）には,
+    /// 入っていないパーツの番号を小さい順に入れて置換にする
+    fn orbit_perm(&self, state: &BigCube, orbit: usize) -> Vec<u8> {
+        let slots = state.orbit_slots(&state.orbits()[orbit]);
+        let mut used = vec![false; slots.len()];
+        let slots: Vec<Option<usize>> = slots
+            .into_iter()
+            .map(|slot| slot.filter(|&p| !std::mem::replace(&mut used[p], true)))
+            .collect();
+        let mut missing = (0..used.len()).filter(|&p| !used[p]);
+        slots
+            .into_iter()
+            .map(|slot| slot.or_else(|| missing.next()).unwrap() as u8)
+            .collect()
+    }
+
+    fn orbit_ori(&self, state: &BigCube, orbit: usize) -> Vec<u8> {
+        state.orbit_ori(&state.orbits()[orbit])
+    }
+
+    fn orbit_cycles(&self, state: &BigCube, orbit: usize) -> Vec<Vec<u8>> {
+        state.orbit_cycles(&state.orbits()[orbit])
+    }
+}
+
+/// 3x3x2（ドミノ）. 側面は 180 度だけ
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Domino;
+
+impl Puzzle for Domino {
+    type Move = Move;
+    type State = DominoCube;
+
+    fn identity(&self) -> DominoCube {
+        DominoCube::identity()
+    }
+
+    fn apply(&self, state: &mut DominoCube, m: Move) {
+        state.apply_move(m);
+    }
+
+    fn inverse(&self, m: Move) -> Move {
+        m.inverse()
+    }
+
+    fn is_solved(&self, state: &DominoCube) -> bool {
+        state.is_solved()
+    }
+
+    fn is_legal(&self, state: &DominoCube) -> bool {
+        state.is_legal()
+    }
+
+    fn moves(&self) -> Vec<Move> {
+        DOMINO_MOVES.to_vec()
+    }
+
+    fn orbits(&self) -> Vec<OrbitInfo> {
+        ["corners", "edges"]
+            .iter()
+            .map(|name| OrbitInfo {
+                name: name.to_string(),
+                pieces: 8,
+                orientations: 1,
+            })
+            .collect()
+    }
+
+    fn orbit_perm(&self, state: &DominoCube, orbit: usize) -> Vec<u8> {
+        match orbit {
+            0 => state.corner_perm.to_vec(),
+            1 => state.edge_perm.to_vec(),
+            _ => panic!("orbit index {} out of range", orbit),
+        }
+    }
+
+    fn orbit_ori(&self, _state: &DominoCube, orbit: usize) -> Vec<u8> {
+        assert!(orbit < 2, "orbit index {} out of range", orbit);
+        vec![0; 8]
+    }
+}
+
+/// 1x3x3（フロッピー）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Floppy;
+
+impl Puzzle for Floppy {
+    type Move = Move;
+    type State = FloppyCube;
+
+    fn identity(&self) -> FloppyCube {
+        FloppyCube::identity()
+    }
+
+    fn apply(&self, state: &mut FloppyCube, m: Move) {
+        state.apply_move(m);
+    }
+
+    fn inverse(&self, m: Move) -> Move {
+        m.inverse()
+    }
+
+    fn is_solved(&self, state: &FloppyCube) -> bool {
+        state.is_solved()
+    }
+
+    fn is_legal(&self, state: &FloppyCube) -> bool {
+        state.is_legal()
+    }
+
+    fn moves(&self) -> Vec<Move> {
+        FLOPPY_MOVES.to_vec()
+    }
+
+    fn orbits(&self) -> Vec<OrbitInfo> {
+        ["corners", "edges"]
+            .iter()
+            .map(|name| OrbitInfo {
+                name: name.to_string(),
+                pieces: 4,
+                orientations: 2,
+            })
+            .collect()
+    }
+
+    fn orbit_perm(&self, state: &FloppyCube, orbit: usize) -> Vec<u8> {
+        match orbit {
+            0 => state.corner_perm.to_vec(),
+            1 => vec![0, 1, 2, 3],
+            _ => panic!("orbit index {} out of range", orbit),
+        }
+    }
+
+    fn orbit_ori(&self, state: &FloppyCube, orbit: usize) -> Vec<u8> {
+        match orbit {
+            0 => state.corner_ori.to_vec(),
+            1 => state.edge_ori.to_vec(),
+            _ => panic!("orbit index {} out of range", orbit),
+        }
+    }
+}
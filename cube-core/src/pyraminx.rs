@@ -0,0 +1,391 @@
+//! pyraminx.rs: ピラミンクス（先端つき）
+//!
+//! 頂点 U（上）, L（手前左）, R（手前右）, B（奥）のまわりに回す.
+//! 大文字の手は頂点側の 2 層（先端, 軸のパーツ, 3 つの辺）を, 小文字の手は
+//! 先端だけを, 頂点から見て時計回りに 120 度回す（`'` で反時計回り）.
+//!
+//! パーツは辺 6 つ（位置と向き 0..2）, 軸のパーツ 4 つと先端 4 つ（向き 0..3）.
+//! 軸のパーツと先端は位置が変わらない. 辺の番号は `EDGE_NAMES` の順.
+//!
+//! 先端と軸のパーツの向きの差は小文字の手でしか変わらないので, 解法は
+//! 先端を 1 手ずつ直し, 残り（933,120 状態）を最短手数表で解く.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::coord::{Coordinate, ProductTable};
+use crate::cycles::perm_cycles;
+use crate::notation::{tokens, ParseError};
+use crate::random::{fix_parity, randomize_orientation, Rng};
+use crate::shape::{turn, Piece, Turn, Vec3};
+use crate::solver::SolveError;
+use crate::state::parity;
+
+/// 辺の位置の名前（index 順）
+pub const EDGE_NAMES: [&str; 6] = ["UL", "UR", "UB", "LR", "LB", "RB"];
+
+/// 頂点
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Vertex {
+    U,
+    L,
+    R,
+    B,
+}
+
+impl Vertex {
+    pub const ALL: [Vertex; 4] = [Vertex::U, Vertex::L, Vertex::R, Vertex::B];
+
+    fn position(self) -> Vec3 {
+        match self {
+            Vertex::U => [1.0, 1.0, 1.0],
+            Vertex::L => [-1.0, -1.0, 1.0],
+            Vertex::R => [1.0, -1.0, -1.0],
+            Vertex::B => [-1.0, 1.0, -1.0],
+        }
+    }
+
+    fn letter(self) -> char {
+        match self {
+            Vertex::U => 'U',
+            Vertex::L => 'L',
+            Vertex::R => 'R',
+            Vertex::B => 'B',
+        }
+    }
+}
+
+/// ピラミンクスの手
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PyraminxMove {
+    pub vertex: Vertex,
+    /// 先端だけを回す（小文字）
+    pub tip: bool,
+    /// 時計回りの 120 度を単位とした回転量 (1: 時計回り, 2: 反時計回り)
+    pub turns: u8,
+}
+
+impl PyraminxMove {
+    /// 大きい手 8 つ（U, U', L, L', R, R', B, B'）
+    pub const BIG: [PyraminxMove; 8] = {
+        let mut moves = [PyraminxMove {
+            vertex: Vertex::U,
+            tip: false,
+            turns: 1,
+        }; 8];
+        let mut i = 0;
+        while i < 8 {
+            moves[i].vertex = Vertex::ALL[i / 2];
+            moves[i].turns = (i % 2) as u8 + 1;
+            i += 1;
+        }
+        moves
+    };
+
+    pub fn inverse(self) -> PyraminxMove {
+        PyraminxMove {
+            turns: 3 - self.turns,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for PyraminxMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = self.vertex.letter();
+        let c = if self.tip { c.to_ascii_lowercase() } else { c };
+        let suffix = if self.turns == 2 { "'" } else { "" };
+        write!(f, "{}{}", c, suffix)
+    }
+}
+
+/// `U L' r b'` のような手順をパースする
+pub fn parse_pyraminx_alg(s: &str) -> Result<Vec<PyraminxMove>, ParseError> {
+    tokens(s)
+        .enumerate()
+        .map(|(index, (offset, token))| {
+            parse_move(token).ok_or_else(|| ParseError {
+                token: token.to_string(),
+                index,
+                offset,
+            })
+        })
+        .collect()
+}
+
+fn parse_move(token: &str) -> Option<PyraminxMove> {
+    let mut chars = token.chars();
+    let c = chars.next()?;
+    let turns = match chars.as_str() {
+        "" => 1,
+        "'" => 2,
+        _ => return None,
+    };
+    let vertex = *Vertex::ALL
+        .iter()
+        .find(|v| v.letter() == c.to_ascii_uppercase())?;
+    Some(PyraminxMove {
+        vertex,
+        tip: c.is_ascii_lowercase(),
+        turns,
+    })
+}
+
+/// 手順を記法にする
+pub fn format_pyraminx_alg(moves: &[PyraminxMove]) -> String {
+    moves
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// ピラミンクスの状態
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Pyraminx {
+    pub edge_perm: [u8; 6],
+    pub edge_ori: [u8; 6],
+    /// 軸のパーツの向き（`Vertex::ALL` の順）
+    pub center_ori: [u8; 4],
+    pub tip_ori: [u8; 4],
+}
+
+/// 合法性の内訳
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PyraminxLegality {
+    /// 辺の置換の偶奇（偶置換でなければならない）
+    pub edge_parity: u8,
+    pub edge_flip_sum_mod2: u8,
+    pub is_legal: bool,
+}
+
+// 手ごとの辺・軸のパーツ・先端の動き
+struct Turns {
+    edges: Turn,
+    centers: Turn,
+    tips: Turn,
+}
+
+fn turns(vertex: Vertex, tip: bool) -> &'static Turns {
+    static TABLE: OnceLock<Vec<Turns>> = OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        // 面は頂点の向かいにあり, 法線は頂点の逆向き
+        let face = |v: Vertex| v.position().map(|x| -x);
+        let others = |vs: &[Vertex]| -> Vec<Vec3> {
+            Vertex::ALL
+                .iter()
+                .filter(|v| !vs.contains(v))
+                .map(|&v| face(v))
+                .collect()
+        };
+        let scaled = |v: Vertex, k: f64| v.position().map(|x| x * k);
+        let edges: Vec<Piece> = EDGE_NAMES
+            .iter()
+            .map(|name| {
+                let vs: Vec<Vertex> = name
+                    .chars()
+                    .map(|c| *Vertex::ALL.iter().find(|v| v.letter() == c).unwrap())
+                    .collect();
+                let pos = [0, 1, 2].map(|i| (vs[0].position()[i] + vs[1].position()[i]) / 2.0);
+                Piece::new(pos, &others(&vs))
+            })
+            .collect();
+        let centers: Vec<Piece> = Vertex::ALL
+            .iter()
+            .map(|&v| Piece::new(scaled(v, 0.5), &others(&[v])))
+            .collect();
+        let tips: Vec<Piece> = Vertex::ALL
+            .iter()
+            .map(|&v| Piece::new(scaled(v, 0.9), &others(&[v])))
+            .collect();
+
+        // 頂点までの距離 √3 を 3 層に分ける切り口
+        let root3 = 3f64.sqrt();
+        let angle = std::f64::consts::TAU / 3.0;
+        let mut table = Vec::new();
+        for tip in [false, true] {
+            let cut = if tip {
+                5.0 / (3.0 * root3)
+            } else {
+                1.0 / (3.0 * root3)
+            };
+            for v in Vertex::ALL {
+                let axis = v.position();
+                table.push(Turns {
+                    edges: turn(&edges, axis, angle, cut),
+                    centers: turn(&centers, axis, angle, cut),
+                    tips: turn(&tips, axis, angle, cut),
+                });
+            }
+        }
+        table
+    });
+    &table[tip as usize * 4 + vertex as usize]
+}
+
+impl Pyraminx {
+    pub fn identity() -> Self {
+        Pyraminx {
+            edge_perm: [0, 1, 2, 3, 4, 5],
+            edge_ori: [0; 6],
+            center_ori: [0; 4],
+            tip_ori: [0; 4],
+        }
+    }
+
+    pub fn apply_move(&mut self, m: PyraminxMove) {
+        let t = turns(m.vertex, m.tip);
+        for _ in 0..m.turns {
+            t.edges.apply(&mut self.edge_perm, &mut self.edge_ori, 2);
+            let mut fixed = [0, 1, 2, 3];
+            t.centers.apply(&mut fixed, &mut self.center_ori, 3);
+            t.tips.apply(&mut fixed, &mut self.tip_ori, 3);
+        }
+    }
+
+    pub fn apply_alg(&self, alg: &[PyraminxMove]) -> Self {
+        let mut p = *self;
+        for &m in alg {
+            p.apply_move(m);
+        }
+        p
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == Pyraminx::identity()
+    }
+
+    pub fn legality(&self) -> PyraminxLegality {
+        let mut seen = [false; 6];
+        let perm_ok = self
+            .edge_perm
+            .iter()
+            .all(|&p| (p as usize) < 6 && !std::mem::replace(&mut seen[p as usize], true));
+        let pieces_ok = perm_ok
+            && self.edge_ori.iter().all(|&o| o < 2)
+            && self.center_ori.iter().chain(&self.tip_ori).all(|&o| o < 3);
+        // 範囲外の番号があるときは偶奇を求めない（0 とする）
+        let edge_parity = if perm_ok { parity(&self.edge_perm) } else { 0 };
+        let edge_flip_sum_mod2 = (self.edge_ori.iter().map(|&o| o as u32).sum::<u32>() % 2) as u8;
+        PyraminxLegality {
+            edge_parity,
+            edge_flip_sum_mod2,
+            is_legal: pieces_ok && edge_parity == 0 && edge_flip_sum_mod2 == 0,
+        }
+    }
+
+    pub fn is_legal(&self) -> bool {
+        self.legality().is_legal
+    }
+
+    /// 一様にランダムな合法状態
+    pub fn random(rng: &mut Rng) -> Self {
+        let mut p = Pyraminx::identity();
+        rng.shuffle(&mut p.edge_perm);
+        fix_parity(&mut p.edge_perm, 0);
+        randomize_orientation(rng, &mut p.edge_ori, 2);
+        for o in p.center_ori.iter_mut().chain(p.tip_ori.iter_mut()) {
+            *o = rng.below(3) as u8;
+        }
+        p
+    }
+
+    /// 辺の置換のサイクル（`corner_cycles` と同じ形）
+    pub fn edge_cycles(&self) -> Vec<Vec<u8>> {
+        perm_cycles(&self.edge_perm)
+    }
+
+    // 先端を軸のパーツに合わせる手（先端ごとに高々 1 手）
+    fn tip_moves(&self) -> Vec<PyraminxMove> {
+        Vertex::ALL
+            .iter()
+            .filter_map(|&vertex| {
+                let i = vertex as usize;
+                (1..3)
+                    .map(|turns| PyraminxMove {
+                        vertex,
+                        tip: true,
+                        turns,
+                    })
+                    .find(|&m| {
+                        let p = self.apply_alg(&[m]);
+                        p.tip_ori[i] == p.center_ori[i]
+                    })
+            })
+            .collect()
+    }
+}
+
+impl Default for Pyraminx {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+type Edges = ([u8; 6], [u8; 6]);
+
+/// 先端を除いた 933,120 状態の最短手数表
+pub struct PyraminxTables {
+    table: ProductTable<Edges, [u8; 4]>,
+}
+
+impl PyraminxTables {
+    pub fn build() -> Self {
+        let id = Pyraminx::identity();
+        let edges = Coordinate::build((id.edge_perm, id.edge_ori), 8, |&(perm, ori), m| {
+            let p = Pyraminx {
+                edge_perm: perm,
+                edge_ori: ori,
+                ..id
+            }
+            .apply_alg(&[PyraminxMove::BIG[m]]);
+            (p.edge_perm, p.edge_ori)
+        });
+        let centers = Coordinate::build(id.center_ori, 8, |&ori, m| {
+            Pyraminx {
+                center_ori: ori,
+                ..id
+            }
+            .apply_alg(&[PyraminxMove::BIG[m]])
+            .center_ori
+        });
+        PyraminxTables {
+            table: ProductTable::build(edges, centers, 8),
+        }
+    }
+
+    /// 先端を除いた状態の数
+    pub fn states(&self) -> usize {
+        self.table.len()
+    }
+
+    /// 先端を除いた最長の最短手数
+    pub fn diameter(&self) -> usize {
+        self.table.diameter()
+    }
+
+    /// 先端の手も数えた最短手数
+    pub fn distance(&self, p: &Pyraminx) -> Result<usize, SolveError> {
+        Ok(self.solve(p)?.len())
+    }
+
+    /// 最短手数の解（先端の手が先）
+    pub fn solve(&self, p: &Pyraminx) -> Result<Vec<PyraminxMove>, SolveError> {
+        if !p.is_legal() {
+            return Err(SolveError::Illegal);
+        }
+        let mut solution = p.tip_moves();
+        let core = self
+            .table
+            .solve(&(p.edge_perm, p.edge_ori), &p.center_ori)
+            .ok_or(SolveError::InvalidTables)?;
+        solution.extend(core.into_iter().map(|m| PyraminxMove::BIG[m]));
+        Ok(solution)
+    }
+
+    /// ランダムステートスクランブル（先端の手が最後）
+    pub fn random_scramble(&self, rng: &mut Rng) -> Vec<PyraminxMove> {
+        let solution = self.solve(&Pyraminx::random(rng)).unwrap();
+        solution.iter().rev().map(|m| m.inverse()).collect()
+    }
+}
@@ -0,0 +1,101 @@
+//! random.rs: シード付き乱数と, ランダムな状態・スクランブルの生成
+//!
+//! 外部クレートに頼らず, 同じシードからはどの環境（WASM を含む）でも同じ列を返す.
+
+use std::sync::Arc;
+
+use crate::solver::{solve, SolveError, SolverTables};
+use crate::state::parity;
+use crate::{invert_alg, Cube, Move};
+
+/// SplitMix64 による擬似乱数
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// 0..n の一様乱数
+    pub fn below(&mut self, n: usize) -> usize {
+        // 偏りを避けるため, n の倍数に収まらない値は引き直す
+        let n = n as u64;
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return (x % n) as usize;
+            }
+        }
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+// 置換の偶奇が合うよう, 必要なら最後の 2 つを入れ替える
+pub(crate) fn fix_parity(perm: &mut [u8], target: u8) {
+    if parity(perm) != target {
+        let n = perm.len();
+        perm.swap(n - 2, n - 1);
+    }
+}
+
+impl Cube {
+    /// 一様にランダムな合法状態
+    pub fn random(rng: &mut Rng) -> Cube {
+        let mut cube = Cube::identity();
+        rng.shuffle(&mut cube.corner_perm);
+        rng.shuffle(&mut cube.edge_perm);
+        fix_parity(&mut cube.edge_perm, parity(&cube.corner_perm));
+        randomize_orientation(rng, &mut cube.corner_ori, 3);
+        randomize_orientation(rng, &mut cube.edge_ori, 2);
+        cube
+    }
+}
+
+// 向きを乱択し, 総和が 0 (mod m) になるよう最後の値を決める
+pub(crate) fn randomize_orientation(rng: &mut Rng, ori: &mut [u8], m: u8) {
+    let n = ori.len();
+    let mut sum = 0;
+    for o in ori[..n - 1].iter_mut() {
+        *o = rng.below(m as usize) as u8;
+        sum += *o;
+    }
+    ori[n - 1] = (m - sum % m) % m;
+}
+
+/// 状態 cube を作るスクランブル（解の逆手順）
+pub fn scramble_for(cube: &Cube, tables: &Arc<SolverTables>) -> Result<Vec<Move>, SolveError> {
+    Ok(invert_alg(&solve(cube, tables, SCRAMBLE_LENGTH)?))
+}
+
+/// ランダムステートスクランブル
+pub fn random_state_scramble(
+    rng: &mut Rng,
+    tables: &Arc<SolverTables>,
+) -> Result<Vec<Move>, SolveError> {
+    scramble_for(&Cube::random(rng), tables)
+}
+
+// これ以下の手数の解が見つかれば探索を打ち切る
+const SCRAMBLE_LENGTH: usize = 21;
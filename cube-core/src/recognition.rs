@@ -0,0 +1,263 @@
+//! recognition.rs: 最終層 (OLL / PLL) のケース判定
+//!
+//! F2L が揃った `Cube` について, U 層 (corner 0..3, edge 0..3) の状態から
+//! AUF を除いたケースを求める. OLL / PLL は名前付きの手順表を持つ.
+//! COLL / ZBLL は標準の名前と 1 手順の解法の表を組み込んでいないので対象外とする.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::{apply_alg, invert_alg, parse_alg, Cube, Move};
+
+/// ケースの集合
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CaseSet {
+    /// 向き揃え (57 ケース)
+    Oll,
+    /// 位置揃え (21 ケース)
+    Pll,
+}
+
+/// 判定結果: `auf` を回してから `algorithm` を行えば（最後の AUF を除いて）揃う
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaseMatch {
+    pub set: CaseSet,
+    pub name: String,
+    pub auf: Option<Move>,
+    pub algorithm: Vec<Move>,
+}
+
+// 持ち替えは手順に展開して, 面の回転のみで記述している
+const OLL_CASES: [(&str, &str); 57] = [
+    ("OLL 1", "R U2 R2 F R F' U2 R' F R F'"),
+    ("OLL 2", "F R U R' U' F' B U L U' L' B'"),
+    ("OLL 3", "B U L U' L' B' U' F R U R' U' F'"),
+    ("OLL 4", "B U L U' L' B' U F R U R' U' F'"),
+    ("OLL 5", "L' B2 R B R' B L"),
+    ("OLL 6", "L F2 R' F' R F' L'"),
+    ("OLL 7", "L F R' F R F2 L'"),
+    ("OLL 8", "R' F' L F' L' F2 R"),
+    ("OLL 9", "R U R' U' R' F R2 U R' U' F'"),
+    ("OLL 10", "R U R' U R' F R F' R U2 R'"),
+    ("OLL 11", "L F R' F R' D R D' R F2 L'"),
+    ("OLL 12", "L R2 F' R F' R' F2 R F' R L'"),
+    ("OLL 13", "F U R U' R2 F' R U R U' R'"),
+    ("OLL 14", "R' F R U R' F' R F U' F'"),
+    ("OLL 15", "L' B' L R' U' R U L' B L"),
+    ("OLL 16", "L F L' R U R' U' L F' L'"),
+    ("OLL 17", "R U R' U R' F R F' U2 R' F R F'"),
+    ("OLL 18", "L F R' F R F2 L2 B' R B' R' B2 L"),
+    ("OLL 19", "L' R B R B R' B' R' L R' F R F'"),
+    ("OLL 20", "L F R' F' R2 L2 B R B' R' B' R' L"),
+    ("OLL 21", "R U2 R' U' R U R' U' R U' R'"),
+    ("OLL 22", "R U2 R2 U' R2 U' R2 U2 R"),
+    ("OLL 23", "R2 D' R U2 R' D R U2 R"),
+    ("OLL 24", "L F R' F' L' F R F'"),
+    ("OLL 25", "F' L F R' F' L' F R"),
+    ("OLL 26", "R U2 R' U' R U' R'"),
+    ("OLL 27", "R U R' U R U2 R'"),
+    ("OLL 28", "L F R' F' L' R U R U' R'"),
+    ("OLL 29", "R U R' U' R U' R' F' U' F R U R'"),
+    ("OLL 30", "F R' F R2 U' R' U' R U R' F2"),
+    ("OLL 31", "R' U' F U R U' R' F' R"),
+    ("OLL 32", "L U F' U' L' U L F L'"),
+    ("OLL 33", "R U R' U' R' F R F'"),
+    ("OLL 34", "R U R2 U' R' F R U R U' F'"),
+    ("OLL 35", "R U2 R2 F R F' R U2 R'"),
+    ("OLL 36", "L' U' L U' L' U L U L F' L' F"),
+    ("OLL 37", "F R' F' R U R U' R'"),
+    ("OLL 38", "R U R' U R U' R' U' R' F R F'"),
+    ("OLL 39", "L F' L' U' L U F U' L'"),
+    ("OLL 40", "R' F R U R' U' F' U R"),
+    ("OLL 41", "R U R' U R U2 R' F R U R' U' F'"),
+    ("OLL 42", "R' U' R U' R' U2 R F R U R' U' F'"),
+    ("OLL 43", "F' U' L' U L F"),
+    ("OLL 44", "F U R U' R' F'"),
+    ("OLL 45", "F R U R' U' F'"),
+    ("OLL 46", "R' U' R' F R F' U R"),
+    ("OLL 47", "R' U' R' F R F' R' F R F' U R"),
+    ("OLL 48", "F R U R' U' R U R' U' F'"),
+    ("OLL 49", "L F' L2 B L2 F L2 B' L"),
+    ("OLL 50", "L' B L2 F' L2 B' L2 F L'"),
+    ("OLL 51", "F U R U' R' U R U' R' F'"),
+    ("OLL 52", "R U R' U R U' B U' B' R'"),
+    ("OLL 53", "R' F2 L F L' F' L F L' F R"),
+    ("OLL 54", "L F2 R' F' R F R' F' R F' L'"),
+    ("OLL 55", "R' F R U R U' R2 F' R2 U' R' U R U R'"),
+    ("OLL 56", "L' B' L U' R' U R U' R' U R L' B L"),
+    ("OLL 57", "R U R' U' R' L F R F' L'"),
+];
+
+const PLL_CASES: [(&str, &str); 21] = [
+    ("Aa-perm", "R' F R' B2 R F' R' B2 R2"),
+    ("Ab-perm", "R B' R F2 R' B R F2 R2"),
+    ("E-perm", "R B' R' F R B R' F' R B R' F R B' R' F'"),
+    ("F-perm", "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R"),
+    ("Ga-perm", "R2 U R' U R' U' R U' R2 U' D R' U R D'"),
+    ("Gb-perm", "R' U' R U D' R2 U R' U R U' R U' R2 D"),
+    ("Gc-perm", "R2 U' R U' R U R' U R2 U D' R U' R' D"),
+    ("Gd-perm", "R U R' U' D R2 U' R U' R' U R' U R2 D'"),
+    ("H-perm", "R2 L2 D R2 L2 U2 R2 L2 D R2 L2"),
+    ("Ja-perm", "R2 D R D' R F2 L' U L F2"),
+    ("Jb-perm", "R U R' F' R U R' U' R' F R2 U' R'"),
+    (
+        "Na-perm",
+        "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'",
+    ),
+    ("Nb-perm", "R' U R U' R' F' U' F R U R' F R' F' R U' R"),
+    ("Ra-perm", "R U' R' U' R U R D R' U' R D' R' U2 R'"),
+    ("Rb-perm", "R2 F R U R U' R' F' R U2 R' U2 R"),
+    ("T-perm", "R U R' U' R' F R2 U' R' U' R U R' F'"),
+    ("Ua-perm", "R U' R U R U R U' R' U' R2"),
+    ("Ub-perm", "R2 U R U R' U' R' U' R' U R'"),
+    ("V-perm", "R' U R' U' R D' R' D R' U D' R2 U' R2 D R2"),
+    ("Y-perm", "F R U' R' U' R U R' F' R U R' U' R' F R F'"),
+    ("Z-perm", "R' L F R2 L2 B R2 L2 F R' L D2 R2 L2"),
+];
+
+const AUFS: [Option<Move>; 4] = [None, Some(Move::U), Some(Move::U2), Some(Move::Up)];
+
+// U 層の (corner_perm, corner_ori, edge_perm, edge_ori) をケース集合ごとに必要な部分だけ残したもの
+type Key = [u8; 16];
+
+fn key(cube: &Cube, set: CaseSet) -> Key {
+    let mut k = [0u8; 16];
+    for i in 0..4 {
+        match set {
+            CaseSet::Oll => {
+                k[4 + i] = cube.corner_ori[i];
+                k[12 + i] = cube.edge_ori[i];
+            }
+            CaseSet::Pll => {
+                k[i] = cube.corner_perm[i];
+                k[8 + i] = cube.edge_perm[i];
+            }
+        }
+    }
+    k
+}
+
+struct Case {
+    name: String,
+    algorithm: Vec<Move>,
+}
+
+struct Table {
+    cases: Vec<Case>,
+    index: HashMap<Key, usize>,
+}
+
+impl Table {
+    fn new() -> Self {
+        Table {
+            cases: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    // alg で（最後の AUF を除いて）揃う状態をすべて登録する
+    fn insert(&mut self, set: CaseSet, name: String, algorithm: Vec<Move>) {
+        let id = self.cases.len();
+        let inverse = invert_alg(&algorithm);
+        for post in AUFS {
+            let mut c = Cube::identity();
+            if let Some(m) = post {
+                c.apply_move(m);
+            }
+            let c = apply_alg(&c, &inverse);
+            self.index.entry(key(&c, set)).or_insert(id);
+        }
+        self.cases.push(Case { name, algorithm });
+    }
+
+    fn lookup(&self, cube: &Cube, set: CaseSet) -> Option<(Option<Move>, &Case)> {
+        AUFS.iter().find_map(|&auf| {
+            let mut c = *cube;
+            if let Some(m) = auf {
+                c.apply_move(m);
+            }
+            self.index
+                .get(&key(&c, set))
+                .map(|&id| (auf, &self.cases[id]))
+        })
+    }
+}
+
+struct Tables {
+    oll: Table,
+    pll: Table,
+}
+
+impl Tables {
+    fn get(&self, set: CaseSet) -> &Table {
+        match set {
+            CaseSet::Oll => &self.oll,
+            CaseSet::Pll => &self.pll,
+        }
+    }
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(build_tables)
+}
+
+fn build_tables() -> Tables {
+    let mut oll = Table::new();
+    oll.insert(CaseSet::Oll, "OLL skip".to_string(), Vec::new());
+    for (name, alg) in OLL_CASES {
+        oll.insert(CaseSet::Oll, name.to_string(), parse_alg(alg).unwrap());
+    }
+
+    let mut pll = Table::new();
+    pll.insert(CaseSet::Pll, "PLL skip".to_string(), Vec::new());
+    for (name, alg) in PLL_CASES {
+        pll.insert(CaseSet::Pll, name.to_string(), parse_alg(alg).unwrap());
+    }
+
+    Tables { oll, pll }
+}
+
+fn corners_oriented(cube: &Cube) -> bool {
+    cube.corner_ori[..4] == [0; 4]
+}
+
+/// F2L（D 面と中段）が揃っているか
+pub fn is_f2l_solved(cube: &Cube) -> bool {
+    (4..8).all(|i| cube.corner_perm[i] == i as u8 && cube.corner_ori[i] == 0)
+        && (4..12).all(|i| cube.edge_perm[i] == i as u8 && cube.edge_ori[i] == 0)
+}
+
+/// F2L が揃った状態の最終層のケースを判定する.
+/// 指定した集合の対象外（例: PLL で向きが揃っていない）なら None
+pub fn recognize(cube: &Cube, set: CaseSet) -> Option<CaseMatch> {
+    if !is_f2l_solved(cube) {
+        return None;
+    }
+    let edges_oriented = cube.edge_ori[..4] == [0; 4];
+    let in_set = match set {
+        CaseSet::Oll => true,
+        CaseSet::Pll => edges_oriented && corners_oriented(cube),
+    };
+    if !in_set {
+        return None;
+    }
+
+    tables()
+        .get(set)
+        .lookup(cube, set)
+        .map(|(auf, case)| CaseMatch {
+            set,
+            name: case.name.clone(),
+            auf,
+            algorithm: case.algorithm.clone(),
+        })
+}
+
+/// ケース集合に含まれる (名前, 参照手順) の一覧（スキップを除く）
+pub fn cases(set: CaseSet) -> Vec<(&'static str, &'static [Move])> {
+    tables().get(set).cases[1..]
+        .iter()
+        .map(|c| (c.name.as_str(), c.algorithm.as_slice()))
+        .collect()
+}
@@ -0,0 +1,217 @@
+//! recon.rs: ソルブの再現（スクランブル + 解法）から CFOP の各段階の区切りを求める
+
+use serde::Serialize;
+
+use crate::moves::Face;
+use crate::notation::ParseError;
+use crate::recognition::{recognize, CaseSet};
+use crate::stages::{rotation_to_bottom, Progress, Stage};
+use crate::symmetry::Rotation;
+use crate::{apply_alg, Cube, Move};
+
+/// 解法中の `//` コメントと, その行の手の範囲 `[start, end)`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Comment {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 持ち替えを展開した解法
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub moves: Vec<Move>,
+    pub comments: Vec<Comment>,
+}
+
+/// 1 つの段階: 手 `[start, end)` で完了した
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Split {
+    pub stage: String,
+    pub start: usize,
+    pub end: usize,
+    pub moves: usize,
+}
+
+/// 解析結果
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Reconstruction {
+    pub cross_face: Option<Face>,
+    pub splits: Vec<Split>,
+    pub oll_case: Option<String>,
+    pub pll_case: Option<String>,
+    pub total_moves: usize,
+    pub solved: bool,
+    pub comments: Vec<Comment>,
+}
+
+/// 解法の文字列を読む. `//` 以降はコメント, 括弧は無視し,
+/// 持ち替え (x, y, z) は以降の手の読み替えとして展開する
+pub fn parse_solution(text: &str) -> Result<Solution, ParseError> {
+    let mut frame = Rotation::identity();
+    let mut moves = Vec::new();
+    let mut comments = Vec::new();
+    let mut index = 0;
+    let mut line_offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let (body, comment) = match line.find("//") {
+            Some(p) => (&line[..p], Some(line[p + 2..].trim())),
+            None => (line, None),
+        };
+        let start = moves.len();
+        for token in body.split_whitespace() {
+            let offset = line_offset + (token.as_ptr() as usize - line.as_ptr() as usize);
+            let token_index = index;
+            index += 1;
+            let t = token.trim_matches(|c| c == '(' || c == ')');
+            if t.is_empty() {
+                continue;
+            }
+            if let Some(r) = parse_rotation(t) {
+                // 持ち替え後の面の名前 → 元の面
+                frame = r.inverse().then(&frame);
+                continue;
+            }
+            let m: Move = t.parse().map_err(|_| ParseError {
+                token: token.to_string(),
+                index: token_index,
+                offset,
+            })?;
+            moves.push(frame.apply_move(m));
+        }
+        if let Some(text) = comment.filter(|c| !c.is_empty()) {
+            comments.push(Comment {
+                text: text.to_string(),
+                start,
+                end: moves.len(),
+            });
+        }
+        line_offset += line.len();
+    }
+    Ok(Solution { moves, comments })
+}
+
+fn parse_rotation(t: &str) -> Option<Rotation> {
+    let base = match t.chars().next()? {
+        'x' => Rotation::x(),
+        'y' => Rotation::y(),
+        'z' => Rotation::z(),
+        _ => return None,
+    };
+    let turns = match &t[1..] {
+        "" => 1,
+        "2" | "2'" => 2,
+        "'" => 3,
+        _ => return None,
+    };
+    let mut r = Rotation::identity();
+    for _ in 0..turns {
+        r = r.then(&base);
+    }
+    Some(r)
+}
+
+/// スクランブル後に解法を 1 手ずつ適用し, 各段階が完了した手を求める.
+/// 段階の完了は「前の段階の完了以降で, 初めてその段階に達した手」とする
+/// （後の段階の手順で一時的に崩れても区切りは変わらない）
+pub fn analyze(scramble: &[Move], solution: &Solution) -> Reconstruction {
+    let moves = &solution.moves;
+    let mut states = Vec::with_capacity(moves.len() + 1);
+    let mut cube = apply_alg(&Cube::identity(), scramble);
+    states.push(cube);
+    for &m in moves {
+        cube.apply_move(m);
+        states.push(cube);
+    }
+
+    // F2L が最も早く終わるクロス面を採用する
+    let best = [Face::D, Face::U, Face::F, Face::B, Face::R, Face::L]
+        .into_iter()
+        .filter_map(|f| {
+            let progress: Vec<Progress> = states.iter().map(|c| c.progress_on(f)).collect();
+            let milestones = milestones(&progress);
+            milestones[0].1?;
+            let key = (
+                milestones[4].1.unwrap_or(usize::MAX),
+                milestones[0].1.unwrap_or(usize::MAX),
+            );
+            Some((key, f, milestones))
+        })
+        .min_by_key(|(key, _, _)| *key);
+
+    let mut cross_face = None;
+    let mut splits = Vec::new();
+    let mut oll_case = None;
+    let mut pll_case = None;
+    if let Some((_, face, milestones)) = best {
+        cross_face = Some(face);
+
+        // 最終層のケースは D に持ち替えて判定する
+        let down = rotation_to_bottom(face);
+        if let Some(i) = milestones[4].1 {
+            oll_case = recognize(&down.apply_cube(&states[i]), CaseSet::Oll).map(|m| m.name);
+        }
+        if let Some(i) = milestones[5].1 {
+            pll_case = recognize(&down.apply_cube(&states[i]), CaseSet::Pll).map(|m| m.name);
+        }
+
+        let mut start = 0;
+        for (stage, end) in milestones {
+            let Some(end) = end else { break };
+            splits.push(Split {
+                stage,
+                start,
+                end,
+                moves: end - start,
+            });
+            start = end;
+        }
+        // AUF が不要なら省く
+        if splits
+            .last()
+            .is_some_and(|s| s.stage == "AUF" && s.moves == 0)
+        {
+            splits.pop();
+        }
+    }
+
+    Reconstruction {
+        cross_face,
+        splits,
+        oll_case,
+        pll_case,
+        total_moves: moves.len(),
+        solved: cube.is_solved(),
+        comments: solution.comments.clone(),
+    }
+}
+
+const STAGE_NAMES: [&str; 8] = [
+    "cross", "F2L 1", "F2L 2", "F2L 3", "F2L 4", "OLL", "PLL", "AUF",
+];
+
+// STAGE_NAMES[step] の段階に達しているか
+fn reached(p: &Progress, step: usize) -> bool {
+    match step {
+        0 => p.stage >= Stage::Cross,
+        1..=4 => p.f2l_slots.len() >= step,
+        5 => p.last_layer_oriented,
+        6 => p.last_layer_permuted,
+        _ => p.stage == Stage::Solved,
+    }
+}
+
+// 段階の名前と, それが完了した状態の番号（= それまでに適用した手数）
+fn milestones(progress: &[Progress]) -> Vec<(String, Option<usize>)> {
+    let mut from = Some(0);
+    STAGE_NAMES
+        .iter()
+        .enumerate()
+        .map(|(step, name)| {
+            let hit = from.and_then(|f| (f..progress.len()).find(|&i| reached(&progress[i], step)));
+            from = hit;
+            (name.to_string(), hit)
+        })
+        .collect()
+}
@@ -0,0 +1,125 @@
+//! shape.rs: 多面体パズルの手を幾何から求める
+//!
+//! パーツを位置と, ステッカーの貼られた面の外向き法線で表す. 手はある軸の
+//! まわりに, 軸方向の成分が閾値より大きいパーツを回す回転.
+//! 結果は `Cube` と同じ約束の置換と向きの変化:
+//! 手の後の位置 j には位置 `perm[j]` にあったパーツが来て, 向きは `ori[j]` 増える.
+//!
+//! ステッカーの並びは, 面の番号が最小のものから外から見て反時計回り.
+//! パーツの向き o は「パーツの k 番目のステッカーが位置の (k + o) 番目に来る」こと.
+
+pub(crate) type Vec3 = [f64; 3];
+
+const EPS: f64 = 1e-6;
+
+/// パーツ: 位置とステッカーの法線（並べ替え済み）
+#[derive(Clone, Debug)]
+pub(crate) struct Piece {
+    pub pos: Vec3,
+    pub normals: Vec<Vec3>,
+}
+
+impl Piece {
+    /// `faces` の法線のうち `faces` の順で最初のものから, 外から見て反時計回りに並べる
+    pub fn new(pos: Vec3, faces: &[Vec3]) -> Piece {
+        let axis = normalize(pos);
+        let angle = |n: &Vec3| {
+            // pos に垂直な平面での, 最初の法線から見た角度
+            let (u, v) = plane_basis(axis, faces[0]);
+            let a = dot(*n, v).atan2(dot(*n, u));
+            if a < -EPS {
+                a + std::f64::consts::TAU
+            } else {
+                a.max(0.0)
+            }
+        };
+        let mut normals = faces.to_vec();
+        normals.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+        Piece { pos, normals }
+    }
+}
+
+/// 置換と向きの変化で表した手
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Turn {
+    pub perm: Vec<u8>,
+    pub ori: Vec<u8>,
+}
+
+impl Turn {
+    /// 状態 (perm, ori) に適用する. m は向きの数
+    pub fn apply(&self, perm: &mut [u8], ori: &mut [u8], m: u8) {
+        let (p, o) = (perm.to_vec(), ori.to_vec());
+        for j in 0..self.perm.len() {
+            let i = self.perm[j] as usize;
+            perm[j] = p[i];
+            ori[j] = (o[i] + self.ori[j]) % m;
+        }
+    }
+}
+
+/// 軸 axis（長さは問わない）のまわりに, 外から見て時計回りに angle だけ回す手.
+/// 軸方向の成分が cut より大きいパーツだけが動く
+pub(crate) fn turn(pieces: &[Piece], axis: Vec3, angle: f64, cut: f64) -> Turn {
+    let axis = normalize(axis);
+    let n = pieces.len();
+    let mut perm = vec![0; n];
+    let mut ori = vec![0; n];
+    for (i, p) in pieces.iter().enumerate() {
+        if dot(p.pos, axis) <= cut {
+            perm[i] = i as u8;
+            continue;
+        }
+        // 外から見て時計回り = 軸のまわりに負の向き
+        let pos = rotate(p.pos, axis, -angle);
+        let j = pieces
+            .iter()
+            .position(|q| close(q.pos, pos) && q.normals.len() == p.normals.len())
+            .expect("rotation must map pieces onto pieces");
+        let first = rotate(p.normals[0], axis, -angle);
+        let d = pieces[j]
+            .normals
+            .iter()
+            .position(|&m| close(m, first))
+            .expect("rotation must map stickers onto stickers");
+        perm[j] = i as u8;
+        ori[j] = d as u8;
+    }
+    Turn { perm, ori }
+}
+
+pub(crate) fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub(crate) fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub(crate) fn normalize(a: Vec3) -> Vec3 {
+    let len = dot(a, a).sqrt();
+    a.map(|x| x / len)
+}
+
+pub(crate) fn close(a: Vec3, b: Vec3) -> bool {
+    (0..3).all(|i| (a[i] - b[i]).abs() < EPS)
+}
+
+// 単位ベクトル axis に垂直な平面の基底（u は first の成分から作る）
+fn plane_basis(axis: Vec3, first: Vec3) -> (Vec3, Vec3) {
+    let d = dot(first, axis);
+    let u = normalize([0, 1, 2].map(|i| first[i] - d * axis[i]));
+    (u, cross(axis, u))
+}
+
+// 単位ベクトル axis のまわりに angle 回す（Rodrigues の公式）
+pub(crate) fn rotate(v: Vec3, axis: Vec3, angle: f64) -> Vec3 {
+    let (s, c) = angle.sin_cos();
+    let k = cross(axis, v);
+    let d = dot(axis, v);
+    [0, 1, 2].map(|i| v[i] * c + k[i] * s + axis[i] * d * (1.0 - c))
+}
@@ -0,0 +1,426 @@
+//! skewb.rs: スキューブ
+//!
+//! 角 8 つ（位置と向き 0..3, 番号は `Cube` の角と同じ）とセンター 6 つ（`Face::ALL` の順）.
+//! 手は WCA の記法で, R (DRB), L (DLF), U (ULB), B (DLB) の角を軸に,
+//! その角の側の半分を角から見て時計回りに 120 度回す（`'` で反時計回り）.
+//! UFR の角はどの手でも動かないので, 全体の持ち替えを考えずに済む.
+//!
+//! 角は座標の積の符号で 2 組（テトラッド）に分かれ, 組の外には移らない.
+//! 手は軸の角をねじり, もう一方の組の 3 つを回すので, 組ごとのねじれの総和は
+//! もう一方の組の置換で決まる（UFR の組は 3 巡回の回転量, 他方は A4 / V4 の類）.
+//! 解法は角の部分（8,748 状態）とセンター（360 状態）の組 3,149,280 状態の
+//! 最短手数表を幅優先で作って引く.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::coord::{Coordinate, ProductTable};
+use crate::cycles::perm_cycles;
+use crate::facelet::CORNER_FACELETS;
+use crate::geometry::{normal, position};
+use crate::notation::{tokens, ParseError};
+use crate::random::{fix_parity, randomize_orientation, Rng};
+use crate::shape::{turn, Piece, Turn, Vec3};
+use crate::solver::SolveError;
+use crate::state::parity;
+use crate::Face;
+
+/// 動かない角 (UFR) の位置
+const FIXED_CORNER: usize = 1;
+
+// テトラッドごとの角の位置. 0 番は UFR を含む組で, UFR 以外の 3 つ
+const TETRADS: [&[usize]; 2] = [&[3, 4, 6], &[0, 2, 5, 7]];
+
+/// 手の軸
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SkewbAxis {
+    R,
+    L,
+    U,
+    B,
+}
+
+impl SkewbAxis {
+    pub const ALL: [SkewbAxis; 4] = [SkewbAxis::R, SkewbAxis::L, SkewbAxis::U, SkewbAxis::B];
+
+    /// 軸になる角の位置
+    pub fn corner(self) -> usize {
+        match self {
+            SkewbAxis::R => 6,
+            SkewbAxis::L => 4,
+            SkewbAxis::U => 3,
+            SkewbAxis::B => 7,
+        }
+    }
+
+    fn letter(self) -> char {
+        match self {
+            SkewbAxis::R => 'R',
+            SkewbAxis::L => 'L',
+            SkewbAxis::U => 'U',
+            SkewbAxis::B => 'B',
+        }
+    }
+}
+
+/// スキューブの手
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SkewbMove {
+    pub axis: SkewbAxis,
+    /// 時計回りの 120 度を単位とした回転量 (1: 時計回り, 2: 反時計回り)
+    pub turns: u8,
+}
+
+impl SkewbMove {
+    /// すべての手（R, R', L, L', U, U', B, B'）
+    pub const ALL: [SkewbMove; 8] = {
+        let mut moves = [SkewbMove {
+            axis: SkewbAxis::R,
+            turns: 1,
+        }; 8];
+        let mut i = 0;
+        while i < 8 {
+            moves[i].axis = SkewbAxis::ALL[i / 2];
+            moves[i].turns = (i % 2) as u8 + 1;
+            i += 1;
+        }
+        moves
+    };
+
+    pub fn inverse(self) -> SkewbMove {
+        SkewbMove {
+            turns: 3 - self.turns,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for SkewbMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let suffix = if self.turns == 2 { "'" } else { "" };
+        write!(f, "{}{}", self.axis.letter(), suffix)
+    }
+}
+
+/// `R U' L B'` のような手順をパースする
+pub fn parse_skewb_alg(s: &str) -> Result<Vec<SkewbMove>, ParseError> {
+    tokens(s)
+        .enumerate()
+        .map(|(index, (offset, token))| {
+            parse_move(token).ok_or_else(|| ParseError {
+                token: token.to_string(),
+                index,
+                offset,
+            })
+        })
+        .collect()
+}
+
+fn parse_move(token: &str) -> Option<SkewbMove> {
+    let mut chars = token.chars();
+    let c = chars.next()?;
+    let turns = match chars.as_str() {
+        "" => 1,
+        "'" => 2,
+        _ => return None,
+    };
+    let axis = *SkewbAxis::ALL.iter().find(|a| a.letter() == c)?;
+    Some(SkewbMove { axis, turns })
+}
+
+/// 手順を記法にする
+pub fn format_skewb_alg(moves: &[SkewbMove]) -> String {
+    moves
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// スキューブの状態
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Skewb {
+    pub corner_perm: [u8; 8],
+    pub corner_ori: [u8; 8],
+    pub center_perm: [u8; 6],
+}
+
+/// 合法性の内訳
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkewbLegality {
+    /// センターの置換の偶奇（偶置換でなければならない）
+    pub center_parity: u8,
+    /// UFR の角がそのまま
+    pub fixed_corner: bool,
+    /// 角がすべて元のテトラッドにある
+    pub tetrads: bool,
+    /// テトラッドごとの角の置換の偶奇（どちらも偶置換）
+    pub corner_parities: [u8; 2],
+    /// テトラッドごとの角のねじれの総和 mod 3
+    pub twist_sums: [u8; 2],
+    /// もう一方のテトラッドの置換から決まる, ねじれの総和のあるべき値
+    pub expected_twist_sums: [u8; 2],
+    pub is_legal: bool,
+}
+
+// 手ごとの角とセンターの動き
+struct Turns {
+    corners: Turn,
+    centers: Turn,
+}
+
+fn turns(axis: SkewbAxis) -> &'static Turns {
+    static TABLE: OnceLock<Vec<Turns>> = OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        let vec = |v: [i8; 3]| -> Vec3 { v.map(|x| x as f64) };
+        let corners: Vec<Piece> = CORNER_FACELETS
+            .iter()
+            .map(|faces| {
+                let normals: Vec<Vec3> = faces.iter().map(|&f| vec(normal(f))).collect();
+                Piece::new(vec(position(faces)), &normals)
+            })
+            .collect();
+        let centers: Vec<Piece> = Face::ALL
+            .iter()
+            .map(|&f| Piece::new(vec(normal(f)), &[vec(normal(f))]))
+            .collect();
+        SkewbAxis::ALL
+            .iter()
+            .map(|a| {
+                let axis = corners[a.corner()].pos;
+                let angle = std::f64::consts::TAU / 3.0;
+                Turns {
+                    corners: turn(&corners, axis, angle, 0.0),
+                    centers: turn(&centers, axis, angle, 0.0),
+                }
+            })
+            .collect()
+    });
+    &table[axis as usize]
+}
+
+// 角の位置のテトラッド（座標の積が正なら 0）
+fn tetrad(pos: usize) -> usize {
+    let p = position(&CORNER_FACELETS[pos]);
+    (p[0] * p[1] * p[2] < 0) as usize
+}
+
+// 組の中での置換（偶置換）から, 各テトラッドのねじれの総和のあるべき値を求める.
+// UFR の組の総和は他方の置換の A4 / V4 での類, 他方の総和は UFR の組の回転量
+fn expected_twists(perms: &[Vec<u8>; 2]) -> [u8; 2] {
+    // 他方の組で {0, 1} の対が移る先の対（0 と組む番号 - 1）
+    let (a, b) = (perms[1][0], perms[1][1]);
+    let partner = match (a, b) {
+        (0, x) | (x, 0) => x,
+        _ => 6 - a - b,
+    };
+    [(4 - partner) % 3, perms[0][0]]
+}
+
+impl Skewb {
+    pub fn identity() -> Self {
+        Skewb {
+            corner_perm: [0, 1, 2, 3, 4, 5, 6, 7],
+            corner_ori: [0; 8],
+            center_perm: [0, 1, 2, 3, 4, 5],
+        }
+    }
+
+    pub fn apply_move(&mut self, m: SkewbMove) {
+        let t = turns(m.axis);
+        for _ in 0..m.turns {
+            t.corners
+                .apply(&mut self.corner_perm, &mut self.corner_ori, 3);
+            let mut ori = [0; 6];
+            t.centers.apply(&mut self.center_perm, &mut ori, 1);
+        }
+    }
+
+    pub fn apply_alg(&self, alg: &[SkewbMove]) -> Self {
+        let mut s = *self;
+        for &m in alg {
+            s.apply_move(m);
+        }
+        s
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == Skewb::identity()
+    }
+
+    pub fn legality(&self) -> SkewbLegality {
+        let fixed_corner = self.corner_perm[FIXED_CORNER] as usize == FIXED_CORNER
+            && self.corner_ori[FIXED_CORNER] == 0;
+        let mut seen = [false; 8];
+        let corners_ok = self
+            .corner_perm
+            .iter()
+            .all(|&p| (p as usize) < 8 && !std::mem::replace(&mut seen[p as usize], true))
+            && self.corner_ori.iter().all(|&o| o < 3);
+        let mut seen = [false; 6];
+        let centers_ok = self
+            .center_perm
+            .iter()
+            .all(|&p| (p as usize) < 6 && !std::mem::replace(&mut seen[p as usize], true));
+        // 範囲外の番号があるときは偶奇を求めない（0 とする）
+        let center_parity = if centers_ok {
+            parity(&self.center_perm)
+        } else {
+            0
+        };
+        let tetrads =
+            corners_ok && (0..8).all(|pos| tetrad(pos) == tetrad(self.corner_perm[pos] as usize));
+        let perms = [0, 1].map(|t| self.tetrad_perm(t));
+        let corner_parities = [0, 1].map(|t| parity(&perms[t]));
+        let twist_sums = TETRADS.map(|positions| {
+            (positions
+                .iter()
+                .map(|&p| self.corner_ori[p] as u32)
+                .sum::<u32>()
+                % 3) as u8
+        });
+        let expected_twist_sums = expected_twists(&perms);
+        let is_legal = centers_ok
+            && center_parity == 0
+            && fixed_corner
+            && tetrads
+            && corner_parities == [0, 0]
+            && twist_sums == expected_twist_sums;
+        SkewbLegality {
+            center_parity,
+            fixed_corner,
+            tetrads,
+            corner_parities,
+            twist_sums,
+            expected_twist_sums,
+            is_legal,
+        }
+    }
+
+    pub fn is_legal(&self) -> bool {
+        self.legality().is_legal
+    }
+
+    // テトラッド t の置換を組の中での番号に読み替えたもの
+    fn tetrad_perm(&self, t: usize) -> Vec<u8> {
+        let positions = TETRADS[t];
+        positions
+            .iter()
+            .map(|&p| {
+                let piece = self.corner_perm[p] as usize;
+                positions.iter().position(|&q| q == piece).unwrap_or(0) as u8
+            })
+            .collect()
+    }
+
+    /// 一様にランダムな合法状態
+    pub fn random(rng: &mut Rng) -> Self {
+        let mut s = Skewb::identity();
+        let perms = TETRADS.map(|positions| {
+            let mut perm: Vec<u8> = (0..positions.len() as u8).collect();
+            rng.shuffle(&mut perm);
+            fix_parity(&mut perm, 0);
+            perm
+        });
+        let targets = expected_twists(&perms);
+        for (t, positions) in TETRADS.iter().enumerate() {
+            let mut ori = vec![0; positions.len()];
+            randomize_orientation(rng, &mut ori, 3);
+            ori[0] = (ori[0] + targets[t]) % 3;
+            for (i, &p) in positions.iter().enumerate() {
+                s.corner_perm[p] = positions[perms[t][i] as usize] as u8;
+                s.corner_ori[p] = ori[i];
+            }
+        }
+        rng.shuffle(&mut s.center_perm);
+        fix_parity(&mut s.center_perm, 0);
+        s
+    }
+
+    /// 角の置換のサイクル
+    pub fn corner_cycles(&self) -> Vec<Vec<u8>> {
+        perm_cycles(&self.corner_perm)
+    }
+
+    /// センターの置換のサイクル
+    pub fn center_cycles(&self) -> Vec<Vec<u8>> {
+        perm_cycles(&self.center_perm)
+    }
+}
+
+impl Default for Skewb {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+type Corners = ([u8; 8], [u8; 8]);
+
+/// 3,149,280 状態の最短手数表
+pub struct SkewbTables {
+    table: ProductTable<Corners, [u8; 6]>,
+}
+
+impl SkewbTables {
+    pub fn build() -> Self {
+        let id = Skewb::identity();
+        let corners = Coordinate::build((id.corner_perm, id.corner_ori), 8, |&(perm, ori), m| {
+            let s = Skewb {
+                corner_perm: perm,
+                corner_ori: ori,
+                ..id
+            }
+            .apply_alg(&[SkewbMove::ALL[m]]);
+            (s.corner_perm, s.corner_ori)
+        });
+        let centers = Coordinate::build(id.center_perm, 8, |&perm, m| {
+            Skewb {
+                center_perm: perm,
+                ..id
+            }
+            .apply_alg(&[SkewbMove::ALL[m]])
+            .center_perm
+        });
+        SkewbTables {
+            table: ProductTable::build(corners, centers, 8),
+        }
+    }
+
+    /// 状態の数
+    pub fn states(&self) -> usize {
+        self.table.len()
+    }
+
+    /// 最長の最短手数
+    pub fn diameter(&self) -> usize {
+        self.table.diameter()
+    }
+
+    /// 最短手数
+    pub fn distance(&self, s: &Skewb) -> Result<usize, SolveError> {
+        if !s.is_legal() {
+            return Err(SolveError::Illegal);
+        }
+        self.table
+            .distance(&(s.corner_perm, s.corner_ori), &s.center_perm)
+            .ok_or(SolveError::InvalidTables)
+    }
+
+    /// 最短手数の解
+    pub fn solve(&self, s: &Skewb) -> Result<Vec<SkewbMove>, SolveError> {
+        if !s.is_legal() {
+            return Err(SolveError::Illegal);
+        }
+        let solution = self
+            .table
+            .solve(&(s.corner_perm, s.corner_ori), &s.center_perm)
+            .ok_or(SolveError::InvalidTables)?;
+        Ok(solution.into_iter().map(|m| SkewbMove::ALL[m]).collect())
+    }
+
+    /// ランダムステートスクランブル
+    pub fn random_scramble(&self, rng: &mut Rng) -> Vec<SkewbMove> {
+        let solution = self.solve(&Skewb::random(rng)).unwrap();
+        solution.iter().rev().map(|m| m.inverse()).collect()
+    }
+}
@@ -0,0 +1,702 @@
+//! solver.rs: 2 フェーズ法（Kociemba）による解法探索
+//!
+//! フェーズ 1 で <U, D, L2, R2, F2, B2> の部分群 G1 へ入れ（角のねじれ, 辺の反転,
+//! E スライスの辺の位置を揃える）, フェーズ 2 で G1 の手だけで完成させる.
+//! フェーズ 1 の解を深さ順に列挙し続けることで, 手数の短い解へ改善していく.
+//!
+//! 探索は [`SolverSession::step`] でノード数を区切って少しずつ進められるので,
+//! ブラウザの Web Worker などから中断・再開しながら呼び出せる.
+//! 枝刈り表は [`SolverTables::to_bytes`] で書き出し, [`SolverTables::from_bytes`] で読み込める.
+
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use crate::{Cube, Move};
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum SolveError {
+    #[error("cube state is not legal")]
+    Illegal,
+    #[error("no solution found for stage {0}")]
+    Stuck(&'static str),
+    #[error("invalid pruning table data")]
+    InvalidTables,
+}
+
+const N_TWIST: usize = 2187; // 3^7
+const N_FLIP: usize = 2048; // 2^11
+const N_SLICE: usize = 495; // C(12, 4)
+const N_PERM8: usize = 40320; // 8!
+const N_PERM4: usize = 24; // 4!
+
+// フェーズ 2 で使う手（Move::ALL の index）: U*, D*, L2, R2, F2, B2
+const PHASE2_MOVES: [usize; 10] = [0, 1, 2, 3, 4, 5, 7, 10, 13, 16];
+
+// U/D 層の辺の位置（フェーズ 2 の辺の置換の対象）
+const UD_EDGES: [usize; 8] = [0, 1, 2, 3, 8, 9, 10, 11];
+
+// 枝刈り表のファイル形式の先頭
+const MAGIC: &[u8; 4] = b"RPT1";
+
+/// 移動表と枝刈り表
+pub struct SolverTables {
+    twist_move: Vec<[u16; 18]>,
+    flip_move: Vec<[u16; 18]>,
+    slice_move: Vec<[u16; 18]>,
+    corner_perm_move: Vec<[u16; 10]>,
+    edge_perm_move: Vec<[u16; 10]>,
+    slice_perm_move: Vec<[u8; 10]>,
+    // 枝刈り表: 揃うまでの最短手数（下界）
+    twist_slice_prune: Vec<u8>,
+    flip_slice_prune: Vec<u8>,
+    corner_slice_prune: Vec<u8>,
+    edge_slice_prune: Vec<u8>,
+}
+
+impl SolverTables {
+    /// すべての表を計算する（数秒かかる）
+    pub fn build() -> Self {
+        let mut tables = Self::move_tables();
+        let (twist, flip, slice) = (&tables.twist_move, &tables.flip_move, &tables.slice_move);
+        tables.twist_slice_prune = prune_table(N_TWIST, N_SLICE, 18, |a, s, m| {
+            (twist[a][m] as usize, slice[s][m] as usize)
+        });
+        tables.flip_slice_prune = prune_table(N_FLIP, N_SLICE, 18, |a, s, m| {
+            (flip[a][m] as usize, slice[s][m] as usize)
+        });
+        let (cp, ep, sp) = (
+            &tables.corner_perm_move,
+            &tables.edge_perm_move,
+            &tables.slice_perm_move,
+        );
+        tables.corner_slice_prune = prune_table(N_PERM8, N_PERM4, 10, |a, s, m| {
+            (cp[a][m] as usize, sp[s][m] as usize)
+        });
+        tables.edge_slice_prune = prune_table(N_PERM8, N_PERM4, 10, |a, s, m| {
+            (ep[a][m] as usize, sp[s][m] as usize)
+        });
+        tables
+    }
+
+    /// 枝刈り表をバイト列に書き出す（移動表は読み込み時に再計算する）
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        for table in [
+            &self.twist_slice_prune,
+            &self.flip_slice_prune,
+            &self.corner_slice_prune,
+            &self.edge_slice_prune,
+        ] {
+            bytes.extend_from_slice(table);
+        }
+        bytes
+    }
+
+    /// [`SolverTables::to_bytes`] で書き出したバイト列から読み込む
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SolveError> {
+        let body = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .ok_or(SolveError::InvalidTables)?;
+        let sizes = [
+            N_TWIST * N_SLICE,
+            N_FLIP * N_SLICE,
+            N_PERM8 * N_PERM4,
+            N_PERM8 * N_PERM4,
+        ];
+        if body.len() != sizes.iter().sum::<usize>() {
+            return Err(SolveError::InvalidTables);
+        }
+        let mut tables = Self::move_tables();
+        let mut rest = body;
+        for (table, size) in [
+            &mut tables.twist_slice_prune,
+            &mut tables.flip_slice_prune,
+            &mut tables.corner_slice_prune,
+            &mut tables.edge_slice_prune,
+        ]
+        .into_iter()
+        .zip(sizes)
+        {
+            let (head, tail) = rest.split_at(size);
+            // 揃った状態は 0 でなければならない
+            if head[0] != 0 {
+                return Err(SolveError::InvalidTables);
+            }
+            *table = head.to_vec();
+            rest = tail;
+        }
+        Ok(tables)
+    }
+
+    fn move_tables() -> Self {
+        let twist_move = (0..N_TWIST)
+            .map(|i| {
+                let mut c = Cube::identity();
+                set_twist(&mut c, i);
+                Move::ALL.map(|m| twist(&moved(&c, m)) as u16)
+            })
+            .collect();
+        let flip_move = (0..N_FLIP)
+            .map(|i| {
+                let mut c = Cube::identity();
+                set_flip(&mut c, i);
+                Move::ALL.map(|m| flip(&moved(&c, m)) as u16)
+            })
+            .collect();
+        let slice_move = (0..N_SLICE)
+            .map(|i| {
+                let mut c = Cube::identity();
+                set_slice(&mut c, i);
+                Move::ALL.map(|m| slice(&moved(&c, m)) as u16)
+            })
+            .collect();
+        let corner_perm_move = (0..N_PERM8)
+            .map(|i| {
+                let mut c = Cube::identity();
+                c.corner_perm = unrank_perm8(i);
+                PHASE2_MOVES.map(|m| corner_perm(&moved(&c, Move::ALL[m])) as u16)
+            })
+            .collect();
+        let edge_perm_move = (0..N_PERM8)
+            .map(|i| {
+                let mut c = Cube::identity();
+                set_ud_edge_perm(&mut c, i);
+                PHASE2_MOVES.map(|m| ud_edge_perm(&moved(&c, Move::ALL[m])) as u16)
+            })
+            .collect();
+        let slice_perm_move = (0..N_PERM4)
+            .map(|i| {
+                let mut c = Cube::identity();
+                set_slice_perm(&mut c, i);
+                PHASE2_MOVES.map(|m| slice_perm(&moved(&c, Move::ALL[m])) as u8)
+            })
+            .collect();
+
+        SolverTables {
+            twist_move,
+            flip_move,
+            slice_move,
+            corner_perm_move,
+            edge_perm_move,
+            slice_perm_move,
+            twist_slice_prune: Vec::new(),
+            flip_slice_prune: Vec::new(),
+            corner_slice_prune: Vec::new(),
+            edge_slice_prune: Vec::new(),
+        }
+    }
+
+    fn phase1_bound(&self, c: Phase1) -> usize {
+        let a = self.twist_slice_prune[c.twist * N_SLICE + c.slice];
+        let b = self.flip_slice_prune[c.flip * N_SLICE + c.slice];
+        a.max(b) as usize
+    }
+
+    fn phase2_bound(&self, c: Phase2) -> usize {
+        let a = self.corner_slice_prune[c.corner * N_PERM4 + c.slice];
+        let b = self.edge_slice_prune[c.edge * N_PERM4 + c.slice];
+        a.max(b) as usize
+    }
+}
+
+fn moved(cube: &Cube, m: Move) -> Cube {
+    let mut c = *cube;
+    c.apply_move(m);
+    c
+}
+
+// (a, s) の組を a * n_s + s で並べた表を, 揃った状態からの幅優先探索で埋める
+pub(crate) fn prune_table(
+    n_a: usize,
+    n_s: usize,
+    n_moves: usize,
+    step: impl Fn(usize, usize, usize) -> (usize, usize),
+) -> Vec<u8> {
+    let mut table = vec![u8::MAX; n_a * n_s];
+    table[0] = 0;
+    let mut depth = 0;
+    let mut filled = 1;
+    while filled < table.len() {
+        let mut changed = false;
+        for i in 0..table.len() {
+            if table[i] != depth {
+                continue;
+            }
+            let (a, s) = (i / n_s, i % n_s);
+            for m in 0..n_moves {
+                let (na, ns) = step(a, s, m);
+                let j = na * n_s + ns;
+                if table[j] == u8::MAX {
+                    table[j] = depth + 1;
+                    filled += 1;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+        depth += 1;
+    }
+    table
+}
+
+// ---- 座標 ----
+
+fn twist(c: &Cube) -> usize {
+    c.corner_ori[..7]
+        .iter()
+        .fold(0, |acc, &o| acc * 3 + o as usize)
+}
+
+fn set_twist(c: &mut Cube, mut value: usize) {
+    let mut sum = 0;
+    for i in (0..7).rev() {
+        c.corner_ori[i] = (value % 3) as u8;
+        sum += c.corner_ori[i];
+        value /= 3;
+    }
+    c.corner_ori[7] = (3 - sum % 3) % 3;
+}
+
+fn flip(c: &Cube) -> usize {
+    c.edge_ori[..11]
+        .iter()
+        .fold(0, |acc, &o| acc * 2 + o as usize)
+}
+
+fn set_flip(c: &mut Cube, mut value: usize) {
+    let mut sum = 0;
+    for i in (0..11).rev() {
+        c.edge_ori[i] = (value % 2) as u8;
+        sum += c.edge_ori[i];
+        value /= 2;
+    }
+    c.edge_ori[11] = sum % 2;
+}
+
+fn is_slice_edge(piece: u8) -> bool {
+    (4..8).contains(&piece)
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+// E スライスの辺 (FL, FR, BR, BL) がある位置の組合せ. 揃った状態が 0
+fn slice(c: &Cube) -> usize {
+    // 位置 4..7 が 0..3 に来るようずらし, 組合せ数系で数える（揃った状態が 0）
+    let mut value = 0;
+    let mut k = 0;
+    for p in 0..12 {
+        let pos = (p + 4) % 12;
+        if is_slice_edge(c.edge_perm[pos]) {
+            value += binomial(p, k + 1);
+            k += 1;
+        }
+    }
+    value
+}
+
+fn set_slice(c: &mut Cube, value: usize) {
+    // 組合せの番号 → 位置の組（大きい位置から貪欲に決める）
+    let mut rest = value;
+    let mut chosen = [false; 12];
+    let mut k = 4;
+    for p in (0..12).rev() {
+        if k > 0 && binomial(p, k) <= rest {
+            rest -= binomial(p, k);
+            chosen[p] = true;
+            k -= 1;
+        }
+    }
+    let (mut slice_piece, mut other_piece) = (4u8, 0u8);
+    for (p, &is_slice) in chosen.iter().enumerate() {
+        let pos = (p + 4) % 12;
+        if is_slice {
+            c.edge_perm[pos] = slice_piece;
+            slice_piece += 1;
+        } else {
+            c.edge_perm[pos] = other_piece;
+            other_piece += if other_piece == 3 { 5 } else { 1 };
+        }
+    }
+}
+
+// 置換の番号（Lehmer 符号）
+pub(crate) fn rank_perm(perm: &[u8]) -> usize {
+    let n = perm.len();
+    (0..n).fold(0, |acc, i| {
+        let smaller = perm[i + 1..].iter().filter(|&&p| p < perm[i]).count();
+        acc * (n - i) + smaller
+    })
+}
+
+pub(crate) fn unrank_perm<const N: usize>(mut value: usize) -> [u8; N] {
+    let mut digits = [0; N];
+    for i in (0..N).rev() {
+        digits[i] = value % (N - i);
+        value /= N - i;
+    }
+    let mut pool: Vec<u8> = (0..N as u8).collect();
+    digits.map(|d| pool.remove(d))
+}
+
+fn unrank_perm8(value: usize) -> [u8; 8] {
+    unrank_perm(value)
+}
+
+fn corner_perm(c: &Cube) -> usize {
+    rank_perm(&c.corner_perm)
+}
+
+fn ud_edge_index(piece: u8) -> u8 {
+    if piece < 4 {
+        piece
+    } else {
+        piece - 4
+    }
+}
+
+fn ud_edge_perm(c: &Cube) -> usize {
+    let perm = UD_EDGES.map(|p| ud_edge_index(c.edge_perm[p]));
+    rank_perm(&perm)
+}
+
+fn set_ud_edge_perm(c: &mut Cube, value: usize) {
+    let perm = unrank_perm8(value);
+    for (i, &p) in UD_EDGES.iter().enumerate() {
+        c.edge_perm[p] = UD_EDGES[perm[i] as usize] as u8;
+    }
+}
+
+fn slice_perm(c: &Cube) -> usize {
+    let perm: Vec<u8> = c.edge_perm[4..8].iter().map(|&p| p - 4).collect();
+    rank_perm(&perm)
+}
+
+fn set_slice_perm(c: &mut Cube, value: usize) {
+    let perm: [u8; 4] = unrank_perm(value);
+    for (i, p) in perm.iter().enumerate() {
+        c.edge_perm[4 + i] = p + 4;
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Phase1 {
+    twist: usize,
+    flip: usize,
+    slice: usize,
+}
+
+impl Phase1 {
+    fn of(c: &Cube) -> Self {
+        Phase1 {
+            twist: twist(c),
+            flip: flip(c),
+            slice: slice(c),
+        }
+    }
+
+    fn apply(self, t: &SolverTables, m: usize) -> Self {
+        Phase1 {
+            twist: t.twist_move[self.twist][m] as usize,
+            flip: t.flip_move[self.flip][m] as usize,
+            slice: t.slice_move[self.slice][m] as usize,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Phase2 {
+    corner: usize,
+    edge: usize,
+    slice: usize,
+}
+
+impl Phase2 {
+    fn of(c: &Cube) -> Self {
+        Phase2 {
+            corner: corner_perm(c),
+            edge: ud_edge_perm(c),
+            slice: slice_perm(c),
+        }
+    }
+
+    fn apply(self, t: &SolverTables, j: usize) -> Self {
+        Phase2 {
+            corner: t.corner_perm_move[self.corner][j] as usize,
+            edge: t.edge_perm_move[self.edge][j] as usize,
+            slice: t.slice_perm_move[self.slice][j] as usize,
+        }
+    }
+
+    fn is_solved(self) -> bool {
+        self.corner == 0 && self.edge == 0 && self.slice == 0
+    }
+}
+
+// 直前の手 last の後に m を行う意味があるか（同じ面の連続と, 向かい合う面の逆順を除く）
+fn allowed(last: Option<usize>, m: usize) -> bool {
+    match last {
+        None => true,
+        Some(l) => {
+            let (f, g) = (m / 3, l / 3);
+            f != g && !(f / 2 == g / 2 && f < g)
+        }
+    }
+}
+
+/// 探索の進み具合
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolverProgress {
+    /// これまでに調べたノード数
+    pub nodes: u64,
+    /// 現在のフェーズ 1 の深さ
+    pub depth: usize,
+    /// これまでで最短の解
+    pub best: Option<Vec<Move>>,
+    /// この step で解が改善したか
+    pub improved: bool,
+    /// 探索が終わったか（目標の手数に達したか, より短い解がないことを確かめた）
+    pub done: bool,
+}
+
+// フェーズ 1 の探索の 1 段
+struct Frame {
+    coord: Phase1,
+    next: usize,
+}
+
+/// 中断・再開できる 2 フェーズ探索
+pub struct SolverSession {
+    tables: Arc<SolverTables>,
+    cube: Cube,
+    target_length: usize,
+    depth: usize,
+    started: bool,
+    stack: Vec<Frame>,
+    path: Vec<usize>,
+    best: Option<Vec<Move>>,
+    nodes: u64,
+    done: bool,
+}
+
+/// これより長い解は探さない
+const MAX_LENGTH: usize = 30;
+
+impl SolverSession {
+    /// target_length 手以下の解が見つかった時点で探索を終える
+    pub fn new(
+        cube: &Cube,
+        tables: Arc<SolverTables>,
+        target_length: usize,
+    ) -> Result<Self, SolveError> {
+        if !cube.is_legal() {
+            return Err(SolveError::Illegal);
+        }
+        let solved = cube.is_solved();
+        Ok(SolverSession {
+            tables,
+            cube: *cube,
+            target_length,
+            depth: 0,
+            started: false,
+            stack: Vec::new(),
+            path: Vec::new(),
+            best: solved.then(Vec::new),
+            nodes: 0,
+            done: solved,
+        })
+    }
+
+    /// 最大 budget ノードだけ探索を進める. 解が改善したらそこで戻る
+    pub fn step(&mut self, budget: u64) -> SolverProgress {
+        let limit = self.nodes + budget;
+        let mut improved = false;
+        while !self.done && !improved && self.nodes < limit {
+            improved = self.advance();
+        }
+        self.progress(improved)
+    }
+
+    /// 探索が終わるまで進める
+    pub fn run(&mut self) -> SolverProgress {
+        let mut improved = false;
+        while !self.done {
+            improved |= self.advance();
+        }
+        self.progress(improved)
+    }
+
+    pub fn best(&self) -> Option<&[Move]> {
+        self.best.as_deref()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    fn progress(&self, improved: bool) -> SolverProgress {
+        SolverProgress {
+            nodes: self.nodes,
+            depth: self.depth,
+            best: self.best.clone(),
+            improved,
+            done: self.done,
+        }
+    }
+
+    fn max_length(&self) -> usize {
+        self.best.as_ref().map_or(MAX_LENGTH, |b| b.len() - 1)
+    }
+
+    // フェーズ 1 の探索を 1 手分進める. 解が改善したら true
+    fn advance(&mut self) -> bool {
+        let tables = Arc::clone(&self.tables);
+        let t = &*tables;
+
+        let Some(top) = self.stack.last_mut() else {
+            return self.next_depth();
+        };
+
+        if top.next == 18 {
+            self.stack.pop();
+            self.path.pop();
+            return false;
+        }
+        let m = top.next;
+        top.next += 1;
+        let coord = top.coord;
+        if !allowed(self.path.last().copied(), m) {
+            return false;
+        }
+
+        self.nodes += 1;
+        let next = coord.apply(t, m);
+        let remaining = self.depth - self.path.len() - 1;
+        let h = t.phase1_bound(next);
+        if h > remaining {
+            return false;
+        }
+        self.path.push(m);
+        if remaining > 0 {
+            self.stack.push(Frame {
+                coord: next,
+                next: 0,
+            });
+            return false;
+        }
+
+        // フェーズ 1 の解. 最後の手が G1 の手なら, より短いフェーズ 1 の解で調べ済み
+        let improved = !PHASE2_MOVES.contains(&m) && self.try_phase2();
+        self.path.pop();
+        improved
+    }
+
+    // 前の深さを調べ終えたので, 次の深さの探索を始める
+    fn next_depth(&mut self) -> bool {
+        if self.started {
+            self.depth += 1;
+        }
+        self.started = true;
+        self.nodes += 1;
+        if self.depth > self.max_length() {
+            self.done = true;
+            return false;
+        }
+        let root = Phase1::of(&self.cube);
+        let h = self.tables.phase1_bound(root);
+        if self.depth == 0 {
+            // すでに G1 にあればフェーズ 2 だけで解く
+            return h == 0 && self.try_phase2();
+        }
+        if h <= self.depth {
+            self.stack.push(Frame {
+                coord: root,
+                next: 0,
+            });
+        }
+        false
+    }
+
+    // 現在のフェーズ 1 の解に続くフェーズ 2 の解を探す
+    fn try_phase2(&mut self) -> bool {
+        let budget = self.max_length().saturating_sub(self.path.len());
+        let mut cube = self.cube;
+        for &m in &self.path {
+            cube.apply_move(Move::ALL[m]);
+        }
+        let start = Phase2::of(&cube);
+        let t = &*self.tables;
+        let h = t.phase2_bound(start);
+        let last = self.path.last().copied();
+
+        let mut moves = Vec::new();
+        let mut nodes = 0;
+        let found = (h..=budget).any(|depth| phase2(t, start, depth, last, &mut moves, &mut nodes));
+        self.nodes += nodes;
+        if !found {
+            return false;
+        }
+
+        let solution: Vec<Move> = self
+            .path
+            .iter()
+            .chain(&moves)
+            .map(|&m| Move::ALL[m])
+            .collect();
+        let length = solution.len();
+        self.best = Some(solution);
+        if length <= self.target_length {
+            self.done = true;
+        }
+        true
+    }
+}
+
+fn phase2(
+    t: &SolverTables,
+    c: Phase2,
+    depth: usize,
+    last: Option<usize>,
+    moves: &mut Vec<usize>,
+    nodes: &mut u64,
+) -> bool {
+    if depth == 0 {
+        return c.is_solved();
+    }
+    for (j, &m) in PHASE2_MOVES.iter().enumerate() {
+        if !allowed(last, m) {
+            continue;
+        }
+        *nodes += 1;
+        let next = c.apply(t, j);
+        if t.phase2_bound(next) > depth - 1 {
+            continue;
+        }
+        moves.push(m);
+        if phase2(t, next, depth - 1, Some(m), moves, nodes) {
+            return true;
+        }
+        moves.pop();
+    }
+    false
+}
+
+/// max_length 手以下の解を 1 つ求める（見つからなければ最短のもの）
+pub fn solve(
+    cube: &Cube,
+    tables: &Arc<SolverTables>,
+    max_length: usize,
+) -> Result<Vec<Move>, SolveError> {
+    let mut session = SolverSession::new(cube, Arc::clone(tables), max_length)?;
+    session
+        .run()
+        .best
+        .ok_or(SolveError::Stuck("two-phase search"))
+}
@@ -0,0 +1,138 @@
+//! stages.rs: CFOP の進行段階（クロス, F2L, OLL, PLL）の判定
+//!
+//! どの面のクロスでも判定できるよう, クロス面が D に来るよう持ち替えてから
+//! D 面基準の判定を行う.
+
+use crate::moves::Face;
+use crate::recognition::is_f2l_solved;
+use crate::symmetry::Rotation;
+use crate::{Cube, Move};
+
+/// 完了している最も先の段階
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Scrambled,
+    Cross,
+    F2l,
+    Oll,
+    Solved,
+}
+
+/// 進行状況
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// 判定に使ったクロス面（クロスがどこにもなければ None）
+    pub cross_face: Option<Face>,
+    /// 入っている F2L スロット（隣接する 2 つの側面で表す）
+    pub f2l_slots: Vec<[Face; 2]>,
+    /// 最終層の向きが揃っている（F2L 完成が前提）
+    pub last_layer_oriented: bool,
+    /// 最終層の位置も揃っている（AUF を除く）
+    pub last_layer_permuted: bool,
+    pub stage: Stage,
+}
+
+// D 面基準での F2L スロット: corner 4..7 と edge 4..7 が対になる
+const D_SLOTS: [[Face; 2]; 4] = [
+    [Face::F, Face::L],
+    [Face::F, Face::R],
+    [Face::B, Face::R],
+    [Face::B, Face::L],
+];
+
+// クロスの候補を調べる順（D を優先）
+const CROSS_ORDER: [Face; 6] = [Face::D, Face::U, Face::F, Face::B, Face::R, Face::L];
+
+/// face を D へ移す持ち替え
+pub fn rotation_to_bottom(face: Face) -> Rotation {
+    Rotation::all()
+        .into_iter()
+        .find(|r| r.apply_face(face) == Face::D)
+        .unwrap()
+}
+
+fn edge_solved(cube: &Cube, i: usize) -> bool {
+    cube.edge_perm[i] == i as u8 && cube.edge_ori[i] == 0
+}
+
+fn corner_solved(cube: &Cube, i: usize) -> bool {
+    cube.corner_perm[i] == i as u8 && cube.corner_ori[i] == 0
+}
+
+fn last_layer_solved_up_to_auf(cube: &Cube) -> bool {
+    let mut c = *cube;
+    (0..4).any(|_| {
+        c.apply_move(Move::U);
+        c.is_solved()
+    })
+}
+
+impl Cube {
+    /// face 面のクロスが揃っているか
+    pub fn is_cross_solved(&self, face: Face) -> bool {
+        let c = rotation_to_bottom(face).apply_cube(self);
+        (8..12).all(|i| edge_solved(&c, i))
+    }
+
+    /// クロスが揃っている面すべて
+    pub fn cross_faces(&self) -> Vec<Face> {
+        CROSS_ORDER
+            .into_iter()
+            .filter(|&f| self.is_cross_solved(f))
+            .collect()
+    }
+
+    /// face 面をクロスとしたときに入っている F2L スロット
+    pub fn solved_f2l_slots(&self, face: Face) -> Vec<[Face; 2]> {
+        let r = rotation_to_bottom(face);
+        let c = r.apply_cube(self);
+        let back = r.inverse();
+        (0..4)
+            .filter(|&i| corner_solved(&c, 4 + i) && edge_solved(&c, 4 + i))
+            .map(|i| D_SLOTS[i].map(|f| back.apply_face(f)))
+            .collect()
+    }
+
+    /// face 面をクロスとしたときの進行状況
+    pub fn progress_on(&self, face: Face) -> Progress {
+        let c = rotation_to_bottom(face).apply_cube(self);
+        let cross = (8..12).all(|i| edge_solved(&c, i));
+        let f2l_slots = if cross {
+            self.solved_f2l_slots(face)
+        } else {
+            Vec::new()
+        };
+        let f2l = cross && is_f2l_solved(&c);
+        let last_layer_oriented = f2l && c.corner_ori[..4] == [0; 4] && c.edge_ori[..4] == [0; 4];
+        let last_layer_permuted = last_layer_oriented && last_layer_solved_up_to_auf(&c);
+
+        let stage = if self.is_solved() {
+            Stage::Solved
+        } else if last_layer_oriented {
+            Stage::Oll
+        } else if f2l {
+            Stage::F2l
+        } else if cross {
+            Stage::Cross
+        } else {
+            Stage::Scrambled
+        };
+
+        Progress {
+            cross_face: cross.then_some(face),
+            f2l_slots,
+            last_layer_oriented,
+            last_layer_permuted,
+            stage,
+        }
+    }
+
+    /// 最も進んでいるクロス面での進行状況
+    pub fn progress(&self) -> Progress {
+        CROSS_ORDER
+            .into_iter()
+            .map(|f| self.progress_on(f))
+            .max_by_key(|p| (p.stage, p.f2l_slots.len(), p.cross_face == Some(Face::D)))
+            .unwrap()
+    }
+}
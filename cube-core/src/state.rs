@@ -1,6 +1,14 @@
 // state.rs: Cube 構造体・基本操作
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// 角の位置の名前（index 順）
+pub const CORNER_NAMES: [&str; 8] = ["UFL", "UFR", "UBR", "UBL", "DFL", "DFR", "DBR", "DBL"];
+
+/// 辺の位置の名前（index 順）
+pub const EDGE_NAMES: [&str; 12] = [
+    "UF", "UR", "UB", "UL", "FL", "FR", "BR", "BL", "DF", "DR", "DB", "DL",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Cube {
     pub corner_perm: [u8; 8], //角パーツの位置
     pub corner_ori: [u8; 8],  //向き
@@ -49,7 +57,7 @@ impl Cube {
 // 長さ len のサイクルは、「len-1 回の交換（transposition）」で表せる。
 // すべてのサイクルについて len - 1 を足し合わせると、置換を生成するために必要な交換回数 inv が得られる（実際には「最小の」交換回数）。
 // 交換回数 inv が偶数なら偶置換、奇数なら奇置換。
-fn parity(perm: &[u8]) -> u8 {
+pub(crate) fn parity(perm: &[u8]) -> u8 {
     let n = perm.len();
     let mut visited = vec![false; n];
     let mut inv = 0;
@@ -0,0 +1,180 @@
+//! supercube.rs: センターの向きも区別するキューブ（スーパーキューブ, 絵柄キューブ）
+//!
+//! `Cube` にセンターの向き（面ごとに外から見て時計回りの 1/4 回転数, 0..4）を加えたもの.
+//! 面を 1/4 回転するとその面のセンターだけが 1 回る.
+//! 1/4 回転ごとに角の置換の偶奇も変わるので, センターの回転数の総和の偶奇は
+//! 角の置換の偶奇と一致する（スーパーキューブのパリティ）.
+//!
+//! 解法ではまず `solve` で `Cube` を揃え, 残ったセンターの回転を
+//! 他を動かさない手順（下の 3 つとその持ち替え）で直す.
+
+use std::sync::Arc;
+
+use crate::geometry::normal;
+use crate::solver::{solve, SolveError, SolverTables};
+use crate::{parse_alg, Cube, Face, LegalityInfo, Move, Rotation};
+
+/// センターの向きを持つ状態
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SuperCube {
+    pub cube: Cube,
+    /// `Face::ALL` の順
+    pub center_ori: [u8; 6],
+}
+
+/// `LegalityInfo` にセンターの条件を加えたもの
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SuperLegalityInfo {
+    pub cube: LegalityInfo,
+    /// センターの回転数の総和 mod 2. 角の置換が奇置換なら 1 でなければならない
+    pub center_twist_sum_mod2: u8,
+    pub is_legal: bool,
+}
+
+impl SuperCube {
+    pub fn identity() -> Self {
+        Self::from_cube(&Cube::identity())
+    }
+
+    /// センターが揃った向きの状態
+    pub fn from_cube(cube: &Cube) -> Self {
+        SuperCube {
+            cube: *cube,
+            center_ori: [0; 6],
+        }
+    }
+
+    pub fn apply_move(&mut self, m: Move) {
+        self.cube.apply_move(m);
+        let f = m.face() as usize;
+        self.center_ori[f] = (self.center_ori[f] + m.quarter_turns()) % 4;
+    }
+
+    pub fn apply_alg(&self, alg: &[Move]) -> Self {
+        let mut c = *self;
+        for &m in alg {
+            c.apply_move(m);
+        }
+        c
+    }
+
+    /// センターの向きも含めて揃っているか
+    pub fn is_solved(&self) -> bool {
+        self.cube.is_solved() && self.center_ori == [0; 6]
+    }
+
+    pub fn legality(&self) -> SuperLegalityInfo {
+        let cube = self.cube.legality();
+        let twist_sum: u32 = self.center_ori.iter().map(|&o| o as u32).sum();
+        let center_twist_sum_mod2 = (twist_sum % 2) as u8;
+        let corner_odd = cube.corner_parity == -1;
+        let is_legal = cube.is_legal
+            && self.center_ori.iter().all(|&o| o < 4)
+            && (center_twist_sum_mod2 == 1) == corner_odd;
+        SuperLegalityInfo {
+            cube,
+            center_twist_sum_mod2,
+            is_legal,
+        }
+    }
+
+    pub fn is_legal(&self) -> bool {
+        self.legality().is_legal
+    }
+}
+
+impl Default for SuperCube {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+// どれも 3x3x3 としては何もしない手順. HALF_TWIST はよく知られた形で, 残りの 2 つは
+// 同じ状態を持ち替えた向きから `solve` で解いた 2 つの解 a, b から a' b を作り,
+// センターが目的の向きになる短いものを選んだ
+//
+// U のセンターだけを 180 度回す
+const HALF_TWIST: &str = "U R L U2 R' L' U R L U2 R' L'";
+// F を時計回り, B を反時計回りに 90 度回す
+const OPPOSITE_TWIST: &str = "U2 F' B' L2 U2 B2 D2 B R2 D2 U2 L2 B U2 F2 D2 R2 D2";
+// B を時計回り, U を反時計回りに 90 度回す
+const ADJACENT_TWIST: &str = "B2 L2 F' L2 B' U2 D2 L2 F L2 B' L2 F2 D' B' R2 L U L2 U2 R2 L F' \
+                              D2 L' F D R' B2 L2 B2 R D2 L' F2 D2 L2 B2 U2";
+
+// (from, to) を (a, b) に移す持ち替えで alg を読み替える
+fn rotated(alg: &str, from: (Face, Face), to: (Face, Face)) -> Vec<Move> {
+    let r = Rotation::all()
+        .into_iter()
+        .find(|r| r.apply_face(from.0) == to.0 && r.apply_face(from.1) == to.1)
+        .unwrap();
+    r.apply_alg(&parse_alg(alg).unwrap())
+}
+
+// 面 f のセンターだけを 180 度回す手順
+fn half_twist(f: Face) -> Vec<Move> {
+    let r = Rotation::all()
+        .into_iter()
+        .find(|r| r.apply_face(Face::U) == f)
+        .unwrap();
+    r.apply_alg(&parse_alg(HALF_TWIST).unwrap())
+}
+
+// a を時計回り, b を反時計回りに 90 度回す手順
+fn pair_twist(a: Face, b: Face) -> Vec<Move> {
+    if normal(a) == normal(b).map(|v| -v) {
+        rotated(OPPOSITE_TWIST, (Face::F, Face::B), (a, b))
+    } else {
+        rotated(ADJACENT_TWIST, (Face::B, Face::U), (a, b))
+    }
+}
+
+/// センターの向きも含めて揃える手順（`Cube` の部分は max_length 手以下を探す）
+pub fn solve_super(
+    cube: &SuperCube,
+    tables: &Arc<SolverTables>,
+    max_length: usize,
+) -> Result<Vec<Move>, SolveError> {
+    if !cube.is_legal() {
+        return Err(SolveError::Illegal);
+    }
+    let mut solution = solve(&cube.cube, tables, max_length)?;
+    let mut state = cube.apply_alg(&solution);
+
+    // 最後の面以外のセンターを順に揃え, 残りを最後の面に寄せる
+    let last = Face::B;
+    for f in Face::ALL.into_iter().filter(|&f| f != last) {
+        let fix = match state.center_ori[f as usize] {
+            0 => continue,
+            1 => pair_twist(last, f),
+            2 => half_twist(f),
+            _ => pair_twist(f, last),
+        };
+        state = state.apply_alg(&fix);
+        solution.extend(fix);
+    }
+    if state.center_ori[last as usize] == 2 {
+        let fix = half_twist(last);
+        state = state.apply_alg(&fix);
+        solution.extend(fix);
+    }
+    debug_assert!(state.is_solved());
+    Ok(cancel(&solution))
+}
+
+// 同じ面の手が続くところをまとめる
+fn cancel(alg: &[Move]) -> Vec<Move> {
+    let mut out: Vec<Move> = Vec::new();
+    for &m in alg {
+        match out.last() {
+            Some(&last) if last.face() == m.face() => {
+                out.pop();
+                let q = (last.quarter_turns() + m.quarter_turns()) % 4;
+                if q != 0 {
+                    out.push(Move::from_face(m.face(), q).unwrap());
+                }
+            }
+            _ => out.push(m),
+        }
+    }
+    out
+}
@@ -0,0 +1,191 @@
+//! symmetry.rs: 持ち替え（キューブ全体の回転）24 通り
+
+use std::fmt;
+
+use crate::facelet::{CORNER_FACELETS, EDGE_FACELETS};
+use crate::moves::Face;
+use crate::{Cube, Move};
+
+/// キューブ全体の回転. 各面がどの面の位置へ移るかで表す
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rotation {
+    faces: [Face; 6],
+    name: String,
+}
+
+impl Rotation {
+    /// 回転なし
+    pub fn identity() -> Self {
+        Rotation {
+            faces: Face::ALL,
+            name: String::new(),
+        }
+    }
+
+    /// x 回転（R と同じ向き）
+    pub fn x() -> Self {
+        use Face::*;
+        Self::from_cycle([F, U, B, D], "x")
+    }
+
+    /// y 回転（U と同じ向き）
+    pub fn y() -> Self {
+        use Face::*;
+        Self::from_cycle([F, L, B, R], "y")
+    }
+
+    /// z 回転（F と同じ向き）
+    pub fn z() -> Self {
+        use Face::*;
+        Self::from_cycle([U, R, D, L], "z")
+    }
+
+    // cycle[i] の面が cycle[i + 1] の位置へ移る
+    fn from_cycle(cycle: [Face; 4], name: &str) -> Self {
+        let mut faces = Face::ALL;
+        for i in 0..4 {
+            faces[cycle[i] as usize] = cycle[(i + 1) % 4];
+        }
+        Rotation {
+            faces,
+            name: name.to_string(),
+        }
+    }
+
+    /// 24 通りすべての回転（単位元が先頭, x/y/z の短い表記順）
+    pub fn all() -> Vec<Rotation> {
+        let gens: Vec<Rotation> = [Self::x(), Self::y(), Self::z()]
+            .iter()
+            .flat_map(|g| {
+                let g2 = g.then(g);
+                let g3 = g2.then(g);
+                [
+                    g.clone(),
+                    g2.renamed(&format!("{}2", g.name)),
+                    g3.renamed(&format!("{}'", g.name)),
+                ]
+            })
+            .collect();
+
+        // 幅優先で生成し, 最初に見つかった表記を名前にする
+        let mut all = vec![Self::identity()];
+        let mut frontier = 0;
+        while frontier < all.len() {
+            let base = all[frontier].clone();
+            for g in &gens {
+                let next = base.then(g);
+                if !all.iter().any(|r| r.faces == next.faces) {
+                    all.push(next);
+                }
+            }
+            frontier += 1;
+        }
+        all
+    }
+
+    /// self の後に other を行う回転
+    pub fn then(&self, other: &Rotation) -> Rotation {
+        let mut faces = Face::ALL;
+        for f in Face::ALL {
+            faces[f as usize] = other.apply_face(self.apply_face(f));
+        }
+        let name = match (self.name.is_empty(), other.name.is_empty()) {
+            (true, _) => other.name.clone(),
+            (_, true) => self.name.clone(),
+            _ => format!("{} {}", self.name, other.name),
+        };
+        Rotation { faces, name }
+    }
+
+    /// 逆回転
+    pub fn inverse(&self) -> Rotation {
+        let mut faces = Face::ALL;
+        for f in Face::ALL {
+            faces[self.apply_face(f) as usize] = f;
+        }
+        let name = self
+            .name
+            .split_whitespace()
+            .rev()
+            .map(|w| match w.strip_suffix('\'') {
+                Some(base) => base.to_string(),
+                None if w.ends_with('2') => w.to_string(),
+                None => format!("{}'", w),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        Rotation { faces, name }
+    }
+
+    fn renamed(&self, name: &str) -> Rotation {
+        Rotation {
+            faces: self.faces,
+            name: name.to_string(),
+        }
+    }
+
+    /// 面 f が回転後に来る位置
+    pub fn apply_face(&self, f: Face) -> Face {
+        self.faces[f as usize]
+    }
+
+    /// 持ち替えた状態で見た手（回転は向きを保つので回転量は不変）
+    pub fn apply_move(&self, m: Move) -> Move {
+        Move::from_face(self.apply_face(m.face()), m.quarter_turns()).unwrap()
+    }
+
+    /// 手順を持ち替えて読み替える
+    pub fn apply_alg(&self, alg: &[Move]) -> Vec<Move> {
+        alg.iter().map(|&m| self.apply_move(m)).collect()
+    }
+
+    /// キューブ全体を持ち替えた後の状態（センター基準で見直したもの）.
+    /// `self.apply_cube(&apply_alg(c, a)) == apply_alg(&self.apply_cube(c), &self.apply_alg(a))`
+    pub fn apply_cube(&self, cube: &Cube) -> Cube {
+        let from = cube.to_facelets();
+        let mut to = from;
+        for (i, slots) in CORNER_FACELETS.iter().enumerate() {
+            let faces = slots.map(|f| self.apply_face(f));
+            // 回転後に i の角が来る位置と, 各ステッカーの並び
+            let (dest, shift) = (0..8)
+                .find_map(|d| {
+                    (0..3)
+                        .find(|&s| (0..3).all(|j| CORNER_FACELETS[d][(j + s) % 3] == faces[j]))
+                        .map(|s| (d, s))
+                })
+                .unwrap();
+            for j in 0..3 {
+                to.corners[dest][(j + shift) % 3] = self.apply_face(from.corners[i][j]);
+            }
+        }
+        for (i, slots) in EDGE_FACELETS.iter().enumerate() {
+            let faces = slots.map(|f| self.apply_face(f));
+            let (dest, shift) = (0..12)
+                .find_map(|d| {
+                    (0..2)
+                        .find(|&s| (0..2).all(|j| EDGE_FACELETS[d][(j + s) % 2] == faces[j]))
+                        .map(|s| (d, s))
+                })
+                .unwrap();
+            for j in 0..2 {
+                to.edges[dest][(j + shift) % 2] = self.apply_face(from.edges[i][j]);
+            }
+        }
+        Cube::from_facelets(&to).unwrap()
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.faces == Face::ALL
+    }
+
+    /// x/y/z による表記（単位元は空文字列）
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
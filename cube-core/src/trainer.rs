@@ -0,0 +1,256 @@
+//! trainer.rs: ケース練習用のランダムな配置と, 練習したケースの記録
+//!
+//! 最終層のケースは `recognition` の名前をそのまま使い, U 層をランダムに
+//! 並べた状態から目的のケースになるものを選ぶ（ケース内で一様）.
+//! F2L は FR スロットのペア（DFR の角と FR の辺）の位置と向きを AUF で
+//! 同一視した 41 ケースで, 番号はこのモジュールでの列挙順.
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use crate::random::{fix_parity, randomize_orientation, scramble_for, Rng};
+use crate::recognition::{cases, recognize};
+use crate::solver::{SolveError, SolverTables};
+use crate::state::parity;
+use crate::{CaseSet, Cube, Move};
+
+/// 練習するケースの種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DrillSet {
+    LastLayer(CaseSet),
+    F2l,
+}
+
+/// 1 回分の練習: ケース名とその状態
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Drill {
+    pub case: String,
+    pub cube: Cube,
+}
+
+impl Drill {
+    /// この状態を作るスクランブル
+    pub fn scramble(&self, tables: &Arc<SolverTables>) -> Result<Vec<Move>, SolveError> {
+        scramble_for(&self.cube, tables)
+    }
+}
+
+/// 練習セッション: ケースをランダムに選び, 出題回数を数える
+#[derive(Clone, Debug)]
+pub struct Trainer {
+    set: DrillSet,
+    rng: Rng,
+    names: Vec<String>,
+    counts: HashMap<String, u32>,
+    drilled: Vec<String>,
+}
+
+impl Trainer {
+    pub fn new(set: DrillSet, seed: u64) -> Self {
+        let names = match set {
+            DrillSet::LastLayer(set) => {
+                cases(set).into_iter().map(|(n, _)| n.to_string()).collect()
+            }
+            DrillSet::F2l => (1..=f2l_cases().len())
+                .map(|i| format!("F2L {}", i))
+                .collect(),
+        };
+        Trainer {
+            set,
+            rng: Rng::new(seed),
+            names,
+            counts: HashMap::new(),
+            drilled: Vec::new(),
+        }
+    }
+
+    pub fn set(&self) -> DrillSet {
+        self.set
+    }
+
+    /// 出題されうるケース名
+    pub fn cases(&self) -> &[String] {
+        &self.names
+    }
+
+    /// 次のケースを選んで出題する
+    pub fn next_drill(&mut self) -> Drill {
+        let case = self.rng.choose(&self.names).clone();
+        let cube = match self.set {
+            DrillSet::LastLayer(set) => random_ll_case(&mut self.rng, set, &case),
+            DrillSet::F2l => {
+                let index = self.names.iter().position(|n| *n == case).unwrap();
+                random_f2l_case(&mut self.rng, index)
+            }
+        };
+        *self.counts.entry(case.clone()).or_insert(0) += 1;
+        self.drilled.push(case.clone());
+        Drill { case, cube }
+    }
+
+    /// ケースの出題回数
+    pub fn count(&self, case: &str) -> u32 {
+        self.counts.get(case).copied().unwrap_or(0)
+    }
+
+    /// 出題したケースと回数（ケースの列挙順）
+    pub fn counts(&self) -> Vec<(&str, u32)> {
+        self.names
+            .iter()
+            .filter_map(|n| self.counts.get(n).map(|&c| (n.as_str(), c)))
+            .collect()
+    }
+
+    /// 出題したケース名（出題順）
+    pub fn drilled(&self) -> &[String] {
+        &self.drilled
+    }
+
+    /// 記録を消す（乱数の状態はそのまま）
+    pub fn reset(&mut self) {
+        self.counts.clear();
+        self.drilled.clear();
+    }
+}
+
+/// F2L が揃い, 最終層が指定したケースになるランダムな状態
+pub fn random_ll_case(rng: &mut Rng, set: CaseSet, case: &str) -> Cube {
+    loop {
+        let cube = random_last_layer(rng, set);
+        if recognize(&cube, set).is_some_and(|m| m.name == case) {
+            return cube;
+        }
+    }
+}
+
+// ケース集合の前提（辺の向き・角の向き）を満たす U 層のランダムな状態
+fn random_last_layer(rng: &mut Rng, set: CaseSet) -> Cube {
+    let mut cube = Cube::identity();
+    rng.shuffle(&mut cube.corner_perm[..4]);
+    rng.shuffle(&mut cube.edge_perm[..4]);
+    fix_parity(&mut cube.edge_perm[..4], parity(&cube.corner_perm[..4]));
+    if set == CaseSet::Oll {
+        randomize_orientation(rng, &mut cube.corner_ori[..4], 3);
+        randomize_orientation(rng, &mut cube.edge_ori[..4], 2);
+    }
+    cube
+}
+
+// FR スロットのペアの位置と向き (角の位置, 角の向き, 辺の位置, 辺の向き)
+type PairKey = (u8, u8, u8, u8);
+
+const CORNER_SLOTS: [u8; 5] = [0, 1, 2, 3, 5];
+const EDGE_SLOTS: [u8; 5] = [0, 1, 2, 3, 5];
+const PAIR_CORNER: u8 = 5;
+const PAIR_EDGE: u8 = 5;
+
+fn pair_key(cube: &Cube) -> PairKey {
+    let c = cube
+        .corner_perm
+        .iter()
+        .position(|&p| p == PAIR_CORNER)
+        .unwrap();
+    let e = cube.edge_perm.iter().position(|&p| p == PAIR_EDGE).unwrap();
+    (c as u8, cube.corner_ori[c], e as u8, cube.edge_ori[e])
+}
+
+// ペアだけを置いた状態（他のパーツは揃ったまま. 合法とは限らない）
+fn place_pair(key: PairKey) -> Cube {
+    let (c, co, e, eo) = key;
+    let mut cube = Cube::identity();
+    cube.corner_perm.swap(c as usize, PAIR_CORNER as usize);
+    cube.corner_ori[c as usize] = co;
+    cube.edge_perm.swap(e as usize, PAIR_EDGE as usize);
+    cube.edge_ori[e as usize] = eo;
+    cube
+}
+
+// AUF で移り合うものの代表（最小のキー）
+fn canonical(key: PairKey) -> PairKey {
+    let mut cube = place_pair(key);
+    let mut best = key;
+    for _ in 0..3 {
+        cube.apply_move(Move::U);
+        best = best.min(pair_key(&cube));
+    }
+    best
+}
+
+/// F2L の 41 ケース（ペアの配置の代表）
+fn f2l_cases() -> &'static [PairKey] {
+    static CASES: OnceLock<Vec<PairKey>> = OnceLock::new();
+    CASES.get_or_init(|| {
+        let mut keys = Vec::new();
+        for &c in &CORNER_SLOTS {
+            for co in 0..3 {
+                for &e in &EDGE_SLOTS {
+                    for eo in 0..2 {
+                        let key = canonical((c, co, e, eo));
+                        if key != (PAIR_CORNER, 0, PAIR_EDGE, 0) && !keys.contains(&key) {
+                            keys.push(key);
+                        }
+                    }
+                }
+            }
+        }
+        keys.sort();
+        keys
+    })
+}
+
+/// 3 つのスロットとクロスが揃い, FR のペアが index 番目の F2L ケースの配置にある
+/// ランダムな状態（残りの U 層はランダム, AUF もランダム）
+pub fn random_f2l_case(rng: &mut Rng, index: usize) -> Cube {
+    let (c, co, e, eo) = f2l_cases()[index];
+    let mut cube = Cube::identity();
+
+    // ペア以外のパーツを, ペアの使っていない位置へランダムに置く
+    let corner_slots: Vec<usize> = CORNER_SLOTS
+        .iter()
+        .filter(|&&s| s != c)
+        .map(|&s| s as usize)
+        .collect();
+    let edge_slots: Vec<usize> = EDGE_SLOTS
+        .iter()
+        .filter(|&&s| s != e)
+        .map(|&s| s as usize)
+        .collect();
+    let mut corners = [0, 1, 2, 3];
+    let mut edges = [0, 1, 2, 3];
+    rng.shuffle(&mut corners);
+    rng.shuffle(&mut edges);
+    let mut corner_ori = [0; 4];
+    let mut edge_ori = [0; 4];
+    randomize_orientation(rng, &mut corner_ori, 3);
+    randomize_orientation(rng, &mut edge_ori, 2);
+
+    cube.corner_perm[c as usize] = PAIR_CORNER;
+    cube.corner_ori[c as usize] = co;
+    cube.edge_perm[e as usize] = PAIR_EDGE;
+    cube.edge_ori[e as usize] = eo;
+    for (k, &s) in corner_slots.iter().enumerate() {
+        cube.corner_perm[s] = corners[k];
+        // 向きの総和はペアの分を打ち消す
+        cube.corner_ori[s] = if k == 0 {
+            (corner_ori[k] + 3 - co) % 3
+        } else {
+            corner_ori[k]
+        };
+    }
+    for (k, &s) in edge_slots.iter().enumerate() {
+        cube.edge_perm[s] = edges[k];
+        cube.edge_ori[s] = if k == 0 {
+            (edge_ori[k] + 2 - eo) % 2
+        } else {
+            edge_ori[k]
+        };
+    }
+    if cube.corner_parity() != cube.edge_parity() {
+        cube.edge_perm.swap(edge_slots[0], edge_slots[1]);
+    }
+
+    for _ in 0..rng.below(4) {
+        cube.apply_move(Move::U);
+    }
+    cube
+}
@@ -0,0 +1,282 @@
+//! void.rs: センターを無視するモード（ヴォイドキューブ, 角と辺だけの練習）
+//!
+//! センターがないとキューブ全体の向きが決まらないので, 持ち替え 24 通りの
+//! どれか 1 つで見て揃っていれば揃ったとみなす. 状態は `Cube` のままで,
+//! ステッカーの色を回転で塗り替えたものを別の向きから見た状態として扱う.
+//!
+//! スライス（M, E, S）は 2 つの面の手と持ち替えの組なので, 角と辺だけを見ると
+//! 辺の 4 サイクルになり, 角と辺の置換の偶奇の一致が崩れる. このモードでは
+//! 偶奇の制約を外し, 解法ではスライスを 1 回使って偶奇を直してから `solve` で揃える.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::facelet::EDGE_FACELETS;
+use crate::notation::{tokens, ParseError};
+use crate::solver::{solve, SolveError, SolverTables};
+use crate::{Cube, Face, LegalityInfo, Move, Rotation};
+
+/// センターの扱い
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CenterMode {
+    /// センターを基準に揃える（通常の 3x3）
+    #[default]
+    Fixed,
+    /// センターを無視する. 持ち替えと, 角と辺の偶奇のずれを許す
+    Void,
+}
+
+/// スライス. M は L, E は D, S は F と同じ向きに回る
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Slice {
+    M,
+    E,
+    S,
+}
+
+impl Slice {
+    pub const ALL: [Slice; 3] = [Slice::M, Slice::E, Slice::S];
+
+    /// 同じ向きに回る面
+    pub fn face(self) -> Face {
+        match self {
+            Slice::M => Face::L,
+            Slice::E => Face::D,
+            Slice::S => Face::F,
+        }
+    }
+
+    // 1/4 回転で cycle[i] の面の位置が cycle[i + 1] の位置へ移る
+    fn cycle(self) -> [Face; 4] {
+        use Face::*;
+        match self {
+            Slice::M => [U, F, D, B],
+            Slice::E => [F, R, B, L],
+            Slice::S => [U, R, D, L],
+        }
+    }
+}
+
+/// スライスの手. turns は時計回りの 1/4 回転数（1..=3）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SliceMove {
+    pub slice: Slice,
+    pub turns: u8,
+}
+
+impl SliceMove {
+    pub const M: SliceMove = SliceMove {
+        slice: Slice::M,
+        turns: 1,
+    };
+
+    pub fn inverse(self) -> SliceMove {
+        SliceMove {
+            turns: 4 - self.turns,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for SliceMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let suffix = ["", "2", "'"][self.turns as usize - 1];
+        write!(f, "{:?}{}", self.slice, suffix)
+    }
+}
+
+impl FromStr for SliceMove {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseError {
+            token: s.to_string(),
+            index: 0,
+            offset: 0,
+        };
+        let mut chars = s.chars();
+        let slice = match chars.next() {
+            Some('M') => Slice::M,
+            Some('E') => Slice::E,
+            Some('S') => Slice::S,
+            _ => return Err(err()),
+        };
+        let turns = match chars.as_str() {
+            "" => 1,
+            "2" => 2,
+            "'" | "p" => 3,
+            _ => return Err(err()),
+        };
+        Ok(SliceMove { slice, turns })
+    }
+}
+
+/// 面の手またはスライス
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VoidMove {
+    Face(Move),
+    Slice(SliceMove),
+}
+
+impl fmt::Display for VoidMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VoidMove::Face(m) => m.fmt(f),
+            VoidMove::Slice(s) => s.fmt(f),
+        }
+    }
+}
+
+/// 面の手とスライスを混ぜた手順をパースする
+pub fn parse_void_alg(s: &str) -> Result<Vec<VoidMove>, ParseError> {
+    tokens(s)
+        .enumerate()
+        .map(|(index, (offset, token))| {
+            token
+                .parse::<Move>()
+                .map(VoidMove::Face)
+                .or_else(|_| token.parse::<SliceMove>().map(VoidMove::Slice))
+                .map_err(|_| ParseError {
+                    token: token.to_string(),
+                    index,
+                    offset,
+                })
+        })
+        .collect()
+}
+
+pub fn format_void_alg(alg: &[VoidMove]) -> String {
+    alg.iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 手順を適用した状態（センターは見ない）
+pub fn apply_void_alg(cube: &Cube, alg: &[VoidMove]) -> Cube {
+    let mut c = *cube;
+    for &m in alg {
+        match m {
+            VoidMove::Face(m) => c.apply_move(m),
+            VoidMove::Slice(s) => c.apply_slice(s),
+        }
+    }
+    c
+}
+
+impl Cube {
+    /// スライスを回す. センターは持たないので辺 4 つだけが動く
+    pub fn apply_slice(&mut self, m: SliceMove) {
+        let cycle = m.slice.cycle();
+        let step = |f: Face| match cycle.iter().position(|&c| c == f) {
+            Some(i) => cycle[(i + m.turns as usize) % 4],
+            None => f,
+        };
+        let axis = m.slice.face() as usize;
+        let from = self.to_facelets();
+        let mut to = from;
+        for (i, slots) in EDGE_FACELETS.iter().enumerate() {
+            if slots.iter().any(|&f| f as usize | 1 == axis | 1) {
+                continue;
+            }
+            let faces = slots.map(step);
+            let (dest, shift) = (0..12)
+                .find_map(|d| {
+                    (0..2)
+                        .find(|&s| (0..2).all(|j| EDGE_FACELETS[d][(j + s) % 2] == faces[j]))
+                        .map(|s| (d, s))
+                })
+                .unwrap();
+            for j in 0..2 {
+                to.edges[dest][(j + shift) % 2] = from.edges[i][j];
+            }
+        }
+        *self = Cube::from_facelets(&to).unwrap();
+    }
+
+    /// ステッカーの色を回転 r で塗り替えた状態. 持ち替えた向きをセンターとみなして見直したもの
+    pub fn recolor(&self, r: &Rotation) -> Cube {
+        let mut f = self.to_facelets();
+        for corner in &mut f.corners {
+            *corner = corner.map(|c| r.apply_face(c));
+        }
+        for edge in &mut f.edges {
+            *edge = edge.map(|c| r.apply_face(c));
+        }
+        Cube::from_facelets(&f).unwrap()
+    }
+
+    /// 揃って見える持ち替え（`recolor` すると揃う回転）. 揃っていなければ None
+    pub fn center_frame(&self) -> Option<Rotation> {
+        Rotation::all()
+            .into_iter()
+            .find(|r| self.recolor(r).is_solved())
+    }
+
+    /// Void では角と辺の置換の偶奇が一致しなくてもよい
+    pub fn legality_in(&self, mode: CenterMode) -> LegalityInfo {
+        let mut info = self.legality();
+        if mode == CenterMode::Void {
+            info.is_legal = info.edge_flip_sum_mod2 == 0 && info.corner_twist_sum_mod3 == 0;
+        }
+        info
+    }
+
+    pub fn is_legal_in(&self, mode: CenterMode) -> bool {
+        self.legality_in(mode).is_legal
+    }
+
+    /// Void ではどれかの持ち替えで揃っていればよい
+    pub fn is_solved_in(&self, mode: CenterMode) -> bool {
+        match mode {
+            CenterMode::Fixed => self.is_solved(),
+            CenterMode::Void => self.center_frame().is_some(),
+        }
+    }
+}
+
+/// mode で揃える手順（面の手の部分は max_length 手以下を探す）.
+/// Void では正しい位置にあるパーツが最も多い持ち替えを目標にし,
+/// 角と辺の偶奇がずれていれば先に M を回す
+pub fn solve_in(
+    cube: &Cube,
+    mode: CenterMode,
+    tables: &Arc<SolverTables>,
+    max_length: usize,
+) -> Result<Vec<VoidMove>, SolveError> {
+    if !cube.is_legal_in(mode) {
+        return Err(SolveError::Illegal);
+    }
+    let mut target = *cube;
+    let mut solution = Vec::new();
+    if mode == CenterMode::Void {
+        // 同点なら偶奇の合う方, さらに同点なら短い表記の持ち替えを選ぶ
+        target = Rotation::all()
+            .iter()
+            .rev()
+            .map(|r| cube.recolor(r))
+            .max_by_key(|c| {
+                let corners =
+                    (0..8).filter(|&i| c.corner_perm[i] == i as u8 && c.corner_ori[i] == 0);
+                let edges = (0..12).filter(|&i| c.edge_perm[i] == i as u8 && c.edge_ori[i] == 0);
+                (
+                    corners.count() + edges.count(),
+                    c.corner_parity() == c.edge_parity(),
+                )
+            })
+            .unwrap();
+        if target.corner_parity() != target.edge_parity() {
+            target.apply_slice(SliceMove::M);
+            solution.push(VoidMove::Slice(SliceMove::M));
+        }
+    }
+    if !target.is_solved() {
+        solution.extend(
+            solve(&target, tables, max_length)?
+                .into_iter()
+                .map(VoidMove::Face),
+        );
+    }
+    Ok(solution)
+}
@@ -1,8 +1,28 @@
 #!/bin/bash
 set -e
 
-echo "🔨 Building WASM module..."
-wasm-pack build --target web
+# ./build.sh        -> pkg/       (ES module for the browser playground)
+# ./build.sh node   -> pkg-node/  (CommonJS package for Node.js, loads synchronously)
+TARGET="${1:-web}"
+
+case "$TARGET" in
+    web)
+        echo "🔨 Building WASM module..."
+        wasm-pack build --target web
+        ;;
+    node)
+        echo "🔨 Building WASM module for Node.js..."
+        wasm-pack build --release --target nodejs --out-dir pkg-node
+        echo "✅ Build complete!"
+        echo ""
+        echo "Try it with: node examples/node-batch.cjs"
+        exit 0
+        ;;
+    *)
+        echo "unknown target: $TARGET (expected web or node)" >&2
+        exit 1
+        ;;
+esac
 
 echo "✅ Build complete!"
 echo ""
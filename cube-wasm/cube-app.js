@@ -7,117 +7,25 @@ let scene, camera, renderer, controls;
 let cubeGroup;
 let wasmModule;
 
-// Corner positions and color definitions
-const CORNER_POSITIONS = [
-    { pos: [-1, 1, 1] },   // 0: UFL
-    { pos: [1, 1, 1] },    // 1: UFR
-    { pos: [1, 1, -1] },   // 2: UBR
-    { pos: [-1, 1, -1] },  // 3: UBL
-    { pos: [-1, -1, 1] },  // 4: DFL
-    { pos: [1, -1, 1] },   // 5: DFR
-    { pos: [1, -1, -1] },  // 6: DBR
-    { pos: [-1, -1, -1] }, // 7: DBL
-];
-
-const CORNER_BASE_COLORS = [
-    [0, 2, 4],  // 0: UFL (黄・青・赤)
-    [0, 4, 3],  // 1: UFR (黄・赤・緑)
-    [0, 3, 5],  // 2: UBR (黄・緑・橙)
-    [0, 5, 2],  // 3: UBL (黄・橙・青)
-    [1, 4, 2],  // 4: DFL (白・赤・青)
-    [1, 3, 4],  // 5: DFR (白・緑・赤)
-    [1, 5, 3],  // 6: DBR (白・橙・緑)
-    [1, 2, 5]   // 7: DBL (白・青・橙)
-];
-
-const CORNER_POSITION_INFO = [
-    { yFace: 'top', side1: 'left', side2: 'front' },
-    { yFace: 'top', side1: 'front', side2: 'right' },
-    { yFace: 'top', side1: 'right', side2: 'back' },
-    { yFace: 'top', side1: 'back', side2: 'left' },
-    { yFace: 'bottom', side1: 'front', side2: 'left' },
-    { yFace: 'bottom', side1: 'right', side2: 'front' },
-    { yFace: 'bottom', side1: 'back', side2: 'right' },
-    { yFace: 'bottom', side1: 'left', side2: 'back' }
-];
-
-const EDGE_POSITIONS = [
-    { pos: [0, 1, 1], faces: [0, 4] },
-    { pos: [1, 1, 0], faces: [0, 3] },
-    { pos: [0, 1, -1], faces: [0, 5] },
-    { pos: [-1, 1, 0], faces: [0, 2] },
-    { pos: [-1, 0, 1], faces: [4, 2] },
-    { pos: [1, 0, 1], faces: [4, 3] },
-    { pos: [1, 0, -1], faces: [5, 3] },
-    { pos: [-1, 0, -1], faces: [5, 2] },
-    { pos: [0, -1, 1], faces: [1, 4] },
-    { pos: [1, -1, 0], faces: [1, 3] },
-    { pos: [0, -1, -1], faces: [1, 5] },
-    { pos: [-1, -1, 0], faces: [1, 2] },
-];
+// Sticker colors by solved face; positions and orientations come from WasmCube.getCubies()
+const FACE_COLORS = {
+    U: 0xffff00, // Yellow
+    D: 0xffffff, // White
+    L: 0x2130E7, // Blue
+    R: 0x00ff00, // Green
+    F: 0xff0000, // Red
+    B: 0xffac51, // Orange
+};
+
+// BoxGeometry material order: +x, -x, +y, -y, +z, -z
+const BOX_FACES = ['R', 'L', 'U', 'D', 'F', 'B'];
 
 function updateProgress(percent, status) {
     document.getElementById('progress-bar').style.width = percent + '%';
     document.getElementById('loading-status').textContent = status;
 }
 
-function getCornerColors(cornerIndex, state) {
-    const perm = state.corner_perm[cornerIndex];
-    const ori = state.corner_ori[cornerIndex];
-    const baseColors = CORNER_BASE_COLORS[perm];
-    
-    let rotatedColors;
-    if (ori === 0) {
-        rotatedColors = [baseColors[0], baseColors[1], baseColors[2]];
-    } else if (ori === 1) {
-        rotatedColors = [baseColors[1], baseColors[2], baseColors[0]];
-    } else {
-        rotatedColors = [baseColors[2], baseColors[0], baseColors[1]];
-    }
-    
-    const posInfo = CORNER_POSITION_INFO[cornerIndex];
-    const result = {
-        top: -1, bottom: -1, left: -1,
-        right: -1, front: -1, back: -1
-    };
-    
-    result[posInfo.yFace] = rotatedColors[0];
-    result[posInfo.side1] = rotatedColors[1];
-    result[posInfo.side2] = rotatedColors[2];
-    
-    return result;
-}
-
-function getEdgeColors(edgeIndex, state) {
-    const perm = state.edge_perm[edgeIndex];
-    const ori = state.edge_ori[edgeIndex];
-    const originalFaces = EDGE_POSITIONS[perm].faces;
-    
-    const [color1, color2] = ori === 1 ? 
-        [originalFaces[1], originalFaces[0]] : 
-        [originalFaces[0], originalFaces[1]];
-    
-    const result = {
-        right: -1, left: -1, top: -1,
-        bottom: -1, front: -1, back: -1
-    };
-    
-    const edgeMapping = [
-        ['top', 'front'], ['top', 'right'], ['top', 'back'], ['top', 'left'],
-        ['front', 'left'], ['front', 'right'], ['back', 'right'], ['back', 'left'],
-        ['bottom', 'front'], ['bottom', 'right'], ['bottom', 'back'], ['bottom', 'left']
-    ];
-    
-    if (edgeIndex < edgeMapping.length) {
-        const [face1, face2] = edgeMapping[edgeIndex];
-        result[face1] = color1;
-        result[face2] = color2;
-    }
-    
-    return result;
-}
-
-function createCubeVisualization(state) {
+function createCubeVisualization(cubies) {
     if (cubeGroup) {
         scene.remove(cubeGroup);
         cubeGroup.traverse((child) => {
@@ -136,88 +44,21 @@ function createCubeVisualization(state) {
     
     const cubeSize = 0.9;
     const gap = 0.05;
-    const colorMap = {
-        0: 0xffff00, // Yellow
-        1: 0xffffff, // White
-        2: 0x2130E7, // Blue
-        3: 0x00ff00, // Green
-        4: 0xff0000, // Red
-        5: 0xffac51, // Orange
-    };
-    
-    // Create corners
-    for (let i = 0; i < 8; i++) {
-        const [x, y, z] = CORNER_POSITIONS[i].pos;
-        const colors = getCornerColors(i, state);
-        
-        const geometry = new THREE.BoxGeometry(cubeSize, cubeSize, cubeSize);
-        const materials = [
-            new THREE.MeshStandardMaterial({ color: colors.right >= 0 ? colorMap[colors.right] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: colors.left >= 0 ? colorMap[colors.left] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: colors.top >= 0 ? colorMap[colors.top] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: colors.bottom >= 0 ? colorMap[colors.bottom] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: colors.front >= 0 ? colorMap[colors.front] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: colors.back >= 0 ? colorMap[colors.back] : 0x000000 }),
-        ];
-        
-        const cubelet = new THREE.Mesh(geometry, materials);
-        cubelet.position.set(x * (cubeSize + gap), y * (cubeSize + gap), z * (cubeSize + gap));
-        
-        const edges = new THREE.EdgesGeometry(geometry);
-        const line = new THREE.LineSegments(edges, new THREE.LineBasicMaterial({ color: 0x000000, linewidth: 2 }));
-        cubelet.add(line);
-        cubeGroup.add(cubelet);
-    }
-    
-    // Create edges
-    for (let i = 0; i < 12; i++) {
-        const [x, y, z] = EDGE_POSITIONS[i].pos;
-        const colors = getEdgeColors(i, state);
-        
-        const geometry = new THREE.BoxGeometry(cubeSize, cubeSize, cubeSize);
-        const materials = [
-            new THREE.MeshStandardMaterial({ color: colors.right >= 0 ? colorMap[colors.right] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: colors.left >= 0 ? colorMap[colors.left] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: colors.top >= 0 ? colorMap[colors.top] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: colors.bottom >= 0 ? colorMap[colors.bottom] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: colors.front >= 0 ? colorMap[colors.front] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: colors.back >= 0 ? colorMap[colors.back] : 0x000000 }),
-        ];
-        
-        const cubelet = new THREE.Mesh(geometry, materials);
-        cubelet.position.set(x * (cubeSize + gap), y * (cubeSize + gap), z * (cubeSize + gap));
-        
-        const edges = new THREE.EdgesGeometry(geometry);
-        const line = new THREE.LineSegments(edges, new THREE.LineBasicMaterial({ color: 0x000000, linewidth: 2 }));
-        cubelet.add(line);
-        cubeGroup.add(cubelet);
-    }
-    
-    // Create centers
-    const centers = [
-        { pos: [1, 0, 0], color: 3 },
-        { pos: [-1, 0, 0], color: 2 },
-        { pos: [0, 1, 0], color: 0 },
-        { pos: [0, -1, 0], color: 1 },
-        { pos: [0, 0, 1], color: 4 },
-        { pos: [0, 0, -1], color: 5 },
-    ];
-    
-    for (const center of centers) {
-        const [x, y, z] = center.pos;
+    for (const cubie of cubies) {
+        const [x, y, z] = cubie.position;
+        const colors = {};
+        for (const sticker of cubie.stickers) {
+            colors[sticker.face] = FACE_COLORS[sticker.color];
+        }
+
         const geometry = new THREE.BoxGeometry(cubeSize, cubeSize, cubeSize);
-        const materials = [
-            new THREE.MeshStandardMaterial({ color: x === 1 ? colorMap[center.color] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: x === -1 ? colorMap[center.color] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: y === 1 ? colorMap[center.color] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: y === -1 ? colorMap[center.color] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: z === 1 ? colorMap[center.color] : 0x000000 }),
-            new THREE.MeshStandardMaterial({ color: z === -1 ? colorMap[center.color] : 0x000000 }),
-        ];
-        
+        const materials = BOX_FACES.map((face) =>
+            new THREE.MeshStandardMaterial({ color: colors[face] ?? 0x000000 })
+        );
+
         const cubelet = new THREE.Mesh(geometry, materials);
         cubelet.position.set(x * (cubeSize + gap), y * (cubeSize + gap), z * (cubeSize + gap));
-        
+
         const edges = new THREE.EdgesGeometry(geometry);
         const line = new THREE.LineSegments(edges, new THREE.LineBasicMaterial({ color: 0x000000, linewidth: 2 }));
         cubelet.add(line);
@@ -229,7 +70,7 @@ function createCubeVisualization(state) {
 
 function updateVisualization() {
     const state = cube.getState();
-    createCubeVisualization(state);
+    createCubeVisualization(cube.getCubies());
     
     const isSolved = cube.isSolved();
     const statusEl = document.getElementById('status');
@@ -308,8 +149,26 @@ function startAnimation() {
     }
 }
 
+// Restore a state (?state=) and algorithm (?alg=) shared by shareLink()
+function loadFromUrl() {
+    const params = new URLSearchParams(window.location.search);
+    const state = params.get('state');
+    const alg = params.get('alg');
+    try {
+        if (state) {
+            cube = wasmModule.WasmCube.fromCode(state);
+        }
+        if (alg) {
+            document.getElementById('algorithm-input').value = wasmModule.decodeAlgorithm(alg);
+        }
+    } catch (e) {
+        console.error('Ignoring invalid shared link:', e.toString());
+    }
+}
+
 function initApp() {
     cube = new wasmModule.WasmCube();
+    loadFromUrl();
     window.cube = cube;
     window.wasmModule = wasmModule;
 
@@ -383,6 +242,23 @@ window.resetCube = function() {
     updateVisualization();
 };
 
+window.shareLink = async function() {
+    try {
+        const url = new URL(window.location.href);
+        url.search = '';
+        url.searchParams.set('state', cube.toCode());
+        const alg = document.getElementById('algorithm-input').value;
+        if (alg) {
+            url.searchParams.set('alg', wasmModule.encodeAlgorithm(alg));
+        }
+        window.history.replaceState(null, '', url);
+        await navigator.clipboard.writeText(url.toString());
+    } catch (e) {
+        console.error('Error creating share link:', e);
+        alert('Error: ' + e);
+    }
+};
+
 window.applyCommutator = function() {
     const a = document.getElementById('alg-a').value;
     const b = document.getElementById('alg-b').value;
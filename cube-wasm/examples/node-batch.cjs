@@ -0,0 +1,17 @@
+// Headless use from Node.js: build with `./build.sh node`, then run
+//
+//   node examples/node-batch.cjs
+
+const { WasmCube, applyMany, isSolvedMany } = require('../pkg-node/cube_wasm.js');
+
+const solved = WasmCube.identity().getState();
+const states = Array.from({ length: 1000 }, () => solved);
+
+// One call for the whole batch
+const sexy = "R U R' U'";
+let current = states;
+for (let i = 0; i < 6; i++) {
+    current = applyMany(current, sexy);
+    const solvedCount = isSolvedMany(current).filter(Boolean).length;
+    console.log(`after ${i + 1} x (${sexy}): ${solvedCount} of ${current.length} solved`);
+}
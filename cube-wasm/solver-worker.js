@@ -0,0 +1,41 @@
+// Web Worker running the two-phase solver without blocking the page.
+//
+// Generate the tables once with `cargo run -p cube-cli -- tables solver-tables.bin`.
+//
+//   const worker = new Worker('./solver-worker.js', { type: 'module' });
+//   worker.postMessage({ scramble: "R U R' U'", targetLength: 20 });
+//   worker.onmessage = (e) => console.log(e.data);
+
+import init, { WasmCube, SolverTables, SolverSession } from './pkg/cube_wasm.js';
+
+const ready = (async () => {
+    await init();
+    const buffer = await (await fetch('./solver-tables.bin')).arrayBuffer();
+    return SolverTables.fromBytes(buffer);
+})();
+
+let current = 0;
+
+self.onmessage = async (event) => {
+    const { scramble, targetLength } = event.data;
+    const tables = await ready;
+    const id = ++current;
+
+    const cube = new WasmCube();
+    cube.applyAlgorithm(scramble);
+    const session = new SolverSession(cube, tables, targetLength);
+
+    // Search in short slices so a newer request can take over
+    while (id === current) {
+        const progress = session.step(50);
+        if (progress.improved || progress.done) {
+            self.postMessage(progress);
+        }
+        if (progress.done) {
+            break;
+        }
+        await new Promise((resolve) => setTimeout(resolve, 0));
+    }
+    session.free();
+    cube.free();
+};
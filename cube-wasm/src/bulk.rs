@@ -0,0 +1,185 @@
+// Bulk operations over packed states in a Uint8Array.
+//
+// Each state takes STATE_BYTES bytes, in CubeState field order:
+// corner_perm (8), corner_ori (8), edge_perm (12), edge_ori (12).
+
+use cube_core::{apply_alg, cycle_type, Cube, Move, Rng};
+use wasm_bindgen::prelude::*;
+
+use crate::error::cube_error;
+use crate::parse_algorithm;
+use crate::types::{cube_from_parts, from_js, to_js, CubeState, JsCubeStates};
+
+/// Bytes per packed state
+pub const STATE_BYTES: usize = 40;
+
+/// Bytes per state returned by `cycleTypesPacked`
+pub const CYCLE_TYPE_BYTES: usize = 20;
+
+// Legality flags returned by legalityPacked
+const LEGAL: u8 = 1;
+const PARITY_MISMATCH: u8 = 2;
+const CORNER_TWISTED: u8 = 4;
+const EDGE_FLIPPED: u8 = 8;
+
+fn unpack(bytes: &[u8], index: usize) -> Result<Cube, JsValue> {
+    read(bytes, index).map_err(|e| cube_error("state", e))
+}
+
+fn read(bytes: &[u8], index: usize) -> Result<Cube, String> {
+    cube_from_parts(&bytes[..8], &bytes[8..16], &bytes[16..28], &bytes[28..])
+        .map_err(|e| format!("state {}: {}", index, e))
+}
+
+fn pack(cube: &Cube, out: &mut [u8]) {
+    out[..8].copy_from_slice(&cube.corner_perm);
+    out[8..16].copy_from_slice(&cube.corner_ori);
+    out[16..28].copy_from_slice(&cube.edge_perm);
+    out[28..].copy_from_slice(&cube.edge_ori);
+}
+
+// Split a buffer into packed states
+fn chunks(states: &[u8]) -> Result<&[[u8; STATE_BYTES]], JsValue> {
+    check_length(states.len())?;
+    Ok(states.as_chunks().0)
+}
+
+fn check_length(len: usize) -> Result<(), JsValue> {
+    if !len.is_multiple_of(STATE_BYTES) {
+        return Err(cube_error(
+            "state",
+            format!("buffer length {} is not a multiple of {}", len, STATE_BYTES),
+        ));
+    }
+    Ok(())
+}
+
+/// Pack states into one buffer
+#[wasm_bindgen(js_name = packStates)]
+pub fn pack_states(states: JsCubeStates) -> Result<Vec<u8>, JsValue> {
+    let states: Vec<CubeState> = from_js(states.into())?;
+    let mut out = vec![0; states.len() * STATE_BYTES];
+    for (state, chunk) in states.iter().zip(out.as_chunks_mut::<STATE_BYTES>().0) {
+        pack(&state.to_cube()?, chunk);
+    }
+    Ok(out)
+}
+
+#[wasm_bindgen(js_name = unpackStates)]
+pub fn unpack_states(states: &[u8]) -> Result<JsCubeStates, JsValue> {
+    let cubes = chunks(states)?
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| unpack(chunk, i).map(|cube| CubeState::from_cube(&cube)))
+        .collect::<Result<Vec<_>, JsValue>>()?;
+    to_js(&cubes)
+}
+
+/// `count` uniformly random legal states, packed
+#[wasm_bindgen(js_name = randomStatesPacked)]
+pub fn random_states_packed(count: usize, seed: f64) -> Vec<u8> {
+    let mut rng = Rng::new(seed as u64);
+    let mut out = vec![0; count * STATE_BYTES];
+    for chunk in out.as_chunks_mut::<STATE_BYTES>().0 {
+        pack(&Cube::random(&mut rng), chunk);
+    }
+    out
+}
+
+/// Apply an algorithm to every packed state, in place
+#[wasm_bindgen(js_name = applyAlgorithmPacked)]
+pub fn apply_algorithm_packed(states: &mut [u8], alg_str: &str) -> Result<(), JsValue> {
+    let moves = parse_algorithm(alg_str)?;
+    check_length(states.len())?;
+    apply_packed(states.as_chunks_mut().0, &moves).map_err(|e| cube_error("state", e))
+}
+
+// Unpack every state before writing, so an invalid state leaves the buffer untouched
+fn apply_packed(states: &mut [[u8; STATE_BYTES]], moves: &[Move]) -> Result<(), String> {
+    let cubes = states
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| read(chunk, i))
+        .collect::<Result<Vec<_>, _>>()?;
+    for (cube, chunk) in cubes.iter().zip(states) {
+        pack(&apply_alg(cube, moves), chunk);
+    }
+    Ok(())
+}
+
+/// One byte of flags per state: 1 = legal, 2 = corner/edge parity mismatch,
+/// 4 = corners twisted, 8 = edges flipped
+#[wasm_bindgen(js_name = legalityPacked)]
+pub fn legality_packed(states: &[u8]) -> Result<Vec<u8>, JsValue> {
+    chunks(states)?
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let info = unpack(chunk, i)?.legality();
+            let mut flags = 0;
+            if info.is_legal {
+                flags |= LEGAL;
+            }
+            if info.corner_parity != info.edge_parity {
+                flags |= PARITY_MISMATCH;
+            }
+            if info.corner_twist_sum_mod3 != 0 {
+                flags |= CORNER_TWISTED;
+            }
+            if info.edge_flip_sum_mod2 != 0 {
+                flags |= EDGE_FLIPPED;
+            }
+            Ok(flags)
+        })
+        .collect()
+}
+
+/// CYCLE_TYPE_BYTES bytes per state: byte k (k < 8) counts corner cycles of
+/// length k + 1, byte 8 + k counts edge cycles of length k + 1
+#[wasm_bindgen(js_name = cycleTypesPacked)]
+pub fn cycle_types_packed(states: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let mut out = Vec::with_capacity(states.len() / STATE_BYTES * CYCLE_TYPE_BYTES);
+    for (i, chunk) in chunks(states)?.iter().enumerate() {
+        let cube = unpack(chunk, i)?;
+        out.extend(cycle_type(&cube.corner_perm));
+        out.extend(cycle_type(&cube.edge_perm));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cube_core::parse_alg;
+
+    #[test]
+    fn pack_round_trip() {
+        let cube = apply_alg(&Cube::identity(), &parse_alg("R U F' D2 L").unwrap());
+        let mut bytes = [0; STATE_BYTES];
+        pack(&cube, &mut bytes);
+        assert_eq!(bytes[..8], cube.corner_perm);
+        assert_eq!(bytes[8..16], cube.corner_ori);
+        assert_eq!(bytes[16..28], cube.edge_perm);
+        assert_eq!(bytes[28..], cube.edge_ori);
+        assert_eq!(read(&bytes, 0), Ok(cube));
+    }
+
+    #[test]
+    fn apply_packed_is_all_or_nothing() {
+        let mut states = [[0; STATE_BYTES]; 3];
+        for chunk in &mut states {
+            pack(&Cube::identity(), chunk);
+        }
+        states[1][0] = 9;
+        let before = states;
+        let moves = parse_alg("R U").unwrap();
+        let err = apply_packed(&mut states, &moves).unwrap_err();
+        assert!(err.starts_with("state 1:"));
+        assert_eq!(states, before);
+
+        pack(&Cube::identity(), &mut states[1]);
+        apply_packed(&mut states, &moves).unwrap();
+        let expected = apply_alg(&Cube::identity(), &moves);
+        assert!(states.iter().all(|chunk| read(chunk, 0) == Ok(expected)));
+    }
+}
@@ -0,0 +1,97 @@
+// Typed error objects thrown to JavaScript
+
+use std::fmt::Display;
+
+use wasm_bindgen::prelude::*;
+
+/// Thrown when a move or algorithm string cannot be parsed
+#[wasm_bindgen(js_name = ParseError)]
+pub struct WasmParseError {
+    token: String,
+    index: usize,
+    offset: usize,
+}
+
+#[wasm_bindgen(js_class = ParseError)]
+impl WasmParseError {
+    /// The offending token
+    #[wasm_bindgen(getter)]
+    pub fn token(&self) -> String {
+        self.token.clone()
+    }
+
+    /// Position of the token among the whitespace-separated tokens (0-based)
+    #[wasm_bindgen(getter)]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Offset of the token in the input string
+    #[wasm_bindgen(getter)]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.to_core().to_string()
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_string_js(&self) -> String {
+        format!("ParseError: {}", self.message())
+    }
+}
+
+impl WasmParseError {
+    fn to_core(&self) -> cube_core::ParseError {
+        cube_core::ParseError {
+            token: self.token.clone(),
+            index: self.index,
+            offset: self.offset,
+        }
+    }
+}
+
+/// Thrown for any other failure; `kind` is one of `"solve"`, `"tables"`, `"notation"`, `"history"`, `"trainer"`, `"codec"`,
+/// `"state"` or `"serialize"`
+#[wasm_bindgen(js_name = CubeError)]
+pub struct WasmError {
+    kind: String,
+    message: String,
+}
+
+#[wasm_bindgen(js_class = CubeError)]
+impl WasmError {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        self.kind.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_string_js(&self) -> String {
+        format!("CubeError ({}): {}", self.kind, self.message)
+    }
+}
+
+pub(crate) fn parse_error(e: cube_core::ParseError) -> JsValue {
+    WasmParseError {
+        token: e.token,
+        index: e.index,
+        offset: e.offset,
+    }
+    .into()
+}
+
+pub(crate) fn cube_error(kind: &str, e: impl Display) -> JsValue {
+    WasmError {
+        kind: kind.to_string(),
+        message: e.to_string(),
+    }
+    .into()
+}
@@ -0,0 +1,50 @@
+// Batch functions for headless use (Node services, tests).
+// They take and return plain CubeState objects, so a whole batch crosses the
+// JS/WASM boundary once instead of once per cube.
+
+use cube_core::{apply_alg, Cube};
+use wasm_bindgen::prelude::*;
+
+use crate::parse_algorithm;
+use crate::types::{from_js, to_js, CubeState, JsBools, JsCubeState, JsCubeStates};
+
+fn states_to_cubes(states: JsCubeStates) -> Result<Vec<Cube>, JsValue> {
+    let states: Vec<CubeState> = from_js(states.into())?;
+    states.iter().map(CubeState::to_cube).collect()
+}
+
+fn cubes_to_states(cubes: &[Cube]) -> Result<JsCubeStates, JsValue> {
+    let states: Vec<CubeState> = cubes.iter().map(CubeState::from_cube).collect();
+    to_js(&states)
+}
+
+/// Apply the same algorithm to every state
+#[wasm_bindgen(js_name = applyMany)]
+pub fn apply_many(states: JsCubeStates, alg_str: &str) -> Result<JsCubeStates, JsValue> {
+    let moves = parse_algorithm(alg_str)?;
+    let cubes: Vec<Cube> = states_to_cubes(states)?
+        .iter()
+        .map(|cube| apply_alg(cube, &moves))
+        .collect();
+    cubes_to_states(&cubes)
+}
+
+/// Apply each algorithm to the same starting state
+#[wasm_bindgen(js_name = applyEach)]
+pub fn apply_each(state: JsCubeState, algs: Vec<String>) -> Result<JsCubeStates, JsValue> {
+    let start = from_js::<CubeState>(state.into())?.to_cube()?;
+    let cubes = algs
+        .iter()
+        .map(|alg| Ok(apply_alg(&start, &parse_algorithm(alg)?)))
+        .collect::<Result<Vec<Cube>, JsValue>>()?;
+    cubes_to_states(&cubes)
+}
+
+#[wasm_bindgen(js_name = isSolvedMany)]
+pub fn is_solved_many(states: JsCubeStates) -> Result<JsBools, JsValue> {
+    let solved: Vec<bool> = states_to_cubes(states)?
+        .iter()
+        .map(Cube::is_solved)
+        .collect();
+    to_js(&solved)
+}
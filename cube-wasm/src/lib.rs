@@ -1,23 +1,39 @@
-use cube_core::{Cube, Move};
-use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use cube_core::{
+    format_alg, parse_alg, CaseSet, Cube, DrillSet, History, Move, NotationStyle, Rng, SolveError,
+    Trainer,
+};
 use wasm_bindgen::prelude::*;
 
-// Console logging for debugging
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-}
+mod bulk;
+mod error;
+mod headless;
+mod types;
 
-macro_rules! console_log {
-    ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
-}
+pub use bulk::{
+    apply_algorithm_packed, cycle_types_packed, legality_packed, pack_states, random_states_packed,
+    unpack_states, CYCLE_TYPE_BYTES, STATE_BYTES,
+};
+pub use error::{WasmError, WasmParseError};
+pub use headless::{apply_each, apply_many, is_solved_many};
+pub use types::{
+    JsBools, JsCubeState, JsCubeStates, JsCubies, JsCycles, JsHistoryData, JsLegalityData,
+    JsMoveInfo, JsSolveProgress, JsStrings, JsTrainerCounts, JsTrainerDrill,
+};
+
+use error::{cube_error, parse_error};
+use types::{
+    to_js, CubeState, CubieData, HistoryData, LegalityData, SolveProgress, TrainerCount,
+    TrainerDrill,
+};
 
 // JavaScript-friendly cube representation
 #[wasm_bindgen]
 #[derive(Clone)]
 pub struct WasmCube {
-    inner: Cube,
+    history: History,
+    style: NotationStyle,
 }
 
 impl Default for WasmCube {
@@ -30,163 +46,460 @@ impl Default for WasmCube {
 impl WasmCube {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
-        console_log!("Creating new cube");
-        Self {
-            inner: Cube::identity(),
-        }
+        Self::from_cube(Cube::identity())
     }
 
     #[wasm_bindgen(js_name = identity)]
     pub fn identity() -> Self {
-        Self {
-            inner: Cube::identity(),
-        }
+        Self::from_cube(Cube::identity())
+    }
+
+    /// Choose how counter-clockwise turns are written in the strings this cube returns:
+    /// `"prime"` for `R'` (default) or `"p"` for `Rp`. Both styles parse back to the same moves.
+    #[wasm_bindgen(js_name = setNotationStyle)]
+    pub fn set_notation_style(&mut self, style: &str) -> Result<(), JsValue> {
+        self.style = parse_style(Some(style))?;
+        Ok(())
+    }
+
+    /// A uniformly random legal state; the same seed always gives the same state
+    #[wasm_bindgen(js_name = randomState)]
+    pub fn random_state(seed: f64) -> Self {
+        Self::from_cube(Cube::random(&mut Rng::new(seed as u64)))
+    }
+
+    /// A random state of a random case from `set` (`"oll"`, `"pll"` or `"f2l"`)
+    #[wasm_bindgen(js_name = randomCase)]
+    pub fn random_case(set: &str, seed: f64) -> Result<WasmCube, JsValue> {
+        let drill = Trainer::new(parse_drill_set(set)?, seed as u64).next_drill();
+        Ok(Self::from_cube(drill.cube))
+    }
+
+    /// Rebuild a cube from a code made by `toCode`; corrupted or unreachable states throw
+    #[wasm_bindgen(js_name = fromCode)]
+    pub fn from_code(code: &str) -> Result<WasmCube, JsValue> {
+        let cube = cube_core::decode_state(code).map_err(|e| cube_error("codec", e))?;
+        Ok(Self::from_cube(cube))
+    }
+
+    /// Compact URL-safe code for the current state (e.g. for a `?state=` parameter)
+    #[wasm_bindgen(js_name = toCode)]
+    pub fn to_code(&self) -> String {
+        cube_core::encode_state(self.cube())
     }
 
     #[wasm_bindgen(js_name = isSolved)]
     pub fn is_solved(&self) -> bool {
-        self.inner.is_solved()
+        self.cube().is_solved()
     }
 
     #[wasm_bindgen(js_name = applyMove)]
     pub fn apply_move(&mut self, move_str: &str) -> Result<(), JsValue> {
-        let m = parse_move(move_str)
-            .ok_or_else(|| JsValue::from_str(&format!("Invalid move: {}", move_str)))?;
-        self.inner.apply_move(m);
+        let m = parse_move(move_str)?;
+        self.history.apply(m);
         Ok(())
     }
 
     #[wasm_bindgen(js_name = applyAlgorithm)]
     pub fn apply_algorithm(&mut self, alg_str: &str) -> Result<(), JsValue> {
         let moves = parse_algorithm(alg_str)?;
-        for m in moves {
-            self.inner.apply_move(m);
-        }
+        self.history.apply_alg(&moves);
         Ok(())
     }
 
+    /// Step back one move, returning the undone move (animate its inverse)
+    #[wasm_bindgen(js_name = undo)]
+    pub fn undo(&mut self) -> Option<String> {
+        self.history.undo().map(|m| format_alg(&[m], self.style))
+    }
+
+    /// Step forward along the timeline, returning the replayed move
+    #[wasm_bindgen(js_name = redo)]
+    pub fn redo(&mut self) -> Option<String> {
+        self.history.redo().map(|m| format_alg(&[m], self.style))
+    }
+
+    /// Jump to the state after `index` moves of the timeline (0 is the start)
+    #[wasm_bindgen(js_name = seek)]
+    pub fn seek(&mut self, index: usize) -> Result<(), JsValue> {
+        self.history
+            .seek(index)
+            .map_err(|e| cube_error("history", e))
+    }
+
+    #[wasm_bindgen(js_name = canUndo)]
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    #[wasm_bindgen(js_name = canRedo)]
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// The timeline, the current position in it and the branches leaving the current position
+    #[wasm_bindgen(js_name = history)]
+    pub fn history(&self) -> Result<JsHistoryData, JsValue> {
+        to_js(&HistoryData::from_history(&self.history, self.style))
+    }
+
+    /// Make the branch starting with `move_str` the one `redo` follows
+    #[wasm_bindgen(js_name = switchBranch)]
+    pub fn switch_branch(&mut self, move_str: &str) -> Result<bool, JsValue> {
+        let m = parse_move(move_str)?;
+        Ok(self.history.switch_branch(m))
+    }
+
     #[wasm_bindgen(js_name = getState)]
-    pub fn get_state(&self) -> JsValue {
-        let state = CubeState::from_cube(&self.inner);
-        serde_wasm_bindgen::to_value(&state).unwrap()
+    pub fn get_state(&self) -> Result<JsCubeState, JsValue> {
+        to_js(&CubeState::from_cube(self.cube()))
     }
 
     #[wasm_bindgen(js_name = getLegality)]
-    pub fn get_legality(&self) -> JsValue {
-        let info = self.inner.legality();
-        let legality = LegalityData {
-            corner_parity: info.corner_parity,
-            edge_parity: info.edge_parity,
-            edge_flip_sum_mod2: info.edge_flip_sum_mod2,
-            corner_twist_sum_mod3: info.corner_twist_sum_mod3,
-            is_legal: info.is_legal,
-        };
-        serde_wasm_bindgen::to_value(&legality).unwrap()
+    pub fn get_legality(&self) -> Result<JsLegalityData, JsValue> {
+        to_js(&LegalityData::from_info(&self.cube().legality()))
     }
 
     #[wasm_bindgen(js_name = getCornerCycles)]
-    pub fn get_corner_cycles(&self) -> JsValue {
-        let cycles = cube_core::corner_cycles(&self.inner);
-        serde_wasm_bindgen::to_value(&cycles).unwrap()
+    pub fn get_corner_cycles(&self) -> Result<JsCycles, JsValue> {
+        to_js(&cube_core::corner_cycles(self.cube()))
     }
 
     #[wasm_bindgen(js_name = getEdgeCycles)]
-    pub fn get_edge_cycles(&self) -> JsValue {
-        let cycles = cube_core::edge_cycles(&self.inner);
-        serde_wasm_bindgen::to_value(&cycles).unwrap()
+    pub fn get_edge_cycles(&self) -> Result<JsCycles, JsValue> {
+        to_js(&cube_core::edge_cycles(self.cube()))
+    }
+
+    /// Position, rotation and sticker colors of the 26 visible cubies, for 3D renderers
+    #[wasm_bindgen(js_name = getCubies)]
+    pub fn get_cubies(&self) -> Result<JsCubies, JsValue> {
+        let cubies: Vec<CubieData> = self
+            .cube()
+            .cubies()
+            .iter()
+            .map(CubieData::from_cubie)
+            .collect();
+        to_js(&cubies)
+    }
+
+    /// Solve with the two-phase solver, returning the first solution of at most `max_length` moves
+    #[wasm_bindgen(js_name = solve)]
+    pub fn solve(
+        &self,
+        tables: &WasmSolverTables,
+        max_length: Option<usize>,
+    ) -> Result<String, JsValue> {
+        let moves = cube_core::solve(
+            self.cube(),
+            &tables.inner,
+            max_length.unwrap_or(DEFAULT_MAX_LENGTH),
+        )
+        .map_err(solve_error)?;
+        Ok(format_alg(&moves, self.style))
+    }
+}
+
+impl WasmCube {
+    fn cube(&self) -> &Cube {
+        self.history.cube()
+    }
+
+    fn from_cube(cube: Cube) -> Self {
+        Self {
+            history: History::new(cube),
+            style: NotationStyle::default(),
+        }
+    }
+}
+
+const DEFAULT_MAX_LENGTH: usize = 22;
+
+// Nodes searched between clock checks in SolverSession::step
+const NODES_PER_CHUNK: u64 = 2000;
+
+// Pruning tables for the two-phase solver
+#[wasm_bindgen(js_name = SolverTables)]
+pub struct WasmSolverTables {
+    inner: Arc<cube_core::SolverTables>,
+}
+
+#[wasm_bindgen(js_class = SolverTables)]
+impl WasmSolverTables {
+    /// Build the tables in place (takes a few seconds; prefer `fromBytes`)
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(cube_core::SolverTables::build()),
+        }
+    }
+
+    /// Load tables previously written by `toBytes` (e.g. fetched as an ArrayBuffer)
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(buffer: &js_sys::ArrayBuffer) -> Result<WasmSolverTables, JsValue> {
+        let bytes = js_sys::Uint8Array::new(buffer).to_vec();
+        let tables = cube_core::SolverTables::from_bytes(&bytes).map_err(solve_error)?;
+        Ok(Self {
+            inner: Arc::new(tables),
+        })
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.inner.to_bytes()
+    }
+}
+
+impl Default for WasmSolverTables {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Incremental solver for use from a Web Worker
+#[wasm_bindgen(js_name = SolverSession)]
+pub struct WasmSolverSession {
+    inner: cube_core::SolverSession,
+    style: NotationStyle,
+}
+
+#[wasm_bindgen(js_class = SolverSession)]
+impl WasmSolverSession {
+    /// Stop as soon as a solution of at most `target_length` moves is found.
+    /// Solutions are written in the cube's notation style
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        cube: &WasmCube,
+        tables: &WasmSolverTables,
+        target_length: Option<usize>,
+    ) -> Result<WasmSolverSession, JsValue> {
+        let inner = cube_core::SolverSession::new(
+            cube.cube(),
+            Arc::clone(&tables.inner),
+            target_length.unwrap_or(0),
+        )
+        .map_err(solve_error)?;
+        Ok(Self {
+            inner,
+            style: cube.style,
+        })
+    }
+
+    /// Search for about `budget_ms` milliseconds, returning early when the solution improves
+    #[wasm_bindgen(js_name = step)]
+    pub fn step(&mut self, budget_ms: f64) -> Result<JsSolveProgress, JsValue> {
+        let start = js_sys::Date::now();
+        let mut improved = false;
+        let mut progress = self.inner.step(NODES_PER_CHUNK);
+        improved |= progress.improved;
+        while !progress.done && !improved && js_sys::Date::now() - start < budget_ms {
+            progress = self.inner.step(NODES_PER_CHUNK);
+            improved |= progress.improved;
+        }
+        progress.improved = improved;
+        to_js(&SolveProgress::from_progress(&progress, self.style))
+    }
+
+    #[wasm_bindgen(js_name = bestSolution)]
+    pub fn best_solution(&self) -> Option<String> {
+        self.inner.best().map(|m| format_alg(m, self.style))
+    }
+
+    #[wasm_bindgen(js_name = isDone)]
+    pub fn is_done(&self) -> bool {
+        self.inner.is_done()
+    }
+}
+
+// Case drilling with per-case counts
+#[wasm_bindgen(js_name = TrainerSession)]
+pub struct WasmTrainerSession {
+    inner: Trainer,
+    tables: Option<Arc<cube_core::SolverTables>>,
+    style: NotationStyle,
+}
+
+#[wasm_bindgen(js_class = TrainerSession)]
+impl WasmTrainerSession {
+    /// Drill cases from `set` (`"oll"`, `"pll"` or `"f2l"`)
+    #[wasm_bindgen(constructor)]
+    pub fn new(set: &str, seed: f64) -> Result<WasmTrainerSession, JsValue> {
+        Ok(Self {
+            inner: Trainer::new(parse_drill_set(set)?, seed as u64),
+            tables: None,
+            style: NotationStyle::default(),
+        })
+    }
+
+    /// Notation style of the scrambles, as for `WasmCube.setNotationStyle`
+    #[wasm_bindgen(js_name = setNotationStyle)]
+    pub fn set_notation_style(&mut self, style: &str) -> Result<(), JsValue> {
+        self.style = parse_style(Some(style))?;
+        Ok(())
+    }
+
+    /// From now on, give each drill a random-state scramble for its case
+    #[wasm_bindgen(js_name = useTables)]
+    pub fn use_tables(&mut self, tables: &WasmSolverTables) {
+        self.tables = Some(Arc::clone(&tables.inner));
+    }
+
+    /// Pick the next case and count it as drilled
+    #[wasm_bindgen(js_name = next)]
+    pub fn next_drill(&mut self) -> Result<JsTrainerDrill, JsValue> {
+        let drill = self.inner.next_drill();
+        let scramble = match &self.tables {
+            Some(tables) => Some(format_alg(
+                &drill.scramble(tables).map_err(solve_error)?,
+                self.style,
+            )),
+            None => None,
+        };
+        to_js(&TrainerDrill {
+            case: drill.case,
+            scramble,
+            state: CubeState::from_cube(&drill.cube),
+        })
+    }
+
+    /// Every case the session can pick
+    #[wasm_bindgen(js_name = cases)]
+    pub fn cases(&self) -> Result<JsStrings, JsValue> {
+        to_js(&self.inner.cases())
+    }
+
+    /// How many times each drilled case came up
+    #[wasm_bindgen(js_name = counts)]
+    pub fn counts(&self) -> Result<JsTrainerCounts, JsValue> {
+        let counts: Vec<TrainerCount> = self
+            .inner
+            .counts()
+            .into_iter()
+            .map(|(case, count)| TrainerCount { case, count })
+            .collect();
+        to_js(&counts)
+    }
+
+    #[wasm_bindgen(js_name = count)]
+    pub fn count(&self, case: &str) -> u32 {
+        self.inner.count(case)
+    }
+
+    /// Drilled cases in the order they came up
+    #[wasm_bindgen(js_name = drilled)]
+    pub fn drilled(&self) -> Result<JsStrings, JsValue> {
+        to_js(&self.inner.drilled())
+    }
+
+    /// Forget the counts (the random sequence carries on)
+    #[wasm_bindgen(js_name = reset)]
+    pub fn reset(&mut self) {
+        self.inner.reset();
     }
 }
 
 // Helper functions
+/// A random-state scramble: a short algorithm reaching a uniformly random legal state
+#[wasm_bindgen(js_name = randomScramble)]
+pub fn random_scramble(
+    tables: &WasmSolverTables,
+    seed: f64,
+    style: Option<String>,
+) -> Result<String, JsValue> {
+    let moves = cube_core::random_state_scramble(&mut Rng::new(seed as u64), &tables.inner)
+        .map_err(solve_error)?;
+    format_algorithm(&moves, style)
+}
+
+/// Axis, layer, angle and affected slots of a move, for animating layer turns
+#[wasm_bindgen(js_name = moveInfo)]
+pub fn move_info(move_str: &str) -> Result<JsMoveInfo, JsValue> {
+    to_js(&parse_move(move_str)?.info())
+}
+
+/// Compact URL-safe code for an algorithm
+#[wasm_bindgen(js_name = encodeAlgorithm)]
+pub fn encode_algorithm(alg_str: &str) -> Result<String, JsValue> {
+    Ok(cube_core::encode_alg(&parse_algorithm(alg_str)?))
+}
+
+/// The algorithm behind a code made by `encodeAlgorithm`
+#[wasm_bindgen(js_name = decodeAlgorithm)]
+pub fn decode_algorithm(code: &str, style: Option<String>) -> Result<String, JsValue> {
+    let moves = cube_core::decode_alg(code).map_err(|e| cube_error("codec", e))?;
+    format_algorithm(&moves, style)
+}
+
 #[wasm_bindgen(js_name = invertAlgorithm)]
-pub fn invert_algorithm(alg_str: &str) -> Result<String, JsValue> {
+pub fn invert_algorithm(alg_str: &str, style: Option<String>) -> Result<String, JsValue> {
     let moves = parse_algorithm(alg_str)?;
     let inverted = cube_core::invert_alg(&moves);
-    Ok(format_algorithm(&inverted))
+    format_algorithm(&inverted, style)
 }
 
 #[wasm_bindgen(js_name = commutator)]
-pub fn commutator(a_str: &str, b_str: &str) -> Result<String, JsValue> {
+pub fn commutator(a_str: &str, b_str: &str, style: Option<String>) -> Result<String, JsValue> {
     let a = parse_algorithm(a_str)?;
     let b = parse_algorithm(b_str)?;
     let result = cube_core::commutator(&a, &b);
-    Ok(format_algorithm(&result))
+    format_algorithm(&result, style)
 }
 
 #[wasm_bindgen(js_name = conjugate)]
-pub fn conjugate(a_str: &str, b_str: &str) -> Result<String, JsValue> {
+pub fn conjugate(a_str: &str, b_str: &str, style: Option<String>) -> Result<String, JsValue> {
     let a = parse_algorithm(a_str)?;
     let b = parse_algorithm(b_str)?;
     let result = cube_core::conjugate(&a, &b);
-    Ok(format_algorithm(&result))
+    format_algorithm(&result, style)
 }
 
-// Serializable types for JavaScript
-#[derive(Serialize, Deserialize)]
-struct CubeState {
-    corner_perm: Vec<u8>,
-    corner_ori: Vec<u8>,
-    edge_perm: Vec<u8>,
-    edge_ori: Vec<u8>,
+// Parse a single move; surrounding whitespace is ignored
+fn parse_move(s: &str) -> Result<Move, JsValue> {
+    let offset = s.len() - s.trim_start().len();
+    s.trim()
+        .parse()
+        .map_err(|e: cube_core::ParseError| parse_error(cube_core::ParseError { offset, ..e }))
 }
 
-impl CubeState {
-    fn from_cube(cube: &Cube) -> Self {
-        Self {
-            corner_perm: cube.corner_perm.to_vec(),
-            corner_ori: cube.corner_ori.to_vec(),
-            edge_perm: cube.edge_perm.to_vec(),
-            edge_ori: cube.edge_ori.to_vec(),
-        }
+pub(crate) fn parse_algorithm(s: &str) -> Result<Vec<Move>, JsValue> {
+    parse_alg(s).map_err(parse_error)
+}
+
+fn parse_drill_set(s: &str) -> Result<DrillSet, JsValue> {
+    Ok(match s {
+        "oll" => DrillSet::LastLayer(CaseSet::Oll),
+        "pll" => DrillSet::LastLayer(CaseSet::Pll),
+        "f2l" => DrillSet::F2l,
+        _ => return Err(cube_error("trainer", format!("unknown case set `{}`", s))),
+    })
+}
+
+fn solve_error(e: SolveError) -> JsValue {
+    let kind = match e {
+        SolveError::InvalidTables => "tables",
+        _ => "solve",
+    };
+    cube_error(kind, e)
+}
+
+// `"prime"` for `R'` (the default when no style is given) or `"p"` for `Rp`
+fn parse_style(style: Option<&str>) -> Result<NotationStyle, JsValue> {
+    match style {
+        None | Some("prime") => Ok(NotationStyle::Prime),
+        Some("p") => Ok(NotationStyle::P),
+        Some(style) => Err(cube_error(
+            "notation",
+            format!("unknown notation style `{}`", style),
+        )),
     }
 }
 
-#[derive(Serialize, Deserialize)]
-struct LegalityData {
-    corner_parity: i32,
-    edge_parity: i32,
-    edge_flip_sum_mod2: u8,
-    corner_twist_sum_mod3: u8,
-    is_legal: bool,
-}
-
-// Parse move notation
-fn parse_move(s: &str) -> Option<Move> {
-    match s.trim() {
-        "U" => Some(Move::U),
-        "U2" => Some(Move::U2),
-        "U'" | "Up" => Some(Move::Up),
-        "D" => Some(Move::D),
-        "D2" => Some(Move::D2),
-        "D'" | "Dp" => Some(Move::Dp),
-        "L" => Some(Move::L),
-        "L2" => Some(Move::L2),
-        "L'" | "Lp" => Some(Move::Lp),
-        "R" => Some(Move::R),
-        "R2" => Some(Move::R2),
-        "R'" | "Rp" => Some(Move::Rp),
-        "F" => Some(Move::F),
-        "F2" => Some(Move::F2),
-        "F'" | "Fp" => Some(Move::Fp),
-        "B" => Some(Move::B),
-        "B2" => Some(Move::B2),
-        "B'" | "Bp" => Some(Move::Bp),
-        _ => None,
-    }
-}
-
-fn parse_algorithm(s: &str) -> Result<Vec<Move>, JsValue> {
-    s.split_whitespace()
-        .map(|token| {
-            parse_move(token).ok_or_else(|| JsValue::from_str(&format!("Invalid move: {}", token)))
-        })
-        .collect()
+/// Rewrite an algorithm in a notation style: `"prime"` for `R'` (default) or `"p"` for `Rp`.
+/// The functions returning algorithms take the same optional `style` argument
+#[wasm_bindgen(js_name = formatAlgorithm)]
+pub fn format_algorithm_js(alg_str: &str, style: Option<String>) -> Result<String, JsValue> {
+    format_algorithm(&parse_algorithm(alg_str)?, style)
 }
 
-fn format_algorithm(moves: &[Move]) -> String {
-    moves
-        .iter()
-        .map(|m| format!("{:?}", m))
-        .collect::<Vec<_>>()
-        .join(" ")
+fn format_algorithm(moves: &[Move], style: Option<String>) -> Result<String, JsValue> {
+    Ok(format_alg(moves, parse_style(style.as_deref())?))
 }
@@ -0,0 +1,388 @@
+// Serializable types for JavaScript, with matching TypeScript declarations
+
+use cube_core::{
+    format_alg, Cube, Cubie, History, LegalityInfo, NotationStyle, PieceKind, SolverProgress,
+    Sticker,
+};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::error::cube_error;
+
+// Exported through TS_SECTION below; kept separate so the tests can read it
+#[allow(dead_code)]
+const TS_TYPES: &str = r#"
+export interface CubeState {
+    corner_perm: number[];
+    corner_ori: number[];
+    edge_perm: number[];
+    edge_ori: number[];
+}
+
+export interface LegalityData {
+    corner_parity: number;
+    edge_parity: number;
+    edge_flip_sum_mod2: number;
+    corner_twist_sum_mod3: number;
+    is_legal: boolean;
+}
+
+/** Disjoint cycles of piece positions, e.g. [[0, 1, 2]] */
+export type Cycles = number[][];
+
+export type FaceName = "U" | "D" | "L" | "R" | "F" | "B";
+
+/** A sticker: the direction it faces now and its color (named by its solved face) */
+export interface Sticker {
+    face: FaceName;
+    color: FaceName;
+}
+
+/** One of the 26 visible cubies; x points to R, y to U and z to F */
+export interface Cubie {
+    kind: "corner" | "edge" | "center";
+    /** Piece number (its solved slot) */
+    piece: number;
+    /** Slot it currently occupies */
+    slot: number;
+    position: [number, number, number];
+    /** Rotation from the solved orientation, as [x, y, z, w] */
+    quaternion: [number, number, number, number];
+    /** The same rotation as a row-major 3x3 matrix */
+    matrix: number[];
+    stickers: Sticker[];
+}
+
+/** Animation data for one move, matching the logic in cube-core */
+export interface MoveInfo {
+    face: FaceName;
+    axis: "x" | "y" | "z";
+    /** Coordinate of the turning layer along the axis (-1 or 1) */
+    layer: number;
+    /** Clockwise quarter turns seen from the face (1, 2 or 3) */
+    quarter_turns: number;
+    /** Angle in radians about the positive axis (right-handed) */
+    angle: number;
+    /** Corner slots in clockwise cycle order; each piece moves quarter_turns places along it */
+    corner_slots: number[];
+    /** Edge slots in clockwise cycle order */
+    edge_slots: number[];
+}
+
+export interface HistoryData {
+    /** Every move of the timeline, including ones that can be redone */
+    moves: string[];
+    /** Number of moves currently applied */
+    position: number;
+    /** First moves of the branches leaving the current position */
+    branches: string[];
+}
+
+export interface TrainerDrill {
+    case: string;
+    /** Random-state scramble for the case (only when the session has solver tables) */
+    scramble?: string;
+    state: CubeState;
+}
+
+export interface TrainerCount {
+    case: string;
+    count: number;
+}
+
+export interface SolveProgress {
+    nodes: number;
+    depth: number;
+    solution?: string;
+    length?: number;
+    improved: boolean;
+    done: boolean;
+}
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_SECTION: &str = TS_TYPES;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CubeState")]
+    pub type JsCubeState;
+
+    #[wasm_bindgen(typescript_type = "CubeState[]")]
+    pub type JsCubeStates;
+
+    #[wasm_bindgen(typescript_type = "boolean[]")]
+    pub type JsBools;
+
+    #[wasm_bindgen(typescript_type = "LegalityData")]
+    pub type JsLegalityData;
+
+    #[wasm_bindgen(typescript_type = "Cycles")]
+    pub type JsCycles;
+
+    #[wasm_bindgen(typescript_type = "Cubie[]")]
+    pub type JsCubies;
+
+    #[wasm_bindgen(typescript_type = "MoveInfo")]
+    pub type JsMoveInfo;
+
+    #[wasm_bindgen(typescript_type = "HistoryData")]
+    pub type JsHistoryData;
+
+    #[wasm_bindgen(typescript_type = "SolveProgress")]
+    pub type JsSolveProgress;
+
+    #[wasm_bindgen(typescript_type = "TrainerDrill")]
+    pub type JsTrainerDrill;
+
+    #[wasm_bindgen(typescript_type = "TrainerCount[]")]
+    pub type JsTrainerCounts;
+
+    #[wasm_bindgen(typescript_type = "string[]")]
+    pub type JsStrings;
+}
+
+// Serialize into the declared TypeScript type
+pub(crate) fn to_js<T: Serialize, U: JsCast>(value: &T) -> Result<U, JsValue> {
+    serde_wasm_bindgen::to_value(value)
+        .map(JsCast::unchecked_into)
+        .map_err(|e| cube_error("serialize", e))
+}
+
+// Deserialize a value passed from JavaScript
+pub(crate) fn from_js<T: for<'de> Deserialize<'de>>(value: JsValue) -> Result<T, JsValue> {
+    serde_wasm_bindgen::from_value(value).map_err(|e| cube_error("serialize", e))
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CubeState {
+    corner_perm: Vec<u8>,
+    corner_ori: Vec<u8>,
+    edge_perm: Vec<u8>,
+    edge_ori: Vec<u8>,
+}
+
+impl CubeState {
+    pub(crate) fn from_cube(cube: &Cube) -> Self {
+        Self {
+            corner_perm: cube.corner_perm.to_vec(),
+            corner_ori: cube.corner_ori.to_vec(),
+            edge_perm: cube.edge_perm.to_vec(),
+            edge_ori: cube.edge_ori.to_vec(),
+        }
+    }
+
+    /// The cube described by a state from JavaScript (pieces must form permutations)
+    pub(crate) fn to_cube(&self) -> Result<Cube, JsValue> {
+        cube_from_parts(
+            &self.corner_perm,
+            &self.corner_ori,
+            &self.edge_perm,
+            &self.edge_ori,
+        )
+        .map_err(|e| cube_error("state", e))
+    }
+}
+
+// Build a cube from its four fields, checking lengths, ranges and permutations
+pub(crate) fn cube_from_parts(
+    corner_perm: &[u8],
+    corner_ori: &[u8],
+    edge_perm: &[u8],
+    edge_ori: &[u8],
+) -> Result<Cube, String> {
+    let mut cube = Cube::identity();
+    copy_pieces(&mut cube.corner_perm, corner_perm, "corner_perm", 8)?;
+    copy_pieces(&mut cube.corner_ori, corner_ori, "corner_ori", 3)?;
+    copy_pieces(&mut cube.edge_perm, edge_perm, "edge_perm", 12)?;
+    copy_pieces(&mut cube.edge_ori, edge_ori, "edge_ori", 2)?;
+    let is_perm = |perm: &[u8]| (0..perm.len() as u8).all(|p| perm.contains(&p));
+    if !is_perm(&cube.corner_perm) || !is_perm(&cube.edge_perm) {
+        return Err("pieces must form a permutation".to_string());
+    }
+    Ok(cube)
+}
+
+// Copy one field, checking its length and that every value is below `bound`
+fn copy_pieces(dst: &mut [u8], src: &[u8], name: &str, bound: u8) -> Result<(), String> {
+    if src.len() != dst.len() || src.iter().any(|&v| v >= bound) {
+        return Err(format!(
+            "{} must hold {} values below {}",
+            name,
+            dst.len(),
+            bound
+        ));
+    }
+    dst.copy_from_slice(src);
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct LegalityData {
+    corner_parity: i32,
+    edge_parity: i32,
+    edge_flip_sum_mod2: u8,
+    corner_twist_sum_mod3: u8,
+    is_legal: bool,
+}
+
+impl LegalityData {
+    pub(crate) fn from_info(info: &LegalityInfo) -> Self {
+        Self {
+            corner_parity: info.corner_parity,
+            edge_parity: info.edge_parity,
+            edge_flip_sum_mod2: info.edge_flip_sum_mod2,
+            corner_twist_sum_mod3: info.corner_twist_sum_mod3,
+            is_legal: info.is_legal,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SolveProgress {
+    nodes: u64,
+    depth: usize,
+    solution: Option<String>,
+    length: Option<usize>,
+    improved: bool,
+    done: bool,
+}
+
+impl SolveProgress {
+    pub(crate) fn from_progress(progress: &SolverProgress, style: NotationStyle) -> Self {
+        Self {
+            nodes: progress.nodes,
+            depth: progress.depth,
+            solution: progress.best.as_deref().map(|m| format_alg(m, style)),
+            length: progress.best.as_ref().map(Vec::len),
+            improved: progress.improved,
+            done: progress.done,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct HistoryData {
+    moves: Vec<String>,
+    position: usize,
+    branches: Vec<String>,
+}
+
+impl HistoryData {
+    pub(crate) fn from_history(history: &History, style: NotationStyle) -> Self {
+        let notation = |m| format_alg(&[m], style);
+        Self {
+            moves: history.history().into_iter().map(notation).collect(),
+            position: history.position(),
+            branches: history.branches().into_iter().map(notation).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct CubieData {
+    kind: PieceKind,
+    piece: usize,
+    slot: usize,
+    position: [i8; 3],
+    quaternion: [f64; 4],
+    matrix: Vec<i8>,
+    stickers: Vec<Sticker>,
+}
+
+impl CubieData {
+    pub(crate) fn from_cubie(cubie: &Cubie) -> Self {
+        Self {
+            kind: cubie.kind,
+            piece: cubie.piece,
+            slot: cubie.slot,
+            position: cubie.position,
+            quaternion: cubie.quaternion(),
+            matrix: cubie.rotation.concat(),
+            stickers: cubie.stickers.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct TrainerDrill {
+    pub(crate) case: String,
+    pub(crate) scramble: Option<String>,
+    pub(crate) state: CubeState,
+}
+
+#[derive(Serialize)]
+pub(crate) struct TrainerCount<'a> {
+    pub(crate) case: &'a str,
+    pub(crate) count: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cube_core::{apply_alg, parse_alg, Move};
+
+    // Field names declared for `interface name` in TS_TYPES
+    fn declared_fields(name: &str) -> Vec<String> {
+        let start = TS_TYPES
+            .find(&format!("export interface {} {{", name))
+            .unwrap_or_else(|| panic!("no interface {}", name));
+        let body = &TS_TYPES[start..];
+        let body = &body[body.find('{').unwrap() + 1..body.find("\n}").unwrap()];
+        body.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("/**"))
+            .map(|line| line.split([':', '?']).next().unwrap().to_string())
+            .collect()
+    }
+
+    // Serialize and compare the keys with the declaration
+    fn assert_fields<T: Serialize>(name: &str, value: &T) -> serde_json::Value {
+        let json = serde_json::to_value(value).unwrap();
+        let mut keys: Vec<String> = json.as_object().unwrap().keys().cloned().collect();
+        let mut declared = declared_fields(name);
+        keys.sort();
+        declared.sort();
+        assert_eq!(keys, declared, "fields of {}", name);
+        json
+    }
+
+    #[test]
+    fn serde_structs_match_typescript_declarations() {
+        let cube = apply_alg(&Cube::identity(), &parse_alg("R U").unwrap());
+        let state = CubeState::from_cube(&cube);
+        assert_fields("CubeState", &state);
+        assert_fields("LegalityData", &LegalityData::from_info(&cube.legality()));
+        let cubie = assert_fields("Cubie", &CubieData::from_cubie(&cube.cubies()[0]));
+        assert_fields("Sticker", &cubie["stickers"][0]);
+        assert_fields("MoveInfo", &Move::R.info());
+
+        let mut history = History::default();
+        history.apply(Move::R);
+        let history = HistoryData::from_history(&history, NotationStyle::Prime);
+        assert_fields("HistoryData", &history);
+        let progress = SolverProgress {
+            nodes: 1,
+            depth: 0,
+            best: Some(vec![Move::R]),
+            improved: true,
+            done: false,
+        };
+        let progress = SolveProgress::from_progress(&progress, NotationStyle::Prime);
+        assert_fields("SolveProgress", &progress);
+        let drill = TrainerDrill {
+            case: "T".to_string(),
+            scramble: None,
+            state,
+        };
+        assert_fields("TrainerDrill", &drill);
+        assert_fields(
+            "TrainerCount",
+            &TrainerCount {
+                case: "T",
+                count: 1,
+            },
+        );
+    }
+}
@@ -1,6 +1,24 @@
 //! ワークスペース統合テスト
 
-use cube_core::{apply_alg, commutator, conjugate, invert_alg, Cube, Move};
+use std::sync::Arc;
+
+use cube_core::puzzle;
+use cube_core::{
+    alg_equivalent, alg_order, analyze, apply_alg, apply_void_alg, commutator, conjugate,
+    cycle_type, decode_alg, decode_state, edge_cycles, encode_alg, encode_state, format_alg,
+    format_big_alg, format_megaminx_alg, format_pochmann, format_pyraminx_alg, format_skewb_alg,
+    format_void_alg, group_report, invert_alg, invert_big_alg, megaminx_corner_faces,
+    megaminx_edge_faces, orbit_cycles, parse_alg, parse_big_alg, parse_domino_alg,
+    parse_floppy_alg, parse_megaminx_alg, parse_pyraminx_alg, parse_skewb_alg, parse_solution,
+    parse_void_alg, pochmann_scramble, recognize, scramble_for, solve, solve_beginner, solve_in,
+    solve_super, three_by_three_def, Axis, Bandage, BandageError, BandagedCube, BigCube,
+    BigCubeSize, CaseSet, CenterMode, CodecError, Cube, DefError, DefPuzzle, DistanceTable, Domino,
+    DominoCube, DominoDistanceTable, DominoTables, DrillSet, EquivOptions, Face, Floppy,
+    FloppyCube, History, MegaFace, Megaminx, Move, NotationStyle, OrbitProblem, PieceKind, Pocket,
+    PocketCube, PocketTables, Puzzle, Pyraminx, PyraminxTables, Rng, Rotation, Skewb, SkewbMove,
+    SkewbTables, Slice, SliceMove, SolveError, SolverSession, SolverTables, Stage, SuperCube,
+    ThreeByThree, Trainer, DOMINO_MOVES,
+};
 
 #[test]
 fn test_cube_solved_state() {
@@ -59,3 +77,1090 @@ fn test_legality() {
     let info = cube.legality();
     assert!(info.is_legal);
 }
+
+#[test]
+fn test_parse_alg() {
+    let alg = parse_alg("R U R' Up F2").unwrap();
+    assert_eq!(alg, vec![Move::R, Move::U, Move::Rp, Move::Up, Move::F2]);
+
+    let err = parse_alg("R  Q U").unwrap_err();
+    assert_eq!(err.token, "Q");
+    assert_eq!(err.index, 1);
+    assert_eq!(err.offset, 3);
+}
+
+#[test]
+fn test_alg_equivalent() {
+    let sune = parse_alg("R U R' U R U2 R'").unwrap();
+
+    // 完全一致
+    let same = parse_alg("R U R' U R U' U' R'").unwrap();
+    assert!(alg_equivalent(&sune, &same, &EquivOptions::exact()).is_some());
+
+    // 前後の AUF 違い
+    let shifted = parse_alg("U2 R U R' U R U2 R' U").unwrap();
+    assert!(alg_equivalent(&sune, &shifted, &EquivOptions::exact()).is_none());
+    let eq = alg_equivalent(&sune, &shifted, &EquivOptions::modulo_auf()).unwrap();
+    assert_eq!(eq.pre_auf, Some(Move::U2));
+    assert_eq!(eq.post_auf, Some(Move::U));
+
+    // 持ち替え違い（U 層の外にも作用するので AUF では一致しない）
+    let insert = parse_alg("R U R'").unwrap();
+    let rotated = parse_alg("F U F'").unwrap();
+    assert!(alg_equivalent(&insert, &rotated, &EquivOptions::modulo_auf()).is_none());
+    let eq = alg_equivalent(&insert, &rotated, &EquivOptions::modulo_symmetry()).unwrap();
+    assert_eq!(eq.rotation.name(), "y");
+}
+
+#[test]
+fn test_rotations() {
+    let all = Rotation::all();
+    assert_eq!(all.len(), 24);
+    assert!(all[0].is_identity());
+    for r in &all {
+        assert!(r.then(&r.inverse()).is_identity());
+    }
+}
+
+// ケースのセットアップ（手順の逆）を前 AUF つきで作る
+fn case_setup(auf: Move, alg: &[Move]) -> Cube {
+    let mut cube = Cube::identity();
+    cube = apply_alg(&cube, &invert_alg(alg));
+    cube.apply_move(auf.inverse());
+    cube
+}
+
+#[test]
+fn test_recognize_pll() {
+    let cube = case_setup(
+        Move::U,
+        &parse_alg("R U R' U' R' F R2 U' R' U' R U R' F'").unwrap(),
+    );
+    let m = recognize(&cube, CaseSet::Pll).unwrap();
+    assert_eq!(m.name, "T-perm");
+
+    let mut solved = apply_alg(&cube, &m.auf.into_iter().collect::<Vec<_>>());
+    solved = apply_alg(&solved, &m.algorithm);
+    assert!(recognize(&solved, CaseSet::Pll).unwrap().name == "PLL skip");
+
+    // 向きが揃っていなければ PLL ではない
+    let sune = case_setup(Move::U2, &parse_alg("R U R' U R U2 R'").unwrap());
+    assert!(recognize(&sune, CaseSet::Pll).is_none());
+    assert_eq!(recognize(&sune, CaseSet::Oll).unwrap().name, "OLL 27");
+}
+
+#[test]
+fn test_recognize_all_named_cases() {
+    use cube_core::recognition::cases;
+
+    assert_eq!(cases(CaseSet::Oll).len(), 57);
+    assert_eq!(cases(CaseSet::Pll).len(), 21);
+
+    // 各ケースが自分自身として判定され, 参照手順で揃う
+    for set in [CaseSet::Oll, CaseSet::Pll] {
+        for (name, alg) in cases(set) {
+            let setup = case_setup(Move::Up, alg);
+            let m = recognize(&setup, set).unwrap();
+            assert_eq!(m.name, name);
+            let mut done = setup;
+            if let Some(auf) = m.auf {
+                done.apply_move(auf);
+            }
+            done = apply_alg(&done, &m.algorithm);
+            assert!(recognize(&done, set).unwrap().name.ends_with("skip"));
+        }
+    }
+}
+
+#[test]
+fn test_rotation_apply_cube() {
+    let alg = parse_alg("R U F' L2 D B").unwrap();
+    let cube = apply_alg(&Cube::identity(), &alg);
+    for r in Rotation::all() {
+        let rotated = r.apply_cube(&cube);
+        assert_eq!(rotated, apply_alg(&Cube::identity(), &r.apply_alg(&alg)));
+        assert_eq!(
+            Cube::from_facelets(&rotated.to_facelets()),
+            Some(rotated),
+            "{}",
+            r
+        );
+    }
+}
+
+#[test]
+fn test_progress() {
+    let solved = Cube::identity();
+    assert_eq!(solved.progress().stage, Stage::Solved);
+    assert_eq!(solved.cross_faces().len(), 6);
+
+    // D クロスは残るが F2L が 1 つ崩れる
+    let cube = apply_alg(&solved, &parse_alg("R U R'").unwrap());
+    let p = cube.progress();
+    assert_eq!(p.cross_face, Some(Face::D));
+    assert_eq!(p.stage, Stage::Cross);
+    assert_eq!(p.f2l_slots.len(), 3);
+    assert!(!p.f2l_slots.contains(&[Face::F, Face::R]));
+
+    // U 面のクロス（R2 は D と U の両方を崩す）
+    let cube = apply_alg(&solved, &parse_alg("D R2 D'").unwrap());
+    assert!(cube.is_cross_solved(Face::L));
+    assert!(!cube.is_cross_solved(Face::D));
+
+    // OLL が終わった状態
+    let tperm = parse_alg("R U R' U' R' F R2 U' R' U' R U R' F'").unwrap();
+    let p = apply_alg(&solved, &tperm).progress();
+    assert_eq!(p.stage, Stage::Oll);
+    assert!(!p.last_layer_permuted);
+    let p = apply_alg(&solved, &[Move::U]).progress();
+    assert!(p.last_layer_permuted);
+}
+
+#[test]
+fn test_reconstruction_splits() {
+    let text = "R U' R'  // 4th pair\n\
+                R U R' U R U2 R'  // OLL\n\
+                y (R U R' U' R' F R2 U' R' U' R U R' F')  // PLL\n\
+                U";
+    let solution = parse_solution(text).unwrap();
+    assert_eq!(solution.moves.len(), 3 + 7 + 14 + 1);
+    assert_eq!(solution.comments[2].text, "PLL");
+    assert_eq!(
+        (solution.comments[2].start, solution.comments[2].end),
+        (10, 24)
+    );
+
+    let scramble = invert_alg(&solution.moves);
+    let report = analyze(&scramble, &solution);
+    assert!(report.solved);
+    assert_eq!(report.cross_face, Some(Face::D));
+    assert_eq!(report.oll_case.as_deref(), Some("OLL 27"));
+    assert_eq!(report.pll_case.as_deref(), Some("T-perm"));
+
+    let stages: Vec<(&str, usize)> = report
+        .splits
+        .iter()
+        .map(|s| (s.stage.as_str(), s.moves))
+        .collect();
+    assert_eq!(
+        stages,
+        vec![
+            ("cross", 0),
+            ("F2L 1", 0),
+            ("F2L 2", 0),
+            ("F2L 3", 0),
+            ("F2L 4", 3),
+            ("OLL", 7),
+            ("PLL", 14),
+            ("AUF", 1),
+        ]
+    );
+}
+
+#[test]
+fn test_solve_beginner() {
+    // 決まった擬似乱数列で作ったスクランブル
+    let mut seed: u64 = 2024;
+    for _ in 0..20 {
+        let scramble: Vec<Move> = (0..25)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                Move::ALL[(seed >> 33) as usize % 18]
+            })
+            .collect();
+        let mut cube = apply_alg(&Cube::identity(), &scramble);
+        let steps = solve_beginner(&cube).unwrap();
+        for step in &steps {
+            assert!(!step.explanation.is_empty());
+            cube = apply_alg(&cube, &step.moves);
+            if step.stage == "middle layer" {
+                assert!(cube.is_cross_solved(Face::D));
+            }
+        }
+        assert!(cube.is_solved());
+        assert!(steps.iter().any(|s| s.stage == "cross"));
+    }
+
+    assert!(solve_beginner(&Cube::identity()).unwrap().is_empty());
+    let mut twisted = Cube::identity();
+    twisted.corner_ori[0] = 1;
+    assert!(solve_beginner(&twisted).is_err());
+}
+
+#[test]
+fn test_two_phase_solver() {
+    let tables = Arc::new(SolverTables::build());
+    let bytes = tables.to_bytes();
+    assert!(SolverTables::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    let tables = Arc::new(SolverTables::from_bytes(&bytes).unwrap());
+
+    let mut seed: u64 = 7;
+    for _ in 0..3 {
+        let scramble: Vec<Move> = (0..30)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                Move::ALL[(seed >> 33) as usize % 18]
+            })
+            .collect();
+        let cube = apply_alg(&Cube::identity(), &scramble);
+        let solution = solve(&cube, &tables, 22).unwrap();
+        assert!(solution.len() <= 22);
+        assert!(apply_alg(&cube, &solution).is_solved());
+    }
+
+    // ランダムステートスクランブルはその状態を作る
+    let target = Cube::random(&mut Rng::new(3));
+    let scramble = scramble_for(&target, &tables).unwrap();
+    assert_eq!(apply_alg(&Cube::identity(), &scramble), target);
+
+    // 少しずつ進めても解は改善していく
+    let cube = apply_alg(
+        &Cube::identity(),
+        &parse_alg("R U F' L2 D B R' U2 F D'").unwrap(),
+    );
+    let mut session = SolverSession::new(&cube, Arc::clone(&tables), 0).unwrap();
+    let mut lengths = Vec::new();
+    while !session.is_done() {
+        let progress = session.step(10_000);
+        if progress.improved {
+            let best = progress.best.unwrap();
+            assert!(apply_alg(&cube, &best).is_solved());
+            lengths.push(best.len());
+        }
+    }
+    assert!(lengths.windows(2).all(|w| w[0] > w[1]));
+    assert!(*lengths.last().unwrap() <= 10);
+}
+
+#[test]
+fn test_notation_round_trip() {
+    assert_eq!(Move::Up.to_string(), "U'");
+    assert_eq!(Move::Rp.to_notation(NotationStyle::P), "Rp");
+
+    let inverse = invert_alg(&parse_alg("R U").unwrap());
+    assert_eq!(format_alg(&inverse, NotationStyle::Prime), "U' R'");
+    for style in [NotationStyle::Prime, NotationStyle::P] {
+        let text = format_alg(&Move::ALL, style);
+        assert_eq!(parse_alg(&text).unwrap(), Move::ALL);
+    }
+}
+
+#[test]
+fn test_history_undo_redo_branch() {
+    let alg = parse_alg("R U R' U'").unwrap();
+    let mut history = History::default();
+    history.apply_alg(&alg);
+    assert_eq!(history.position(), 4);
+
+    assert_eq!(history.undo(), Some(Move::Up));
+    assert_eq!(history.undo(), Some(Move::Rp));
+    assert_eq!(*history.cube(), apply_alg(&Cube::identity(), &alg[..2]));
+    assert_eq!(history.redo(), Some(Move::Rp));
+    assert_eq!(history.history(), alg);
+
+    history.seek(0).unwrap();
+    assert!(history.cube().is_solved());
+    assert!(!history.can_undo());
+    assert!(history.seek(5).is_err());
+
+    // 戻した位置から別の手を回すと枝分かれし, 元の枝も残る
+    history.seek(2).unwrap();
+    history.apply(Move::F);
+    assert_eq!(history.history(), parse_alg("R U F").unwrap());
+    history.undo();
+    assert_eq!(history.branches(), vec![Move::F, Move::Rp]);
+    assert!(history.switch_branch(Move::Rp));
+    history.seek(4).unwrap();
+    assert_eq!(*history.cube(), apply_alg(&Cube::identity(), &alg));
+}
+
+#[test]
+fn test_cubies_geometry() {
+    let solved = Cube::identity().cubies();
+    assert_eq!(solved.len(), 26);
+    assert!(solved
+        .iter()
+        .all(|c| c.stickers.iter().all(|s| s.face == s.color)));
+
+    let cube = apply_alg(&Cube::identity(), &parse_alg("R U F' L2 D B'").unwrap());
+    for (c, home) in cube.cubies().iter().zip(&solved) {
+        assert_eq!(home.piece, home.slot);
+        let home = &solved[solved
+            .iter()
+            .position(|h| h.kind == c.kind && h.slot == c.piece)
+            .unwrap()];
+        let r = c.rotation;
+        // 回転行列は直交で行列式 1, 元の位置を現在の位置へ移す
+        let det = r[0][0] * (r[1][1] * r[2][2] - r[1][2] * r[2][1])
+            - r[0][1] * (r[1][0] * r[2][2] - r[1][2] * r[2][0])
+            + r[0][2] * (r[1][0] * r[2][1] - r[1][1] * r[2][0]);
+        assert_eq!(det, 1);
+        let moved: Vec<i8> = (0..3)
+            .map(|i| (0..3).map(|j| r[i][j] * home.position[j]).sum())
+            .collect();
+        assert_eq!(moved, c.position);
+        let q = c.quaternion();
+        assert!((q.iter().map(|x| x * x).sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+    assert_eq!(cube.cubies()[20].kind, PieceKind::Center);
+}
+
+#[test]
+fn test_move_info() {
+    let info = Move::R.info();
+    assert_eq!(info.face, Face::R);
+    assert_eq!(info.axis, Axis::X);
+    assert_eq!(info.layer, 1);
+    assert!((info.angle + std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+
+    // 回転角で位置を回すと, 巡回順で quarter_turns 個先の位置になる
+    for m in Move::ALL {
+        let info = m.info();
+        let solved = Cube::identity().cubies();
+        let (c, s) = (
+            info.angle.cos().round() as i8,
+            info.angle.sin().round() as i8,
+        );
+        let rotate = |p: [i8; 3]| match info.axis {
+            Axis::X => [p[0], c * p[1] - s * p[2], s * p[1] + c * p[2]],
+            Axis::Y => [c * p[0] + s * p[2], p[1], -s * p[0] + c * p[2]],
+            Axis::Z => [c * p[0] - s * p[1], s * p[0] + c * p[1], p[2]],
+        };
+        let q = info.quarter_turns as usize;
+        for (offset, slots) in [(0, info.corner_slots), (8, info.edge_slots)] {
+            for k in 0..4 {
+                let from = solved[offset + slots[k]].position;
+                let to = solved[offset + slots[(k + q) % 4]].position;
+                assert_eq!(rotate(from), to, "{}", m);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_random_state_and_trainer() {
+    // 同じシードからは同じ状態
+    let a = Cube::random(&mut Rng::new(42));
+    assert_eq!(a, Cube::random(&mut Rng::new(42)));
+    assert!(a.is_legal());
+
+    let mut trainer = Trainer::new(DrillSet::F2l, 1);
+    assert_eq!(trainer.cases().len(), 41);
+    for _ in 0..20 {
+        let drill = trainer.next_drill();
+        assert!(drill.cube.is_legal());
+        assert!(!drill.cube.is_solved());
+    }
+
+    let mut trainer = Trainer::new(DrillSet::LastLayer(CaseSet::Pll), 5);
+    for _ in 0..10 {
+        let drill = trainer.next_drill();
+        assert_eq!(
+            recognize(&drill.cube, CaseSet::Pll).unwrap().name,
+            drill.case
+        );
+    }
+    assert_eq!(trainer.drilled().len(), 10);
+    let total: u32 = trainer.counts().iter().map(|&(_, n)| n).sum();
+    assert_eq!(total, 10);
+    let first = trainer.drilled()[0].clone();
+    assert!(trainer.count(&first) >= 1);
+}
+
+#[test]
+fn test_url_codec() {
+    let cube = Cube::random(&mut Rng::new(9));
+    let code = encode_state(&cube);
+    assert!(code.starts_with("s1."));
+    assert_eq!(decode_state(&code).unwrap(), cube);
+
+    let alg = parse_alg("R U R' U' F2 D' B").unwrap();
+    assert_eq!(decode_alg(&encode_alg(&alg)).unwrap(), alg);
+
+    // 壊れた入力は種類ごとのエラーになる
+    assert!(matches!(
+        decode_state(&code.replace("s1.", "s9.")),
+        Err(CodecError::UnsupportedVersion { .. })
+    ));
+    assert!(matches!(
+        decode_state(&code[..code.len() - 1]),
+        Err(CodecError::WrongLength { .. })
+    ));
+    assert!(matches!(
+        decode_alg("a1.AB*"),
+        Err(CodecError::InvalidCharacter { ch: '*', offset: 5 })
+    ));
+    let mut twisted = Cube::identity();
+    twisted.corner_ori[0] = 1;
+    assert!(matches!(
+        decode_state(&encode_state(&twisted)),
+        Err(CodecError::Illegal(_))
+    ));
+}
+
+#[test]
+fn test_cycle_type() {
+    // U は角と辺をそれぞれ 4-cycle で動かす
+    let cube = apply_alg(&Cube::identity(), &[Move::U]);
+    assert_eq!(cycle_type(&cube.corner_perm), vec![4, 0, 0, 1, 0, 0, 0, 0]);
+    assert_eq!(
+        cycle_type(&cube.edge_perm),
+        vec![8, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+fn test_pocket_cube_solver() {
+    let tables = PocketTables::build();
+
+    // 持ち替えで移り合う状態は揃っているとみなす（R L' は x 持ち替え）
+    let rotated = PocketCube::identity().apply_alg(&parse_alg("R L'").unwrap());
+    assert!(rotated.is_solved());
+
+    let cube = PocketCube::identity().apply_alg(&parse_alg("L D' B2 R U'").unwrap());
+    assert!(cube.is_legal());
+    let solution = tables.solve(&cube).unwrap();
+    assert_eq!(solution.len(), tables.distance(&cube).unwrap());
+    assert!(solution.len() <= 5);
+    assert!(cube.apply_alg(&solution).is_solved());
+
+    let mut rng = Rng::new(11);
+    for _ in 0..5 {
+        let scramble = tables.random_scramble(&mut rng);
+        // 2x2x2 の最長は 11 手
+        assert!(scramble.len() <= 11);
+        let scrambled = PocketCube::identity().apply_alg(&scramble);
+        assert!(scrambled
+            .apply_alg(&tables.solve(&scrambled).unwrap())
+            .is_solved());
+    }
+
+    let mut twisted = PocketCube::identity();
+    twisted.corner_ori[0] = 1;
+    assert!(!twisted.is_legal());
+    assert!(tables.solve(&twisted).is_err());
+}
+
+#[test]
+fn test_big_cube() {
+    let alg = parse_big_alg("R 2R Rw r 3Rw 2-3Rw x'", 4).unwrap();
+    let layers: Vec<(u8, u8)> = alg.iter().map(|m| (m.from, m.to)).collect();
+    assert_eq!(
+        layers,
+        vec![(1, 1), (2, 2), (1, 2), (1, 2), (1, 3), (2, 3), (1, 4)]
+    );
+    assert_eq!(format_big_alg(&alg, 4), "R 2R Rw Rw 3Rw 2-3Rw x'");
+    assert!(parse_big_alg("5R", 4).is_err());
+    assert!(parse_big_alg("3Rw", 5).is_ok());
+
+    // 外側と内側の層を合わせて回しても 4 回で戻る. セクシームーブは 6 回
+    let cube = BigCube::new(4).unwrap();
+    assert!(cube
+        .apply_alg(&parse_big_alg("Rw Rw Rw Rw", 4).unwrap())
+        .is_solved());
+    let sexy = parse_big_alg("R U R' U'", 4).unwrap();
+    let mut c = cube.clone();
+    for i in 1..=6 {
+        c = c.apply_alg(&sexy);
+        assert_eq!(c.is_solved(), i == 6);
+    }
+    assert!(cube
+        .apply_alg(&parse_big_alg("x y z", 4).unwrap())
+        .is_solved());
+
+    // 内側の 1 層だけ回した状態も合法. ウイングは 4-cycle で動く
+    let slice = cube.apply_alg(&parse_big_alg("2R", 4).unwrap());
+    assert!(!slice.is_solved());
+    assert!(slice.is_legal());
+    let wings = &slice.orbits().iter().find(|o| o.name == "wings 2").unwrap();
+    assert_eq!(
+        slice
+            .orbit_cycles(wings)
+            .iter()
+            .map(Vec::len)
+            .collect::<Vec<_>>(),
+        vec![4]
+    );
+
+    // 7x7 は 11 個の軌道に分かれる
+    let big = BigCube::new(7).unwrap();
+    assert_eq!(big.orbits().len(), 11);
+    let scrambled = big.apply_alg(&parse_big_alg("3Rw U' 2-3Lw 4F2 y b' 3R", 7).unwrap());
+    assert!(scrambled.is_legal());
+    assert!(scrambled
+        .apply_alg(&invert_big_alg(
+            &parse_big_alg("3Rw U' 2-3Lw 4F2 y b' 3R", 7).unwrap()
+        ))
+        .is_solved());
+
+    // 角の 3 枚のステッカーを回すとねじれ, 中央の辺の 2 枚を入れ替えると反転
+    let mut stickers = BigCube::new(5).unwrap().stickers().to_vec();
+    let (u, f, r) = (4 * 5 + 4, 4 * 25 + 4, 3 * 25);
+    let (a, b, c) = (stickers[u], stickers[f], stickers[r]);
+    (stickers[u], stickers[f], stickers[r]) = (c, a, b);
+    let twisted = BigCube::from_stickers(5, stickers).unwrap();
+    let corners = twisted
+        .legality()
+        .into_iter()
+        .find(|o| o.name == "corners")
+        .unwrap();
+    assert_eq!(corners.problems, vec![OrbitProblem::Twist]);
+
+    let mut stickers = BigCube::new(5).unwrap().stickers().to_vec();
+    stickers.swap(4 * 5 + 2, 4 * 25 + 2);
+    let flipped = BigCube::from_stickers(5, stickers).unwrap();
+    let midges = flipped
+        .legality()
+        .into_iter()
+        .find(|o| o.name == "midges")
+        .unwrap();
+    assert_eq!(midges.problems, vec![OrbitProblem::Flip]);
+    assert!(BigCube::from_stickers(5, vec![0; 150]).is_none());
+
+    // 角と中央の辺のステッカーを入れ替えると, 軌道内の置換にはならないが panic しない
+    let mut stickers = BigCube::new(5).unwrap().stickers().to_vec();
+    stickers.swap(0, 2);
+    let mixed = BigCube::from_stickers(5, stickers).unwrap();
+    let corners = mixed.orbits().iter().find(|o| o.name == "corners").unwrap();
+    assert!(mixed.orbit_perm(corners).is_none());
+    assert!(!mixed.orbit_cycles(corners).is_empty());
+    let five = BigCubeSize::new(5).unwrap();
+    for (i, orbit) in mixed.orbits().iter().enumerate() {
+        let mut perm = five.orbit_perm(&mixed, i);
+        perm.sort_unstable();
+        assert!(perm.iter().copied().eq(0..orbit.cubies.len() as u8));
+        assert_eq!(orbit_cycles(&five, &mixed, i), mixed.orbit_cycles(orbit));
+    }
+}
+
+#[test]
+fn test_generic_puzzle() {
+    let sexy = parse_alg("R U R' U'").unwrap();
+    assert_eq!(alg_order(&ThreeByThree, &parse_alg("R U").unwrap()), 105);
+    assert_eq!(alg_order(&ThreeByThree, &sexy), 6);
+
+    // 3x3x3 の操作は一般の関数と同じ結果になる
+    let a = parse_alg("R").unwrap();
+    let b = parse_alg("U").unwrap();
+    assert_eq!(
+        puzzle::commutator(&ThreeByThree, &a, &b),
+        commutator(&a, &b)
+    );
+    let cube = apply_alg(&Cube::identity(), &sexy);
+    assert_eq!(
+        orbit_cycles(&ThreeByThree, &cube, 0),
+        cube_core::corner_cycles(&cube)
+    );
+
+    // NxNxN でも同じ関数が使える
+    let four = BigCubeSize::new(4).unwrap();
+    let big_sexy = parse_big_alg("R U R' U'", 4).unwrap();
+    assert_eq!(alg_order(&four, &big_sexy), 6);
+    assert_eq!(
+        puzzle::commutator(&four, &big_sexy[..1], &big_sexy[1..2]),
+        big_sexy
+    );
+    let five = BigCubeSize::new(5).unwrap();
+    let names: Vec<String> = five.orbits().into_iter().map(|o| o.name).collect();
+    assert!(names.contains(&"midges".to_string()));
+    let state = puzzle::apply(
+        &five,
+        &five.identity(),
+        &parse_big_alg("3Rw U 2F' r2 D' 3L B 2U2 R'", 5).unwrap(),
+    );
+    assert!(five.is_legal(&state));
+    for (i, orbit) in five.orbits().iter().enumerate() {
+        let ori = five.orbit_ori(&state, i);
+        let sum: u32 = ori.iter().map(|&o| o as u32).sum();
+        assert_eq!(sum % orbit.orientations as u32, 0, "{}", orbit.name);
+    }
+
+    // 2x2x2 は DBL を固定した <U, R, F> で, 持ち替えの分を含まない
+    assert_eq!(Pocket.moves().len(), 9);
+    assert_eq!(
+        group_report(&Pocket).order,
+        Some(cube_core::pocket::POCKET_STATES as u128)
+    );
+    assert_eq!(cube_core::pocket::POCKET_STATES, 3_674_160);
+
+    // 部分群 <U2, R2> の最短手数表
+    let table = DistanceTable::build_with_moves(ThreeByThree, vec![Move::U2, Move::R2]);
+    assert_eq!(table.len(), 12);
+    assert_eq!(table.diameter(), 6);
+    let state = apply_alg(&Cube::identity(), &parse_alg("R2 U2 R2").unwrap());
+    let solution = table.solve(&state).unwrap();
+    assert_eq!(solution.len(), 3);
+    assert!(apply_alg(&state, &solution).is_solved());
+    assert_eq!(
+        table.distance(&apply_alg(&Cube::identity(), &[Move::U])),
+        None
+    );
+}
+
+#[test]
+fn test_def_puzzle() {
+    // 3x3x3 を定義ファイルにして読み直すと, `Cube` と同じ状態になる
+    let text = three_by_three_def();
+    let def = DefPuzzle::parse(&text).unwrap();
+    assert_eq!(def.move_names(), vec!["U", "D", "L", "R", "F", "B"]);
+    assert_eq!(DefPuzzle::parse(&def.to_def()).unwrap(), def);
+
+    let scramble = "R U2 F' L D B2 R' U' F2 D' L2 B";
+    let cube = apply_alg(&Cube::identity(), &parse_alg(scramble).unwrap());
+    let alg = def.parse_alg(scramble).unwrap();
+    assert_eq!(def.format_alg(&alg), scramble);
+    let state = puzzle::apply(&def, &def.identity(), &alg);
+    assert_eq!(
+        state.perm,
+        vec![cube.corner_perm.to_vec(), cube.edge_perm.to_vec()]
+    );
+    assert_eq!(
+        state.ori,
+        vec![cube.corner_ori.to_vec(), cube.edge_ori.to_vec()]
+    );
+    assert!(def.is_legal(&state));
+    assert!(def.is_solved(&puzzle::apply(&def, &state, &puzzle::invert(&def, &alg))));
+
+    // 一般の道具がそのまま使える
+    assert_eq!(alg_order(&def, &def.parse_alg("R U").unwrap()), 105);
+    let subgroup = DistanceTable::build_with_moves(def.clone(), def.parse_alg("U2 R2").unwrap());
+    assert_eq!(subgroup.len(), 12);
+
+    // 角を 1 つねじった状態と, 辺を 2 つ入れ替えた状態は作れない
+    let mut twisted = def.identity();
+    twisted.ori[0][0] = 1;
+    assert!(!def.is_legal(&twisted));
+    let mut swapped = def.identity();
+    swapped.perm[1].swap(0, 1);
+    assert!(!def.is_legal(&swapped));
+
+    // 区別しないパーツと, 向きのない軌道
+    let toy = DefPuzzle::parse(
+        "Name toy
+         Set PIECES 4 1
+         Solved
+         PIECES
+         1 1 2 2
+         End
+         Move A   # 1 と 2 の入れ替え
+         PIECES
+         2 1 3 4
+         End
+         Move B
+         PIECES
+         1 3 2 4
+         End",
+    )
+    .unwrap();
+    let table = DistanceTable::build(toy.clone());
+    assert_eq!(table.len(), 3);
+    assert_eq!(toy.move_order(0), 2);
+
+    assert_eq!(
+        DefPuzzle::parse("Set A 3 1\nMove X\nB\n1 2 3\nEnd"),
+        Err(DefError::UnknownSet {
+            line: 3,
+            name: "B".to_string()
+        })
+    );
+    assert_eq!(
+        DefPuzzle::parse("Set A 3 1\nMove X\nA\n1 1 3\nEnd"),
+        Err(DefError::InvalidPermutation {
+            line: 4,
+            set: "A".to_string()
+        })
+    );
+    assert_eq!(
+        DefPuzzle::parse("Set A 2 2\nSolved\nA\n1 2\n0 2\nEnd"),
+        Err(DefError::InvalidOrientation {
+            line: 5,
+            set: "A".to_string()
+        })
+    );
+    let many: String = (0..65).map(|i| format!("Set S{} 2 1\n", i)).collect();
+    assert_eq!(
+        DefPuzzle::parse(&many),
+        Err(DefError::TooManySets { line: 65 })
+    );
+}
+
+#[test]
+fn test_supercube() {
+    // 面を 1/4 回転すると, その面のセンターも回る
+    let sexy = parse_alg("R U R' U'").unwrap();
+    let mut c = SuperCube::identity();
+    for _ in 0..6 {
+        c = c.apply_alg(&sexy);
+    }
+    assert!(c.is_solved());
+    let twisted = SuperCube::identity()
+        .apply_alg(&parse_alg("R U R' U' R' F R2 U' R' U' R U R' F'").unwrap());
+    assert!(twisted.cube.legality().is_legal);
+    assert_eq!(twisted.center_ori, [3, 0, 0, 0, 0, 0]);
+    assert!(twisted.is_legal());
+    assert!(!twisted.is_solved());
+
+    // センターの回転数の総和の偶奇は角の置換の偶奇と一致する
+    let mut bad = SuperCube::identity();
+    bad.center_ori[4] = 1;
+    let info = bad.legality();
+    assert!(info.cube.is_legal);
+    assert_eq!(info.center_twist_sum_mod2, 1);
+    assert!(!info.is_legal);
+
+    let tables = Arc::new(SolverTables::build());
+    assert_eq!(
+        solve_super(&bad, &tables, 21),
+        Err(cube_core::SolveError::Illegal)
+    );
+    let mut rng = Rng::new(5);
+    for scramble in [
+        "R U R' U' R' F R2 U' R' U' R U R' F'",
+        "U R L U2 R' L' U R L U2 R' L'",
+    ] {
+        let mut cube = SuperCube::identity().apply_alg(&parse_alg(scramble).unwrap());
+        for _ in 0..20 {
+            cube.apply_move(*rng.choose(&Move::ALL));
+        }
+        let solution = solve_super(&cube, &tables, 21).unwrap();
+        assert!(cube.apply_alg(&solution).is_solved());
+    }
+
+    // 角と辺が揃っていれば, センターの向きの合法な組み合わせは全て揃えられる
+    for code in 0..4096 {
+        let mut cube = SuperCube::identity();
+        for (f, ori) in cube.center_ori.iter_mut().enumerate() {
+            *ori = (code >> (2 * f)) as u8 % 4;
+        }
+        if !cube.is_legal() {
+            continue;
+        }
+        let solution = solve_super(&cube, &tables, 21).unwrap();
+        assert!(
+            cube.apply_alg(&solution).is_solved(),
+            "{:?}",
+            cube.center_ori
+        );
+    }
+}
+
+#[test]
+fn test_pyraminx_and_skewb() {
+    // ピラミンクス: 記法の往復と手の位数
+    let alg = parse_pyraminx_alg("U L' r b' B").unwrap();
+    assert_eq!(format_pyraminx_alg(&alg), "U L' r b' B");
+    assert!(parse_pyraminx_alg("U2").is_err());
+    let mut p = Pyraminx::identity();
+    for _ in 0..3 {
+        p = p.apply_alg(&parse_pyraminx_alg("R").unwrap());
+    }
+    assert!(p.is_solved());
+    let sune = Pyraminx::identity().apply_alg(&parse_pyraminx_alg("R U R' U R U R'").unwrap());
+    assert!(sune.is_legal());
+    assert_eq!(sune.edge_cycles().iter().filter(|c| c.len() > 1).count(), 1);
+
+    let mut bad = Pyraminx::identity();
+    bad.edge_ori[0] = 1;
+    assert_eq!(bad.legality().edge_flip_sum_mod2, 1);
+    assert!(!bad.is_legal());
+
+    // 範囲外の番号や向きは panic せずに不正とする
+    let mut junk = Pyraminx::identity();
+    junk.edge_perm[0] = 9;
+    junk.edge_ori = [200; 6];
+    assert!(!junk.is_legal());
+
+    let tables = PyraminxTables::build();
+    assert_eq!(tables.states(), 933_120);
+    assert_eq!(tables.diameter(), 11);
+    assert_eq!(tables.solve(&bad), Err(SolveError::Illegal));
+    assert_eq!(tables.solve(&junk), Err(SolveError::Illegal));
+    let mut rng = Rng::new(46);
+    for _ in 0..5 {
+        let scramble = tables.random_scramble(&mut rng);
+        let state = Pyraminx::identity().apply_alg(&scramble);
+        assert!(state.is_legal());
+        let solution = tables.solve(&state).unwrap();
+        assert!(solution.len() <= scramble.len());
+        assert!(state.apply_alg(&solution).is_solved());
+    }
+    assert_eq!(tables.distance(&sune).unwrap(), 7);
+
+    // スキューブ: 記法の往復と手の位数
+    let alg = parse_skewb_alg("R U' L B'").unwrap();
+    assert_eq!(format_skewb_alg(&alg), "R U' L B'");
+    assert!(parse_skewb_alg("F").is_err());
+    let mut s = Skewb::identity();
+    for _ in 0..3 {
+        s = s.apply_alg(&parse_skewb_alg("B").unwrap());
+    }
+    assert!(s.is_solved());
+    let s = Skewb::identity().apply_alg(&parse_skewb_alg("R").unwrap());
+    assert_eq!(s.center_cycles().iter().filter(|c| c.len() == 3).count(), 1);
+    assert_eq!(s.corner_cycles().iter().filter(|c| c.len() == 3).count(), 1);
+
+    // 手で届く状態はすべて合法
+    let mut s = Skewb::identity();
+    for _ in 0..200 {
+        s.apply_move(*rng.choose(&SkewbMove::ALL));
+        assert!(s.is_legal());
+    }
+    let mut twisted = Skewb::identity();
+    twisted.corner_ori[0] = 1;
+    twisted.corner_ori[2] = 2;
+    assert!(twisted.is_legal());
+    twisted.corner_ori[3] = 1;
+    assert_eq!(twisted.legality().twist_sums, [1, 0]);
+    assert!(!twisted.is_legal());
+    let mut junk = Skewb::identity();
+    junk.center_perm[0] = 9;
+    junk.corner_ori = [100; 8];
+    assert!(!junk.is_legal());
+
+    let tables = SkewbTables::build();
+    assert_eq!(tables.states(), 3_149_280);
+    assert_eq!(tables.diameter(), 11);
+    assert_eq!(tables.solve(&junk), Err(SolveError::Illegal));
+    for _ in 0..5 {
+        let state = Skewb::random(&mut rng);
+        let solution = tables.solve(&state).unwrap();
+        assert_eq!(solution.len(), tables.distance(&state).unwrap());
+        assert!(state.apply_alg(&solution).is_solved());
+        let scramble = tables.random_scramble(&mut rng);
+        assert!(Skewb::identity().apply_alg(&scramble).is_legal());
+    }
+}
+
+#[test]
+fn test_megaminx() {
+    let alg = parse_megaminx_alg("R++ D-- U' F2' BL DBR2").unwrap();
+    assert_eq!(format_megaminx_alg(&alg), "R++ D-- U' F2' BL DBR2");
+    assert!(parse_megaminx_alg("R3").is_err());
+    assert!(parse_megaminx_alg("X++").is_err());
+
+    // 面の手も全体回しも 5 回で元に戻る
+    for s in ["R", "DBL2'", "R++", "D--"] {
+        let m = parse_megaminx_alg(s).unwrap();
+        let mut c = Megaminx::identity();
+        for _ in 0..5 {
+            c = c.apply_alg(&m);
+        }
+        assert!(c.is_solved(), "{}", s);
+    }
+    let r = Megaminx::identity().apply_alg(&parse_megaminx_alg("R").unwrap());
+    assert_eq!(r.corner_cycles().iter().filter(|c| c.len() == 5).count(), 1);
+    assert_eq!(r.edge_cycles().iter().filter(|c| c.len() == 5).count(), 1);
+    assert_eq!(MegaFace::R.opposite(), MegaFace::DBL);
+    assert!(megaminx_corner_faces(0).contains(&MegaFace::U));
+    assert_eq!(megaminx_edge_faces(0), [MegaFace::U, MegaFace::F]);
+
+    // 全体回し = 向かいの面を逆に回す
+    let rotated = Megaminx::identity().apply_alg(&parse_megaminx_alg("R+ DBL'").unwrap());
+    assert!(!rotated.is_solved());
+    assert!(rotated.is_solved_up_to_rotation());
+
+    let mut rng = Rng::new(47);
+    let scramble = pochmann_scramble(&mut rng);
+    assert_eq!(scramble.len(), 77);
+    let text = format_pochmann(&scramble);
+    assert_eq!(text.lines().count(), 7);
+    assert!(text.lines().all(|l| l.ends_with('U') || l.ends_with("U'")));
+    assert_eq!(
+        parse_megaminx_alg(&text.replace('\n', " ")).unwrap(),
+        scramble
+    );
+    let c = Megaminx::identity().apply_alg(&scramble);
+    assert!(c.is_legal());
+    assert!(!c.is_solved_up_to_rotation());
+    let inverse: Vec<_> = scramble.iter().rev().map(|m| m.inverse()).collect();
+    assert!(c.apply_alg(&inverse).is_solved());
+
+    let mut bad = Megaminx::identity();
+    bad.corner_ori[3] = 1;
+    assert_eq!(bad.legality().corner_twist_sum_mod3, 1);
+    assert!(!bad.is_legal());
+    let mut bad = Megaminx::identity();
+    bad.edge_perm.swap(0, 1);
+    assert_eq!(bad.legality().edge_parity, -1);
+    assert!(!bad.is_legal());
+}
+
+#[test]
+fn test_cuboids() {
+    // 側面の 90 度回しは受け付けない
+    assert!(parse_domino_alg("U R2 D' F2").is_ok());
+    assert_eq!(parse_domino_alg("U R").unwrap_err().index, 1);
+    assert!(parse_floppy_alg("R2 F2").is_ok());
+    assert!(parse_floppy_alg("R2 U").is_err());
+
+    // サイクルと位数
+    let alg = parse_domino_alg("U R2").unwrap();
+    assert_eq!(alg_order(&Domino, &alg), 30);
+    let c = DominoCube::identity().apply_alg(&alg);
+    assert!(c.is_legal());
+    assert_eq!(c.corner_cycles(), orbit_cycles(&Domino, &c, 0));
+    let f = FloppyCube::identity().apply_alg(&parse_floppy_alg("R2 F2").unwrap());
+    assert_eq!(f.corner_cycles(), vec![vec![0, 2, 1]]);
+    assert!(f.is_legal());
+    let mut bad = f;
+    bad.edge_ori[0] ^= 1;
+    assert!(!bad.is_legal());
+
+    // 群の位数: 3x3 と比べる
+    let report = group_report(&ThreeByThree);
+    assert_eq!(report.order, Some(43_252_003_274_489_856_000));
+    assert_eq!(group_report(&Domino).order, Some(1_625_702_400));
+    let floppy = group_report(&Floppy);
+    assert_eq!(floppy.order, Some(192));
+    assert!(floppy
+        .to_string()
+        .ends_with("order: 192 = 4 * 3 * 2 * 2 * 2 * 2"));
+
+    // 全状態の最短手数表
+    let table = DistanceTable::build(Floppy);
+    assert_eq!(table.len() as u128, floppy.order.unwrap());
+    assert_eq!(table.diameter(), 8);
+    assert_eq!(table.distance(&f), Some(2));
+    let half_turns = DistanceTable::build_with_moves(
+        Domino,
+        vec![Move::U2, Move::D2, Move::R2, Move::L2, Move::F2, Move::B2],
+    );
+    assert_eq!(half_turns.len(), 55_296);
+    assert_eq!(half_turns.diameter(), 12);
+
+    // ドミノの最短解
+    let tables = DominoTables::build();
+    let mut rng = Rng::new(48);
+    for _ in 0..3 {
+        let scramble: Vec<Move> = (0..8).map(|_| *rng.choose(&DOMINO_MOVES)).collect();
+        let c = DominoCube::identity().apply_alg(&scramble);
+        let solution = tables.solve(&c).unwrap();
+        assert!(solution.len() <= scramble.len());
+        assert!(c.apply_alg(&solution).is_solved());
+    }
+    assert_eq!(
+        tables.distance(&DominoCube::identity().apply_alg(&alg)),
+        Ok(2)
+    );
+}
+
+// 全状態の表は約 400MB で, release でも数分かかるので `cargo test --release -- --ignored` で確かめる
+#[test]
+#[ignore]
+fn test_domino_distance_table() {
+    let table = DominoDistanceTable::build();
+    assert_eq!(table.len(), 1_625_702_400);
+    assert_eq!(table.depth_counts()[..3], [1, 10, 67]);
+    assert_eq!(table.diameter(), 18);
+    let tables = DominoTables::build();
+    let mut rng = Rng::new(48);
+    for _ in 0..5 {
+        let scramble: Vec<Move> = (0..20).map(|_| *rng.choose(&DOMINO_MOVES)).collect();
+        let c = DominoCube::identity().apply_alg(&scramble);
+        let solution = table.solve(&c).unwrap();
+        assert!(c.apply_alg(&solution).is_solved());
+        assert_eq!(Ok(solution.len()), tables.distance(&c));
+    }
+}
+
+#[test]
+fn test_bandaged_cube() {
+    assert_eq!(
+        Bandage::parse("UFR-UB"),
+        Err(BandageError::NotAdjacent("UFR".into(), "UB".into()))
+    );
+    assert_eq!(
+        Bandage::parse("UFR-XY"),
+        Err(BandageError::UnknownPiece("XY".into()))
+    );
+
+    // 側面の横 3 つずつを固定すると, 揃った状態では U と D しか回せない
+    let bandage =
+        Bandage::parse("UFL-UF-UFR UBL-UB-UBR DFL-DF-DFR DBL-DB-DBR FL-L-BL FR-R-BR").unwrap();
+    let mut cube = BandagedCube::new(bandage.clone());
+    assert_eq!(
+        cube.legal_moves(),
+        vec![Move::U, Move::U2, Move::Up, Move::D, Move::D2, Move::Dp]
+    );
+    assert!(matches!(
+        cube.apply_move(Move::R),
+        Err(BandageError::Blocked { mv: Move::R, .. })
+    ));
+
+    // U と D を回すと上下の塊が L と R にそろい, L と R も回せる
+    cube.apply_alg(&parse_alg("U D'").unwrap()).unwrap();
+    assert_eq!(cube.legal_moves().len(), 12);
+    assert!(!cube.legal_moves().contains(&Move::F));
+    let before = cube.cube;
+    assert!(cube.apply_alg(&parse_alg("R F").unwrap()).is_err());
+    assert_eq!(cube.cube, before);
+    cube.apply_alg(&parse_alg("R R' D U'").unwrap()).unwrap();
+    assert!(cube.is_solved());
+
+    // 到達できる状態のグラフ
+    let graph = bandage.explore(100_000).unwrap();
+    assert_eq!(graph.len(), 11_520);
+    assert_eq!(graph.diameter(), 13);
+    assert_eq!(&graph.depth_counts[..3], &[1, 6, 9]);
+    assert_eq!(
+        bandage.explore(1_000),
+        Err(BandageError::TooManyStates { limit: 1_000 })
+    );
+}
+
+#[test]
+fn test_void_cube() {
+    let alg = parse_void_alg("R M' L' U M2 E S'").unwrap();
+    assert_eq!(format_void_alg(&alg), "R M' L' U M2 E S'");
+    assert_eq!(parse_void_alg("R X").unwrap_err().index, 1);
+
+    // x = R M' L' は角と辺だけを見ると持ち替えた完成状態
+    let x = apply_void_alg(&Cube::identity(), &alg[..3]);
+    assert!(!x.is_solved());
+    assert!(x.is_solved_in(CenterMode::Void));
+    assert_eq!(
+        x.center_frame().map(|r| r.name().to_string()),
+        Some("x".into())
+    );
+    assert!(!x.is_legal());
+    assert!(x.is_legal_in(CenterMode::Void));
+
+    // スライスは辺の 4 サイクルで, 4 回で戻る
+    let m = apply_void_alg(&Cube::identity(), &parse_void_alg("M").unwrap());
+    assert_eq!(edge_cycles(&m), vec![vec![0, 2, 10, 8]]);
+    let mut c = m;
+    for _ in 0..3 {
+        c.apply_slice(SliceMove::M);
+    }
+    assert!(c.is_solved());
+    let info = m.legality_in(CenterMode::Void);
+    assert_ne!(info.corner_parity, info.edge_parity);
+    assert!(info.is_legal);
+    let mut twisted = m;
+    twisted.corner_ori[0] = 1;
+    assert!(!twisted.is_legal_in(CenterMode::Void));
+
+    let tables = Arc::new(SolverTables::build());
+    assert_eq!(
+        solve_in(&m, CenterMode::Fixed, &tables, 22),
+        Err(cube_core::SolveError::Illegal)
+    );
+    assert_eq!(solve_in(&x, CenterMode::Void, &tables, 22), Ok(vec![]));
+    let mut rng = Rng::new(50);
+    for _ in 0..3 {
+        let mut cube = m;
+        for _ in 0..15 {
+            cube.apply_move(*rng.choose(&Move::ALL));
+        }
+        cube.apply_slice(SliceMove {
+            slice: *rng.choose(&Slice::ALL),
+            turns: 1,
+        });
+        let solution = solve_in(&cube, CenterMode::Void, &tables, 22).unwrap();
+        assert!(apply_void_alg(&cube, &solution).is_solved_in(CenterMode::Void));
+    }
+}